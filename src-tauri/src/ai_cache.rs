@@ -0,0 +1,178 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::ai_provider::AiProviderKind;
+use crate::answer_cache::unix_now;
+use crate::error::{AppError, AppResult};
+use crate::prompt_templates::TemplateRef;
+
+const CACHE_FILE: &str = "ai_cache.db";
+
+// 缓存的回复在 24 小时内有效
+pub const CACHE_TTL_SECS: i64 = 24 * 60 * 60;
+
+// AI 回复的本地缓存，避免对未修改的答案重复发起付费调用
+pub struct AiCache {
+    conn: Mutex<Connection>,
+    ttl: i64,
+}
+
+// 缓存键：接口类型、模型、模板版本和渲染后提示词的哈希
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey {
+    pub provider: AiProviderKind,
+    pub model: String,
+    pub template: TemplateRef,
+    pub prompt_hash: String,
+}
+
+impl CacheKey {
+    pub fn new(provider: AiProviderKind, model: &str, template: TemplateRef, prompt: &str) -> Self {
+        let prompt_hash = Sha256::digest(prompt.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        Self {
+            provider,
+            model: model.to_string(),
+            template,
+            prompt_hash,
+        }
+    }
+}
+
+impl AiCache {
+    pub fn open(data_dir: &Path) -> AppResult<Self> {
+        let conn = Connection::open(data_dir.join(CACHE_FILE))
+            .map_err(|e| AppError::storage(format!("打开 AI 响应缓存失败: {}", e)))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS responses (
+                provider TEXT NOT NULL,
+                model TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                version INTEGER NOT NULL,
+                prompt_hash TEXT NOT NULL,
+                text TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                PRIMARY KEY (provider, model, kind, version, prompt_hash)
+            );",
+        )
+        .map_err(|e| AppError::storage(format!("初始化 AI 响应缓存失败: {}", e)))?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            ttl: CACHE_TTL_SECS,
+        })
+    }
+
+    // 未过期的缓存回复
+    pub fn get(&self, key: &CacheKey) -> AppResult<Option<String>> {
+        self.get_at(key, unix_now())
+    }
+
+    fn get_at(&self, key: &CacheKey, now: i64) -> AppResult<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT text FROM responses
+             WHERE provider = ?1 AND model = ?2 AND kind = ?3 AND version = ?4
+               AND prompt_hash = ?5 AND created_at > ?6",
+            params![
+                key.provider.as_str(),
+                key.model,
+                key.template.kind.as_str(),
+                key.template.version,
+                key.prompt_hash,
+                now - self.ttl
+            ],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| AppError::storage(format!("读取 AI 响应缓存失败: {}", e)))
+    }
+
+    // 保存回复，同时清理已过期的条目
+    pub fn put(&self, key: &CacheKey, text: &str) -> AppResult<()> {
+        self.put_at(key, text, unix_now())
+    }
+
+    fn put_at(&self, key: &CacheKey, text: &str, now: i64) -> AppResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM responses WHERE created_at <= ?1",
+            params![now - self.ttl],
+        )
+        .and_then(|_| {
+            conn.execute(
+                "INSERT OR REPLACE INTO responses
+                     (provider, model, kind, version, prompt_hash, text, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    key.provider.as_str(),
+                    key.model,
+                    key.template.kind.as_str(),
+                    key.template.version,
+                    key.prompt_hash,
+                    text,
+                    now
+                ],
+            )
+        })
+        .map_err(|e| AppError::storage(format!("写入 AI 响应缓存失败: {}", e)))?;
+        Ok(())
+    }
+
+    // 清空缓存，返回删除的条数
+    pub fn clear(&self) -> AppResult<usize> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM responses", [])
+            .map_err(|e| AppError::storage(format!("清空 AI 响应缓存失败: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt_templates::PromptKind;
+
+    fn key(version: i64, prompt: &str) -> CacheKey {
+        let template = TemplateRef {
+            kind: PromptKind::Optimize,
+            version,
+        };
+        CacheKey::new(AiProviderKind::OpenAi, "gpt", template, prompt)
+    }
+
+    #[test]
+    fn cached_replies_expire_and_depend_on_every_key_part() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = AiCache::open(dir.path()).unwrap();
+        let now = 1_700_000_000;
+        cache.put_at(&key(1, "提示"), "回复", now).unwrap();
+
+        assert_eq!(
+            cache.get_at(&key(1, "提示"), now + 60).unwrap().as_deref(),
+            Some("回复")
+        );
+        assert_eq!(cache.get_at(&key(2, "提示"), now).unwrap(), None);
+        assert_eq!(cache.get_at(&key(1, "提示 "), now).unwrap(), None);
+        let mut other_model = key(1, "提示");
+        other_model.model = "gpt-mini".to_string();
+        assert_eq!(cache.get_at(&other_model, now).unwrap(), None);
+        assert_eq!(
+            cache.get_at(&key(1, "提示"), now + CACHE_TTL_SECS).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn clear_removes_all_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = AiCache::open(dir.path()).unwrap();
+        cache.put(&key(1, "a"), "1").unwrap();
+        cache.put(&key(1, "b"), "2").unwrap();
+
+        assert_eq!(cache.clear().unwrap(), 2);
+        assert_eq!(cache.get(&key(1, "a")).unwrap(), None);
+    }
+}
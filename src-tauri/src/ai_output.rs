@@ -0,0 +1,375 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::ai_provider::OutputSchema;
+use crate::error::{AppError, AppResult};
+
+// AI 审核结论
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewVerdict {
+    #[serde(alias = "合理")]
+    Reasonable,
+    #[serde(alias = "基本合理")]
+    MostlyReasonable,
+    #[serde(alias = "需修改")]
+    NeedsRevision,
+}
+
+impl ReviewVerdict {
+    // 从"= 需修改"之类的文本中识别结论，"基本合理"和"需修改"优先于"合理"
+    fn from_text(text: &str) -> Option<Self> {
+        if text.contains("需修改") {
+            Some(Self::NeedsRevision)
+        } else if text.contains("基本合理") {
+            Some(Self::MostlyReasonable)
+        } else if text.contains("合理") {
+            Some(Self::Reasonable)
+        } else {
+            None
+        }
+    }
+}
+
+// AI 审核的内容，结构化输出时模型直接按此结构返回
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewReport {
+    pub verdict: ReviewVerdict,
+    #[serde(default)]
+    pub explanation: String, // 专业判断说明
+    #[serde(default)]
+    pub risks: Vec<String>, // 潜在风险或注意点
+    #[serde(default)]
+    pub suggestion: Option<String>, // 修改建议
+    #[serde(default)]
+    pub revision_reason: Option<String>, // 需修改原因
+    #[serde(default)]
+    pub suggested_reply: Option<String>, // 修改后推荐回复
+    #[serde(default)]
+    pub principles: Vec<String>, // 修改依据（专家原则）
+}
+
+// 风险检测的内容
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RiskReport {
+    pub has_risk: bool,
+    #[serde(default)]
+    pub reason: String, // 一句话原因
+}
+
+// AI 优化的内容
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OptimizationReport {
+    pub final_reply: String, // 最终客服回复
+    #[serde(default)]
+    pub explanation: String, // 内部优化说明
+    #[serde(default)]
+    pub corrections: Vec<String>, // 纠正原回复的原因，未纠正时为空
+}
+
+// 严格模式的 schema 要求列出所有字段，可选字段用 null 表示
+pub fn review_schema() -> OutputSchema {
+    let optional_text =
+        |description: &str| json!({ "type": ["string", "null"], "description": description });
+    let list = |description: &str| json!({ "type": "array", "items": { "type": "string" }, "description": description });
+    OutputSchema {
+        name: "review_report",
+        description: "客服回复的审核结果",
+        schema: json!({
+            "type": "object",
+            "properties": {
+                "verdict": {
+                    "type": "string",
+                    "enum": ["reasonable", "mostly_reasonable", "needs_revision"],
+                    "description": "审核结论：reasonable=合理，mostly_reasonable=基本合理，needs_revision=需修改",
+                },
+                "explanation": { "type": "string", "description": "专业判断说明" },
+                "risks": list("潜在风险或注意点，每条一项"),
+                "suggestion": optional_text("修改建议，合理时为 null"),
+                "revision_reason": optional_text("需修改原因，仅在需修改时提供"),
+                "suggested_reply": optional_text("修改后推荐回复，仅在需修改时提供"),
+                "principles": list("修改依据（专家原则），每条一项"),
+            },
+            "required": [
+                "verdict", "explanation", "risks", "suggestion",
+                "revision_reason", "suggested_reply", "principles",
+            ],
+            "additionalProperties": false,
+        }),
+    }
+}
+
+pub fn risk_schema() -> OutputSchema {
+    OutputSchema {
+        name: "risk_report",
+        description: "客服回复的风险检测结果",
+        schema: json!({
+            "type": "object",
+            "properties": {
+                "has_risk": { "type": "boolean", "description": "是否存在误导、错误信息、不当表述等风险" },
+                "reason": { "type": "string", "description": "一句话原因说明" },
+            },
+            "required": ["has_risk", "reason"],
+            "additionalProperties": false,
+        }),
+    }
+}
+
+pub fn optimization_schema() -> OutputSchema {
+    OutputSchema {
+        name: "optimization_report",
+        description: "客服回复的优化结果",
+        schema: json!({
+            "type": "object",
+            "properties": {
+                "final_reply": { "type": "string", "description": "优化后的最终客服回复" },
+                "explanation": { "type": "string", "description": "内部优化说明，包括优化点和改进原因" },
+                "corrections": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "纠正原回复的原因，每条一项，未纠正时为空数组",
+                },
+            },
+            "required": ["final_reply", "explanation", "corrections"],
+            "additionalProperties": false,
+        }),
+    }
+}
+
+// 解析审核结果，返回 (结果, 是否为 JSON)。接口不支持结构化输出或模板仍要求
+// 【审核结论】等旧格式时，按段落标题解析文本
+pub fn parse_review(text: &str) -> AppResult<(ReviewReport, bool)> {
+    if let Some(report) = parse_embedded_json::<ReviewReport>(text) {
+        return Ok((report, true));
+    }
+
+    let sections = sections(text);
+    let section = |names: &[&str]| {
+        sections
+            .iter()
+            .find(|(title, _)| names.iter().any(|name| title.starts_with(name)))
+            .map(|(_, body)| body.trim().trim_start_matches('=').trim().to_string())
+            .filter(|body| !body.is_empty())
+    };
+
+    let verdict = section(&["审核结论", "专业审核结论"])
+        .and_then(|text| ReviewVerdict::from_text(&text))
+        .ok_or_else(|| AppError::parse("无法从 AI 回复中解析审核结论"))?;
+    Ok((
+        ReviewReport {
+            verdict,
+            explanation: section(&["专业判断说明"]).unwrap_or_default(),
+            risks: list_items(section(&["潜在风险或注意点", "问题点说明"])),
+            suggestion: section(&["修改建议"]),
+            revision_reason: section(&["需修改原因"]),
+            suggested_reply: section(&["修改后推荐回复"]),
+            principles: list_items(section(&["修改依据"])),
+        },
+        false,
+    ))
+}
+
+// 解析风险检测结果，返回 (结果, 是否为 JSON)。兼容 "RISK = YES / REASON = ..." 的旧格式
+pub fn parse_risk(text: &str) -> AppResult<(RiskReport, bool)> {
+    if let Some(report) = parse_embedded_json::<RiskReport>(text) {
+        return Ok((report, true));
+    }
+
+    let mut has_risk = None;
+    let mut reason = String::new();
+    for line in text.lines() {
+        let line = line.trim().trim_start_matches(['*', '-', ' ']);
+        let Some((key, value)) = line.split_once(['=', ':', '：']) else {
+            continue;
+        };
+        let value = value.trim().trim_matches('*').trim();
+        match key.trim().trim_matches('*').to_ascii_uppercase().as_str() {
+            "RISK" => {
+                let value = value.to_ascii_uppercase();
+                has_risk = if value.starts_with("YES") || value.starts_with('是') {
+                    Some(true)
+                } else if value.starts_with("NO") || value.starts_with('否') {
+                    Some(false)
+                } else {
+                    has_risk
+                };
+            }
+            "REASON" => reason = value.to_string(),
+            _ => {}
+        }
+    }
+
+    let has_risk = has_risk.ok_or_else(|| AppError::parse("无法从 AI 回复中解析风险结论"))?;
+    Ok((RiskReport { has_risk, reason }, false))
+}
+
+// 解析优化结果，返回 (结果, 是否为 JSON)。兼容【最终客服回复】/【内部优化说明】的旧格式，
+// 没有段落标题时整段文本即为回复
+pub fn parse_optimization(text: &str) -> AppResult<(OptimizationReport, bool)> {
+    if let Some(report) = parse_embedded_json::<OptimizationReport>(text) {
+        return Ok((report, true));
+    }
+
+    let sections = sections(text);
+    let section = |name: &str| {
+        sections
+            .iter()
+            .find(|(title, _)| title.starts_with(name))
+            .map(|(_, body)| body.trim().to_string())
+    };
+
+    let final_reply = section("最终客服回复").unwrap_or_else(|| text.trim().to_string());
+    if final_reply.is_empty() {
+        return Err(AppError::parse("AI 未返回优化后的回复"));
+    }
+    let explanation = section("内部优化说明").unwrap_or_default();
+    let corrections = explanation
+        .lines()
+        .filter_map(|line| line.split_once("纠正原因"))
+        .map(|(_, reason)| {
+            reason
+                .trim_start_matches([':', '：', ' '])
+                .trim()
+                .to_string()
+        })
+        .filter(|reason| !reason.is_empty())
+        .collect();
+    Ok((
+        OptimizationReport {
+            final_reply,
+            explanation,
+            corrections,
+        },
+        false,
+    ))
+}
+
+// 取出回复中的 JSON 对象，容忍 ```json 代码块和前后的说明文字
+fn parse_embedded_json<T: DeserializeOwned>(text: &str) -> Option<T> {
+    let start = text.find('{')?;
+    let end = text.rfind('}')?;
+    if end < start {
+        return None;
+    }
+    serde_json::from_str(&text[start..=end]).ok()
+}
+
+// 按行首的【标题】切分段落，标题同一行的剩余内容计入正文（模型照抄的"（仅在…时提供）"除外）
+fn sections(text: &str) -> Vec<(String, String)> {
+    let mut sections: Vec<(String, String)> = Vec::new();
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        let header = trimmed
+            .strip_prefix('【')
+            .and_then(|rest| rest.split_once('】'));
+        match (header, sections.last_mut()) {
+            (Some((title, rest)), _) => {
+                let rest = rest.trim();
+                let note = rest.starts_with('（') && rest.ends_with('）');
+                let rest = if note { "" } else { rest };
+                sections.push((title.trim().to_string(), rest.to_string()));
+            }
+            (None, Some((_, body))) => {
+                body.push('\n');
+                body.push_str(line);
+            }
+            (None, None) => {}
+        }
+    }
+    sections
+}
+
+// 把多行文本拆成列表项，去掉"- "、"1. "之类的前缀
+fn list_items(text: Option<String>) -> Vec<String> {
+    text.unwrap_or_default()
+        .lines()
+        .map(|line| {
+            let line = line.trim().trim_start_matches(['-', '*', '•', '·']);
+            let without_number = line.trim_start_matches(|c: char| c.is_ascii_digit());
+            let line = match without_number.strip_prefix(['.', '、', ')', '）']) {
+                Some(rest) if without_number.len() < line.len() => rest,
+                _ => line,
+            };
+            line.trim().to_string()
+        })
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn review_json_in_code_fence() {
+        let text = "结果如下：\n```json\n{\"verdict\":\"needs_revision\",\"explanation\":\"不严谨\",\"risks\":[\"夸大功效\"],\"suggestion\":null,\"revision_reason\":\"夸大\",\"suggested_reply\":\"新回复\",\"principles\":[]}\n```";
+
+        let (report, structured) = parse_review(text).unwrap();
+
+        assert!(structured);
+        assert_eq!(report.verdict, ReviewVerdict::NeedsRevision);
+        assert_eq!(report.risks, vec!["夸大功效"]);
+        assert_eq!(report.suggested_reply.as_deref(), Some("新回复"));
+    }
+
+    #[test]
+    fn review_falls_back_to_section_headers() {
+        let text = "【审核结论】= 基本合理\n\n【专业判断说明】\n整体准确\n\n【潜在风险或注意点】\n1. 未提及过敏人群\n- 语气略生硬\n\n【修改建议】（可选）\n补充过敏提示\n\n【修改依据（专家原则）】\n食品安全优先";
+
+        let (report, structured) = parse_review(text).unwrap();
+
+        assert!(!structured);
+        assert_eq!(report.verdict, ReviewVerdict::MostlyReasonable);
+        assert_eq!(report.explanation, "整体准确");
+        assert_eq!(report.risks, vec!["未提及过敏人群", "语气略生硬"]);
+        assert_eq!(report.suggestion.as_deref(), Some("补充过敏提示"));
+        assert_eq!(report.revision_reason, None);
+        assert_eq!(report.principles, vec!["食品安全优先"]);
+    }
+
+    #[test]
+    fn review_without_verdict_is_an_error() {
+        assert!(parse_review("看起来还行").is_err());
+    }
+
+    #[test]
+    fn optimization_accepts_json_and_legacy_sections() {
+        let (json_report, structured) =
+            parse_optimization(r#"{"final_reply":"您好","explanation":"更礼貌","corrections":[]}"#)
+                .unwrap();
+        let (legacy, legacy_structured) = parse_optimization(
+            "【最终客服回复】\n您好，建议饭后服用。\n\n【内部优化说明】\n语气更友好\n纠正原因：原回复建议空腹服用",
+        )
+        .unwrap();
+
+        assert!(structured);
+        assert_eq!(json_report.final_reply, "您好");
+        assert!(!legacy_structured);
+        assert_eq!(legacy.final_reply, "您好，建议饭后服用。");
+        assert_eq!(legacy.corrections, vec!["原回复建议空腹服用"]);
+        // 没有段落标题时整段即为回复
+        assert_eq!(parse_optimization(" 您好 ").unwrap().0.final_reply, "您好");
+        assert!(parse_optimization("  ").is_err());
+    }
+
+    #[test]
+    fn risk_accepts_json_and_legacy_lines() {
+        let (json_report, structured) =
+            parse_risk(r#"{"has_risk": true, "reason": "夸大功效"}"#).unwrap();
+        let (legacy, legacy_structured) = parse_risk("**RISK** = NO\nREASON：表述准确").unwrap();
+
+        assert!(structured && json_report.has_risk);
+        assert!(!legacy_structured);
+        assert_eq!(
+            legacy,
+            RiskReport {
+                has_risk: false,
+                reason: "表述准确".to_string()
+            }
+        );
+        // 旧实现把 "NO (YES if ...)" 之类的行误判为有风险
+        assert!(!parse_risk("RISK = NO (not YES)").unwrap().0.has_risk);
+        assert!(parse_risk("无法判断").is_err());
+    }
+}
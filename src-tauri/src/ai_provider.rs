@@ -0,0 +1,868 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::commands::AiConfig;
+use crate::error::{AppError, AppResult};
+
+const TEMPERATURE: f64 = 0.7;
+const MAX_TOKENS: u32 = 2000;
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+pub const DEFAULT_AZURE_API_VERSION: &str = "2024-06-01";
+
+// AI 接口类型，对应 AiConfig.provider
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AiProviderKind {
+    #[default]
+    #[serde(rename = "openai")]
+    OpenAi, // OpenAI 兼容的 chat/completions（火山方舟等）
+    Anthropic, // Anthropic Messages API
+    Azure,     // Azure OpenAI，model 填部署名
+    Ollama,    // 本地 Ollama 服务（/api/chat），不需要 API Key
+}
+
+impl AiProviderKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AiProviderKind::OpenAi => "openai",
+            AiProviderKind::Anthropic => "anthropic",
+            AiProviderKind::Azure => "azure",
+            AiProviderKind::Ollama => "ollama",
+        }
+    }
+}
+
+// 流式响应的分帧方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFormat {
+    Sse,    // text/event-stream，逐条 data 字段
+    NdJson, // application/x-ndjson，每行一个 JSON
+}
+
+impl StreamFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            StreamFormat::Sse => "text/event-stream",
+            StreamFormat::NdJson => "application/x-ndjson",
+        }
+    }
+}
+
+// 一次调用消耗的 token 数，接口未返回用量时为 0
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+impl std::ops::Add for TokenUsage {
+    type Output = TokenUsage;
+
+    fn add(self, other: TokenUsage) -> TokenUsage {
+        TokenUsage {
+            prompt_tokens: self.prompt_tokens + other.prompt_tokens,
+            completion_tokens: self.completion_tokens + other.completion_tokens,
+        }
+    }
+}
+
+impl std::ops::AddAssign for TokenUsage {
+    fn add_assign(&mut self, other: TokenUsage) {
+        *self = *self + other;
+    }
+}
+
+// 一次对话的回复文本和用量
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Completion {
+    pub text: String,
+    pub usage: TokenUsage,
+    pub cached: bool,          // 来自本地缓存，未调用接口，usage 为 0
+    pub model: Option<String>, // 响应中实际提供服务的模型（如带日期的版本），接口未返回时为 None
+}
+
+// 一条流式数据的解析结果
+#[derive(Debug, PartialEq, Eq)]
+pub enum StreamEvent {
+    Delta(String),     // 新增文本，可能为空（如心跳、元数据事件）
+    Usage(TokenUsage), // 用量（可能分多次返回，累加）
+    Done(Option<TokenUsage>),
+}
+
+// 要求模型按 JSON Schema 输出的结构化结果
+#[derive(Debug, Clone)]
+pub struct OutputSchema {
+    pub name: &'static str, // schema / 工具名称
+    pub description: &'static str,
+    pub schema: serde_json::Value,
+}
+
+// 一次对话请求的选项
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChatOptions<'a> {
+    pub stream: bool,
+    pub schema: Option<&'a OutputSchema>, // 要求结构化输出，回复文本为符合 schema 的 JSON
+}
+
+// 不同厂商的对话接口：负责构造请求和解析响应，发送与重试由调用方处理
+pub trait AiProvider: Send + Sync {
+    // 构造一次单轮对话请求
+    fn request(
+        &self,
+        http: &reqwest::Client,
+        prompt: &str,
+        options: ChatOptions,
+    ) -> reqwest::RequestBuilder;
+
+    // 解析非流式响应，返回回复文本（结构化输出时为 JSON 文本）和用量
+    fn parse_response(&self, body: &str) -> AppResult<Completion>;
+
+    fn stream_format(&self) -> StreamFormat {
+        StreamFormat::Sse
+    }
+
+    // 解析一条流式数据（SSE 的 data 字段或 NDJSON 的一行）
+    fn parse_stream_data(&self, data: &str) -> AppResult<StreamEvent>;
+
+    // 流式数据中实际提供服务的模型，该条数据不带模型时返回 None
+    fn stream_model(&self, data: &str) -> Option<String> {
+        let value: serde_json::Value = serde_json::from_str(data).ok()?;
+        value["model"].as_str().map(str::to_string)
+    }
+}
+
+pub fn provider(config: AiConfig) -> Box<dyn AiProvider> {
+    match config.provider {
+        AiProviderKind::OpenAi => Box::new(OpenAi(config)),
+        AiProviderKind::Anthropic => Box::new(Anthropic(config)),
+        AiProviderKind::Azure => Box::new(AzureOpenAi(config)),
+        AiProviderKind::Ollama => Box::new(Ollama(config)),
+    }
+}
+
+fn api_base(config: &AiConfig) -> &str {
+    config.api_base.trim_end_matches('/')
+}
+
+fn parse_json<T: DeserializeOwned>(text: &str, what: &str) -> AppResult<T> {
+    serde_json::from_str(text).map_err(|e| AppError::parse(format!("解析{}失败: {}", what, e)))
+}
+
+// OpenAI 兼容接口（Azure 共用请求体和响应格式）
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ChatMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<ChatMessage>,
+    temperature: Option<f64>,
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    model: Option<String>,
+    choices: Option<Vec<ChatChoice>>,
+    usage: Option<ChatUsage>,
+    error: Option<ChatError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+}
+
+impl From<ChatUsage> for TokenUsage {
+    fn from(usage: ChatUsage) -> Self {
+        TokenUsage {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoice {
+    message: Option<ChatMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChunk {
+    #[serde(default)]
+    choices: Vec<ChatChunkChoice>,
+    usage: Option<ChatUsage>, // 请求 include_usage 时在 [DONE] 前单独返回一条
+    error: Option<ChatError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChunkChoice {
+    delta: Option<ChatDelta>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatDelta {
+    content: Option<String>,
+}
+
+fn user_message(prompt: &str) -> Vec<ChatMessage> {
+    vec![ChatMessage {
+        role: "user".to_string(),
+        content: prompt.to_string(),
+    }]
+}
+
+// include_usage：流式输出时要求返回用量（Azure 旧版 api-version 不支持该参数）
+fn chat_request<'a>(
+    model: &'a str,
+    prompt: &str,
+    options: ChatOptions,
+    include_usage: bool,
+) -> ChatRequest<'a> {
+    ChatRequest {
+        model,
+        messages: user_message(prompt),
+        temperature: Some(TEMPERATURE),
+        max_tokens: Some(MAX_TOKENS),
+        stream: options.stream,
+        response_format: options.schema.map(|schema| {
+            json!({
+                "type": "json_schema",
+                "json_schema": {
+                    "name": schema.name,
+                    "description": schema.description,
+                    "schema": schema.schema,
+                    "strict": true,
+                },
+            })
+        }),
+        stream_options: (options.stream && include_usage).then(|| json!({ "include_usage": true })),
+    }
+}
+
+fn parse_chat_response(body: &str) -> AppResult<Completion> {
+    let response: ChatResponse = parse_json(body, "响应")?;
+    if let Some(error) = response.error {
+        return Err(AppError::ai(format!("API 错误: {}", error.message)));
+    }
+    let text = response
+        .choices
+        .and_then(|choices| choices.into_iter().next())
+        .and_then(|choice| choice.message)
+        .map(|message| message.content)
+        .ok_or_else(|| AppError::parse("API 响应格式错误"))?;
+    Ok(Completion {
+        text,
+        usage: response.usage.map(TokenUsage::from).unwrap_or_default(),
+        cached: false,
+        model: response.model,
+    })
+}
+
+fn parse_chat_chunk(data: &str) -> AppResult<StreamEvent> {
+    if data == "[DONE]" {
+        return Ok(StreamEvent::Done(None));
+    }
+    let chunk: ChatChunk = parse_json(data, "流式响应")?;
+    if let Some(error) = chunk.error {
+        return Err(AppError::ai(format!("API 错误: {}", error.message)));
+    }
+    if let Some(usage) = chunk.usage {
+        return Ok(StreamEvent::Usage(usage.into()));
+    }
+    Ok(StreamEvent::Delta(
+        chunk
+            .choices
+            .into_iter()
+            .filter_map(|choice| choice.delta.and_then(|d| d.content))
+            .collect(),
+    ))
+}
+
+struct OpenAi(AiConfig);
+
+impl AiProvider for OpenAi {
+    fn request(
+        &self,
+        http: &reqwest::Client,
+        prompt: &str,
+        options: ChatOptions,
+    ) -> reqwest::RequestBuilder {
+        http.post(format!("{}/chat/completions", api_base(&self.0)))
+            .bearer_auth(&self.0.api_key)
+            .json(&chat_request(&self.0.model, prompt, options, true))
+    }
+
+    fn parse_response(&self, body: &str) -> AppResult<Completion> {
+        parse_chat_response(body)
+    }
+
+    fn parse_stream_data(&self, data: &str) -> AppResult<StreamEvent> {
+        parse_chat_chunk(data)
+    }
+}
+
+// Azure OpenAI：部署名在路径中，api-version 为查询参数，使用 api-key 请求头
+struct AzureOpenAi(AiConfig);
+
+impl AiProvider for AzureOpenAi {
+    fn request(
+        &self,
+        http: &reqwest::Client,
+        prompt: &str,
+        options: ChatOptions,
+    ) -> reqwest::RequestBuilder {
+        let api_version = self
+            .0
+            .api_version
+            .as_deref()
+            .filter(|v| !v.trim().is_empty())
+            .unwrap_or(DEFAULT_AZURE_API_VERSION);
+        http.post(format!(
+            "{}/openai/deployments/{}/chat/completions",
+            api_base(&self.0),
+            self.0.model
+        ))
+        .query(&[("api-version", api_version)])
+        .header("api-key", &self.0.api_key)
+        .json(&chat_request(&self.0.model, prompt, options, false))
+    }
+
+    fn parse_response(&self, body: &str) -> AppResult<Completion> {
+        parse_chat_response(body)
+    }
+
+    fn parse_stream_data(&self, data: &str) -> AppResult<StreamEvent> {
+        parse_chat_chunk(data)
+    }
+}
+
+// Anthropic Messages API
+
+#[derive(Debug, Deserialize)]
+struct AnthropicResponse {
+    model: Option<String>,
+    #[serde(default)]
+    content: Vec<AnthropicContent>,
+    usage: Option<AnthropicUsage>,
+    error: Option<AnthropicError>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AnthropicUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+}
+
+impl From<AnthropicUsage> for TokenUsage {
+    fn from(usage: AnthropicUsage) -> Self {
+        TokenUsage {
+            prompt_tokens: usage.input_tokens,
+            completion_tokens: usage.output_tokens,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicContent {
+    #[serde(rename = "type")]
+    kind: String,
+    text: Option<String>,
+    input: Option<serde_json::Value>, // tool_use 块的参数，即结构化输出
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicError {
+    message: String,
+}
+
+// 流式事件：content_block_delta 携带文本（或工具参数的 JSON 片段），message_stop 表示结束。
+// message_start 的 usage 为输入 token 数，message_delta 的 usage 为累计输出 token 数
+#[derive(Debug, Deserialize)]
+struct AnthropicEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    delta: Option<AnthropicDelta>,
+    message: Option<AnthropicStartMessage>,
+    usage: Option<AnthropicUsage>,
+    error: Option<AnthropicError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicStartMessage {
+    model: Option<String>,
+    usage: Option<AnthropicUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicDelta {
+    text: Option<String>,
+    partial_json: Option<String>,
+}
+
+struct Anthropic(AiConfig);
+
+impl AiProvider for Anthropic {
+    fn request(
+        &self,
+        http: &reqwest::Client,
+        prompt: &str,
+        options: ChatOptions,
+    ) -> reqwest::RequestBuilder {
+        let mut body = json!({
+            "model": self.0.model,
+            "max_tokens": MAX_TOKENS,
+            "temperature": TEMPERATURE,
+            "messages": user_message(prompt),
+        });
+        if options.stream {
+            body["stream"] = json!(true);
+        }
+        // 结构化输出通过强制调用唯一的工具实现，工具参数即结果
+        if let Some(schema) = options.schema {
+            body["tools"] = json!([{
+                "name": schema.name,
+                "description": schema.description,
+                "input_schema": schema.schema,
+            }]);
+            body["tool_choice"] = json!({ "type": "tool", "name": schema.name });
+        }
+        http.post(format!("{}/messages", api_base(&self.0)))
+            .header("x-api-key", &self.0.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(&body)
+    }
+
+    fn parse_response(&self, body: &str) -> AppResult<Completion> {
+        let response: AnthropicResponse = parse_json(body, "响应")?;
+        if let Some(error) = response.error {
+            return Err(AppError::ai(format!("API 错误: {}", error.message)));
+        }
+        let usage = response.usage.map(TokenUsage::from).unwrap_or_default();
+        if let Some(input) = response
+            .content
+            .iter()
+            .find(|block| block.kind == "tool_use")
+            .and_then(|block| block.input.as_ref())
+        {
+            return Ok(Completion {
+                text: input.to_string(),
+                usage,
+                cached: false,
+                model: response.model,
+            });
+        }
+        let texts: Vec<String> = response
+            .content
+            .into_iter()
+            .filter(|block| block.kind == "text")
+            .filter_map(|block| block.text)
+            .collect();
+        if texts.is_empty() {
+            return Err(AppError::parse("API 响应格式错误"));
+        }
+        Ok(Completion {
+            text: texts.concat(),
+            usage,
+            cached: false,
+            model: response.model,
+        })
+    }
+
+    fn parse_stream_data(&self, data: &str) -> AppResult<StreamEvent> {
+        let event: AnthropicEvent = parse_json(data, "流式响应")?;
+        match event.kind.as_str() {
+            "content_block_delta" => Ok(StreamEvent::Delta(
+                event
+                    .delta
+                    .and_then(|d| d.text.or(d.partial_json))
+                    .unwrap_or_default(),
+            )),
+            "message_start" => Ok(StreamEvent::Usage(TokenUsage {
+                completion_tokens: 0,
+                ..event
+                    .message
+                    .and_then(|m| m.usage)
+                    .map(TokenUsage::from)
+                    .unwrap_or_default()
+            })),
+            "message_delta" => Ok(StreamEvent::Usage(TokenUsage {
+                prompt_tokens: 0,
+                ..event.usage.map(TokenUsage::from).unwrap_or_default()
+            })),
+            "message_stop" => Ok(StreamEvent::Done(None)),
+            "error" => Err(AppError::ai(format!(
+                "API 错误: {}",
+                event.error.map(|e| e.message).unwrap_or_default()
+            ))),
+            _ => Ok(StreamEvent::Delta(String::new())),
+        }
+    }
+
+    // 只有 message_start 事件带模型
+    fn stream_model(&self, data: &str) -> Option<String> {
+        let event: AnthropicEvent = serde_json::from_str(data).ok()?;
+        event.message?.model
+    }
+}
+
+// Ollama 本地服务（/api/chat），流式输出为 NDJSON
+
+#[derive(Debug, Deserialize)]
+struct OllamaResponse {
+    model: Option<String>,
+    message: Option<ChatDelta>,
+    #[serde(default)]
+    done: bool,
+    // 仅在最后一条（done 为 true）中返回
+    prompt_eval_count: Option<u64>,
+    eval_count: Option<u64>,
+    error: Option<String>,
+}
+
+impl OllamaResponse {
+    fn parse(data: &str, what: &str) -> AppResult<Self> {
+        let response: Self = parse_json(data, what)?;
+        match response.error {
+            Some(error) => Err(AppError::ai(format!("API 错误: {}", error))),
+            None => Ok(response),
+        }
+    }
+
+    fn usage(&self) -> TokenUsage {
+        TokenUsage {
+            prompt_tokens: self.prompt_eval_count.unwrap_or(0),
+            completion_tokens: self.eval_count.unwrap_or(0),
+        }
+    }
+}
+
+struct Ollama(AiConfig);
+
+impl AiProvider for Ollama {
+    fn request(
+        &self,
+        http: &reqwest::Client,
+        prompt: &str,
+        options: ChatOptions,
+    ) -> reqwest::RequestBuilder {
+        // Ollama 默认流式输出，stream 必须显式传入
+        let mut body = json!({
+            "model": self.0.model,
+            "messages": user_message(prompt),
+            "stream": options.stream,
+            "options": { "temperature": TEMPERATURE, "num_predict": MAX_TOKENS },
+        });
+        if let Some(schema) = options.schema {
+            body["format"] = schema.schema.clone();
+        }
+        let request = http
+            .post(format!("{}/api/chat", api_base(&self.0)))
+            .json(&body);
+        // 经反向代理访问时可能需要鉴权
+        if self.0.api_key.is_empty() {
+            request
+        } else {
+            request.bearer_auth(&self.0.api_key)
+        }
+    }
+
+    fn parse_response(&self, body: &str) -> AppResult<Completion> {
+        let response = OllamaResponse::parse(body, "响应")?;
+        let usage = response.usage();
+        let text = response
+            .message
+            .and_then(|m| m.content)
+            .ok_or_else(|| AppError::parse("API 响应格式错误"))?;
+        Ok(Completion {
+            text,
+            usage,
+            cached: false,
+            model: response.model,
+        })
+    }
+
+    fn stream_format(&self) -> StreamFormat {
+        StreamFormat::NdJson
+    }
+
+    fn parse_stream_data(&self, data: &str) -> AppResult<StreamEvent> {
+        let response = OllamaResponse::parse(data, "流式响应")?;
+        if response.done {
+            return Ok(StreamEvent::Done(Some(response.usage())));
+        }
+        Ok(StreamEvent::Delta(
+            response.message.and_then(|m| m.content).unwrap_or_default(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use serde_json::Value;
+
+    fn config(provider: AiProviderKind, api_base: &str) -> AiConfig {
+        AiConfig {
+            provider,
+            api_key: "key".to_string(),
+            api_base: api_base.to_string(),
+            model: "model-1".to_string(),
+            api_version: None,
+        }
+    }
+
+    fn build(provider: &dyn AiProvider, options: ChatOptions) -> (reqwest::Request, Value) {
+        let request = provider
+            .request(&reqwest::Client::new(), "你好", options)
+            .build()
+            .unwrap();
+        let body = serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap();
+        (request, body)
+    }
+
+    #[test]
+    fn azure_uses_deployment_path_and_api_key_header() {
+        let provider = provider(config(
+            AiProviderKind::Azure,
+            "https://res.openai.azure.com/",
+        ));
+
+        let (request, body) = build(provider.as_ref(), ChatOptions::default());
+
+        assert_eq!(
+            request.url().as_str(),
+            "https://res.openai.azure.com/openai/deployments/model-1/chat/completions?api-version=2024-06-01"
+        );
+        assert_eq!(request.headers()["api-key"], "key");
+        assert!(request.headers().get("authorization").is_none());
+        assert_eq!(body["messages"][0]["content"], "你好");
+    }
+
+    fn schema() -> OutputSchema {
+        OutputSchema {
+            name: "report",
+            description: "结果",
+            schema: json!({ "type": "object", "properties": { "ok": { "type": "boolean" } } }),
+        }
+    }
+
+    #[test]
+    fn openai_requests_json_schema_output() {
+        let provider = provider(config(AiProviderKind::OpenAi, "https://example.com/v1"));
+        let schema = schema();
+        let options = ChatOptions {
+            stream: false,
+            schema: Some(&schema),
+        };
+
+        let (_, body) = build(provider.as_ref(), options);
+
+        assert_eq!(body["response_format"]["type"], "json_schema");
+        assert_eq!(body["response_format"]["json_schema"]["name"], "report");
+        assert_eq!(body["response_format"]["json_schema"]["strict"], true);
+    }
+
+    #[test]
+    fn openai_stream_requests_usage_except_azure() {
+        let options = ChatOptions {
+            stream: true,
+            schema: None,
+        };
+        let openai = provider(config(AiProviderKind::OpenAi, "https://example.com/v1"));
+        let azure = provider(config(
+            AiProviderKind::Azure,
+            "https://res.openai.azure.com",
+        ));
+
+        let (_, openai_body) = build(openai.as_ref(), options);
+        let (_, azure_body) = build(azure.as_ref(), options);
+        let usage = openai
+            .parse_stream_data(r#"{"choices":[],"usage":{"prompt_tokens":30,"completion_tokens":8,"total_tokens":38}}"#)
+            .unwrap();
+
+        assert_eq!(openai_body["stream_options"]["include_usage"], true);
+        assert!(azure_body.get("stream_options").is_none());
+        assert_eq!(
+            usage,
+            StreamEvent::Usage(TokenUsage {
+                prompt_tokens: 30,
+                completion_tokens: 8
+            })
+        );
+    }
+
+    #[test]
+    fn anthropic_request_has_version_header() {
+        let provider = provider(config(
+            AiProviderKind::Anthropic,
+            "https://api.anthropic.com/v1",
+        ));
+        let schema = schema();
+        let options = ChatOptions {
+            stream: true,
+            schema: Some(&schema),
+        };
+
+        let (request, body) = build(provider.as_ref(), options);
+
+        assert_eq!(
+            request.url().as_str(),
+            "https://api.anthropic.com/v1/messages"
+        );
+        assert_eq!(request.headers()["x-api-key"], "key");
+        assert_eq!(request.headers()["anthropic-version"], ANTHROPIC_VERSION);
+        assert_eq!(body["max_tokens"], MAX_TOKENS);
+        assert_eq!(body["stream"], true);
+        assert_eq!(body["tools"][0]["input_schema"], schema.schema);
+        assert_eq!(body["tool_choice"]["name"], "report");
+    }
+
+    #[test]
+    fn anthropic_tool_use_is_returned_as_json() {
+        let provider = provider(config(AiProviderKind::Anthropic, ""));
+
+        let completion = provider
+            .parse_response(
+                r#"{"content":[{"type":"tool_use","id":"t1","name":"report","input":{"ok":true}}],"usage":{"input_tokens":12,"output_tokens":5}}"#,
+            )
+            .unwrap();
+
+        assert_eq!(completion.text, r#"{"ok":true}"#);
+        assert_eq!(
+            completion.usage,
+            TokenUsage {
+                prompt_tokens: 12,
+                completion_tokens: 5
+            }
+        );
+    }
+
+    #[test]
+    fn ollama_sends_stream_flag_without_key() {
+        let mut config = config(AiProviderKind::Ollama, "http://localhost:11434");
+        config.api_key = String::new();
+        let provider = provider(config);
+
+        let (request, body) = build(provider.as_ref(), ChatOptions::default());
+
+        assert_eq!(request.url().as_str(), "http://localhost:11434/api/chat");
+        assert!(request.headers().get("authorization").is_none());
+        assert_eq!(body["stream"], false);
+    }
+
+    #[test]
+    fn anthropic_stream_events() {
+        let provider = provider(config(AiProviderKind::Anthropic, ""));
+
+        let delta = provider
+            .parse_stream_data(r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"你好"}}"#)
+            .unwrap();
+        let ping = provider.parse_stream_data(r#"{"type":"ping"}"#).unwrap();
+        let start = provider
+            .parse_stream_data(r#"{"type":"message_start","message":{"usage":{"input_tokens":20,"output_tokens":1}}}"#)
+            .unwrap();
+        let message_delta = provider
+            .parse_stream_data(r#"{"type":"message_delta","delta":{"stop_reason":"end_turn"},"usage":{"output_tokens":15}}"#)
+            .unwrap();
+        let stop = provider
+            .parse_stream_data(r#"{"type":"message_stop"}"#)
+            .unwrap();
+        let err = provider
+            .parse_stream_data(
+                r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#,
+            )
+            .unwrap_err();
+
+        assert_eq!(delta, StreamEvent::Delta("你好".to_string()));
+        assert_eq!(ping, StreamEvent::Delta(String::new()));
+        assert_eq!(
+            start,
+            StreamEvent::Usage(TokenUsage {
+                prompt_tokens: 20,
+                completion_tokens: 0
+            })
+        );
+        assert_eq!(
+            message_delta,
+            StreamEvent::Usage(TokenUsage {
+                prompt_tokens: 0,
+                completion_tokens: 15
+            })
+        );
+        assert_eq!(stop, StreamEvent::Done(None));
+        assert_eq!(err.kind, ErrorKind::Ai);
+    }
+
+    #[test]
+    fn served_model_is_read_from_responses() {
+        let openai = provider(config(AiProviderKind::Azure, ""));
+        let completion = openai
+            .parse_response(
+                r#"{"model":"gpt-4o-2024-08-06","choices":[{"message":{"role":"assistant","content":"好"}}]}"#,
+            )
+            .unwrap();
+        assert_eq!(completion.model.as_deref(), Some("gpt-4o-2024-08-06"));
+        assert_eq!(
+            openai.stream_model(r#"{"model":"gpt-4o-2024-08-06","choices":[]}"#),
+            Some("gpt-4o-2024-08-06".to_string())
+        );
+        assert_eq!(openai.stream_model("[DONE]"), None);
+
+        // 兼容接口不一定返回模型，由调用方退回到配置的模型
+        let completion = openai
+            .parse_response(r#"{"choices":[{"message":{"role":"assistant","content":"好"}}]}"#)
+            .unwrap();
+        assert_eq!(completion.model, None);
+
+        let anthropic = provider(config(AiProviderKind::Anthropic, ""));
+        assert_eq!(
+            anthropic.stream_model(
+                r#"{"type":"message_start","message":{"model":"claude-x-20250101","usage":{"input_tokens":1}}}"#
+            ),
+            Some("claude-x-20250101".to_string())
+        );
+        assert_eq!(
+            anthropic.stream_model(r#"{"type":"message_delta","usage":{"output_tokens":1}}"#),
+            None
+        );
+    }
+
+    #[test]
+    fn old_config_defaults_to_openai() {
+        let config: AiConfig = serde_json::from_value(serde_json::json!({
+            "api_key": "key",
+            "api_base": "https://example.com/v1",
+            "model": "m",
+        }))
+        .unwrap();
+
+        assert_eq!(config.provider, AiProviderKind::OpenAi);
+        assert_eq!(
+            serde_json::to_value(AiProviderKind::OpenAi).unwrap(),
+            "openai"
+        );
+    }
+}
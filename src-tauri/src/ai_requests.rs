@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tokio::sync::oneshot;
+
+use crate::error::{AppError, AppResult};
+
+// 进行中的 AI 请求（Tauri 托管状态），按前端生成的 request_id 登记，用于取消。
+// 每次登记带一个递增的 token：请求被取消后到真正结束之前，前端可能已用同一 request_id 发起新请求，
+// 注销时只移除自己登记的那一条
+#[derive(Default)]
+pub struct AiRequests {
+    pending: Mutex<HashMap<String, (u64, oneshot::Sender<()>)>>,
+    next_token: AtomicU64,
+}
+
+// 请求结束（完成、失败或被取消）时注销
+struct Registration<'a> {
+    requests: &'a AiRequests,
+    request_id: &'a str,
+    token: u64,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        let mut pending = self.requests.pending.lock().unwrap();
+        if pending
+            .get(self.request_id)
+            .is_some_and(|(token, _)| *token == self.token)
+        {
+            pending.remove(self.request_id);
+        }
+    }
+}
+
+impl AiRequests {
+    // 运行一个 AI 请求。有 request_id 时登记，被取消后丢弃 future（随之中断 HTTP 请求）并返回 cancelled 错误
+    pub async fn run<T>(
+        &self,
+        request_id: Option<&str>,
+        task: impl Future<Output = AppResult<T>>,
+    ) -> AppResult<T> {
+        let Some(request_id) = request_id else {
+            return task.await;
+        };
+
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        let token = self.next_token.fetch_add(1, Ordering::Relaxed);
+        {
+            let mut pending = self.pending.lock().unwrap();
+            if pending.contains_key(request_id) {
+                return Err(AppError::invalid_input(format!(
+                    "请求 ID 已在使用中: {}",
+                    request_id
+                )));
+            }
+            pending.insert(request_id.to_string(), (token, cancel_tx));
+        }
+        let _registration = Registration {
+            requests: self,
+            request_id,
+            token,
+        };
+
+        tokio::select! {
+            result = task => result,
+            _ = cancel_rx => Err(AppError::cancelled()),
+        }
+    }
+
+    // 取消进行中的请求，返回是否找到该请求（已结束的请求返回 false）
+    pub fn cancel(&self, request_id: &str) -> bool {
+        let entry = self.pending.lock().unwrap().remove(request_id);
+        entry.is_some_and(|(_, tx)| tx.send(()).is_ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn cancel_stops_pending_request() {
+        let requests = Arc::new(AiRequests::default());
+
+        let running = {
+            let requests = requests.clone();
+            tokio::spawn(async move {
+                requests
+                    .run(Some("req-1"), std::future::pending::<AppResult<()>>())
+                    .await
+            })
+        };
+        while !requests.pending.lock().unwrap().contains_key("req-1") {
+            tokio::task::yield_now().await;
+        }
+
+        assert!(requests.cancel("req-1"));
+        let err = running.await.unwrap().unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Cancelled);
+        assert!(requests.pending.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn finished_request_is_unregistered() {
+        let requests = AiRequests::default();
+
+        let result = requests.run(Some("req-1"), async { Ok(1) }).await;
+
+        assert_eq!(result.unwrap(), 1);
+        assert!(!requests.cancel("req-1"));
+    }
+
+    #[tokio::test]
+    async fn duplicate_request_id_is_rejected() {
+        let requests = Arc::new(AiRequests::default());
+        let (_release, wait) = oneshot::channel::<()>();
+        let first = {
+            let requests = requests.clone();
+            tokio::spawn(async move {
+                requests
+                    .run(Some("req-1"), async {
+                        let _ = wait.await;
+                        Ok(())
+                    })
+                    .await
+            })
+        };
+        while !requests.pending.lock().unwrap().contains_key("req-1") {
+            tokio::task::yield_now().await;
+        }
+
+        let err = requests
+            .run(Some("req-1"), async { Ok(()) })
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.kind, ErrorKind::InvalidInput);
+        requests.cancel("req-1");
+        first.await.unwrap().unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn cancelled_request_does_not_unregister_reused_id() {
+        let requests = AiRequests::default();
+        // 只轮询一次，完成登记但不等待结束
+        let mut first =
+            Box::pin(requests.run(Some("req-1"), std::future::pending::<AppResult<()>>()));
+        tokio::select! {
+            biased;
+            _ = &mut first => unreachable!(),
+            _ = async {} => {}
+        }
+        assert!(requests.cancel("req-1"));
+
+        // 第一个请求结束之前，前端用同一 ID 发起了新请求
+        let mut second =
+            Box::pin(requests.run(Some("req-1"), std::future::pending::<AppResult<()>>()));
+        tokio::select! {
+            biased;
+            _ = &mut second => unreachable!(),
+            _ = async {} => {}
+        }
+        assert_eq!(first.await.unwrap_err().kind, ErrorKind::Cancelled);
+
+        // 新请求仍然可以取消
+        assert!(requests.cancel("req-1"));
+        assert_eq!(second.await.unwrap_err().kind, ErrorKind::Cancelled);
+        assert!(requests.pending.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+
+use crate::ai_provider::{AiProvider, Completion, StreamEvent, StreamFormat};
+use crate::error::{AppError, AppResult};
+
+// AI 流式输出推送给前端的事件名
+pub const AI_STREAM_EVENT: &str = "ai-stream";
+
+// 推送给前端的流式事件，request_id 由前端生成，用于区分同时进行的多个请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AiStreamEvent {
+    Delta {
+        request_id: String,
+        text: String, // 新增的一段文本
+    },
+    Done {
+        request_id: String,
+        result: serde_json::Value, // 与命令返回值相同的解析结果
+    },
+    Error {
+        request_id: String,
+        error: AppError,
+    },
+}
+
+// 接收流式输出的增量文本
+pub trait StreamSink: Send + Sync {
+    fn delta(&self, text: &str);
+}
+
+// 把增量文本以 Tauri 事件推送到发起请求的窗口
+pub struct WindowSink {
+    window: tauri::Window,
+    request_id: String,
+}
+
+impl WindowSink {
+    pub fn new(window: tauri::Window, request_id: String) -> Self {
+        Self { window, request_id }
+    }
+
+    fn emit(&self, event: AiStreamEvent) {
+        // 窗口已关闭时推送失败，忽略即可
+        let _ = self
+            .window
+            .emit_to(self.window.label(), AI_STREAM_EVENT, event);
+    }
+
+    // 推送最终结果或错误
+    pub fn finish<T: Serialize>(&self, result: &AppResult<T>) {
+        let request_id = self.request_id.clone();
+        let event = match result.as_ref().map(serde_json::to_value) {
+            Ok(Ok(result)) => AiStreamEvent::Done { request_id, result },
+            Ok(Err(e)) => AiStreamEvent::Error {
+                request_id,
+                error: AppError::internal(format!("序列化结果失败: {}", e)),
+            },
+            Err(error) => AiStreamEvent::Error {
+                request_id,
+                error: error.clone(),
+            },
+        };
+        self.emit(event);
+    }
+}
+
+impl StreamSink for WindowSink {
+    fn delta(&self, text: &str) {
+        self.emit(AiStreamEvent::Delta {
+            request_id: self.request_id.clone(),
+            text: text.to_string(),
+        });
+    }
+}
+
+// 按行切分流式响应字节流。网络分块可能截断一行或一个 UTF-8 字符，未结束的部分留到下一块
+pub struct StreamDecoder {
+    format: StreamFormat,
+    buffer: Vec<u8>,
+}
+
+impl StreamDecoder {
+    pub fn new(format: StreamFormat) -> Self {
+        Self {
+            format,
+            buffer: Vec::new(),
+        }
+    }
+
+    // 返回这一块中完整的数据：SSE 的 data 字段内容，或 NDJSON 的非空行
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+        let mut data = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            data.extend(self.data(&String::from_utf8_lossy(&line)));
+        }
+        data
+    }
+
+    // 流结束时缓冲区中没有换行结尾的最后一行
+    pub fn finish(&mut self) -> Option<String> {
+        let rest = String::from_utf8_lossy(&std::mem::take(&mut self.buffer)).to_string();
+        self.data(&rest)
+    }
+
+    fn data(&self, line: &str) -> Option<String> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        match self.format {
+            StreamFormat::Sse => line
+                .strip_prefix("data:")
+                .map(|value| value.trim_start().to_string()),
+            StreamFormat::NdJson => Some(line.trim())
+                .filter(|line| !line.is_empty())
+                .map(str::to_string),
+        }
+    }
+}
+
+// 读取流式响应，逐段推送增量文本，返回完整文本
+pub async fn read_chat_stream(
+    mut response: reqwest::Response,
+    provider: &dyn AiProvider,
+    sink: &dyn StreamSink,
+) -> AppResult<Completion> {
+    let mut decoder = StreamDecoder::new(provider.stream_format());
+    let mut completion = Completion::default();
+
+    loop {
+        let chunk = response.chunk().await.map_err(AppError::network)?;
+        let lines = match &chunk {
+            Some(bytes) => decoder.push(bytes),
+            None => decoder.finish().into_iter().collect(),
+        };
+        for data in lines {
+            if completion.model.is_none() {
+                completion.model = provider.stream_model(&data);
+            }
+            match provider.parse_stream_data(&data)? {
+                StreamEvent::Done(usage) => {
+                    completion.usage += usage.unwrap_or_default();
+                    return Ok(completion);
+                }
+                StreamEvent::Usage(usage) => completion.usage += usage,
+                StreamEvent::Delta(text) if !text.is_empty() => {
+                    sink.delta(&text);
+                    completion.text.push_str(&text);
+                }
+                StreamEvent::Delta(_) => {}
+            }
+        }
+        if chunk.is_none() {
+            return Ok(completion);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decoder_joins_lines_split_across_chunks() {
+        let mut decoder = StreamDecoder::new(StreamFormat::Sse);
+        let text = "data: {\"a\":\"你好\"}\n\n: keep-alive\ndata: [DONE]\n";
+        let bytes = text.as_bytes();
+        // 在"你"的 UTF-8 编码中间切开
+        let split = text.find('你').unwrap() + 1;
+
+        let mut data = decoder.push(&bytes[..split]);
+        assert!(data.is_empty());
+        data.extend(decoder.push(&bytes[split..]));
+
+        assert_eq!(data, vec!["{\"a\":\"你好\"}", "[DONE]"]);
+        assert_eq!(decoder.finish(), None);
+    }
+
+    #[test]
+    fn decoder_keeps_unterminated_last_line() {
+        let mut decoder = StreamDecoder::new(StreamFormat::Sse);
+
+        assert!(decoder.push(b"data: tail").is_empty());
+        assert_eq!(decoder.finish().as_deref(), Some("tail"));
+    }
+
+    #[test]
+    fn ndjson_decoder_yields_non_empty_lines() {
+        let mut decoder = StreamDecoder::new(StreamFormat::NdJson);
+
+        let data = decoder.push(b"{\"a\":1}\r\n\n{\"b\":");
+        assert_eq!(data, vec!["{\"a\":1}"]);
+        assert!(decoder.push(b"2}").is_empty());
+        assert_eq!(decoder.finish().as_deref(), Some("{\"b\":2}"));
+    }
+}
@@ -0,0 +1,351 @@
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::commands::AnswerRecord;
+use crate::error::{AppError, AppResult};
+
+const CACHE_FILE: &str = "answers_cache.db";
+
+// 多维表格记录的本地 SQLite 缓存（离线模式 / 无凭证时读取）
+pub struct AnswerCache {
+    conn: Mutex<Connection>,
+}
+
+// 某张表在缓存中的快照
+pub struct CachedTable {
+    pub records: Vec<AnswerRecord>,
+    pub synced_at: i64, // 最后一次同步时间（秒级时间戳）
+}
+
+// 某张表的同步进度
+pub struct SyncState {
+    pub synced_at: i64,
+    pub watermark: Option<i64>, // 已同步记录中最大的 last_modified_time（毫秒）
+}
+
+impl AnswerCache {
+    pub fn open(data_dir: &Path) -> AppResult<Self> {
+        let conn = Connection::open(data_dir.join(CACHE_FILE))
+            .map_err(|e| AppError::storage(format!("打开本地缓存失败: {}", e)))?;
+        Self::init(conn)
+    }
+
+    fn init(conn: Connection) -> AppResult<Self> {
+        // 初始表结构，之后的变更都在 MIGRATIONS 中按版本追加
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS records (
+                app_token TEXT NOT NULL,
+                table_id TEXT NOT NULL,
+                record_id TEXT NOT NULL,
+                fields TEXT NOT NULL,
+                PRIMARY KEY (app_token, table_id, record_id)
+            );
+            CREATE TABLE IF NOT EXISTS sync_state (
+                app_token TEXT NOT NULL,
+                table_id TEXT NOT NULL,
+                synced_at INTEGER NOT NULL,
+                PRIMARY KEY (app_token, table_id)
+            );",
+        )
+        .map_err(|e| AppError::storage(format!("初始化本地缓存失败: {}", e)))?;
+        migrate(&conn)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    // 用一次完整拉取的结果替换整张表的缓存
+    pub fn replace_table(
+        &self,
+        app_token: &str,
+        table_id: &str,
+        records: &[AnswerRecord],
+    ) -> AppResult<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn
+            .transaction()
+            .map_err(|e| AppError::storage(format!("写入本地缓存失败: {}", e)))?;
+        tx.execute(
+            "DELETE FROM records WHERE app_token = ?1 AND table_id = ?2",
+            params![app_token, table_id],
+        )
+        .map_err(|e| AppError::storage(format!("写入本地缓存失败: {}", e)))?;
+        upsert_records(&tx, app_token, table_id, records)?;
+        let watermark = records.iter().filter_map(|r| r.last_modified_time).max();
+        save_sync_state(&tx, app_token, table_id, watermark)?;
+        tx.commit()
+            .map_err(|e| AppError::storage(format!("写入本地缓存失败: {}", e)))
+    }
+
+    // 应用一次增量同步：写入新增/修改的记录，删除上游已删除的记录
+    pub fn apply_changes(
+        &self,
+        app_token: &str,
+        table_id: &str,
+        upserts: &[AnswerRecord],
+        removed: &[String],
+        watermark: Option<i64>,
+    ) -> AppResult<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn
+            .transaction()
+            .map_err(|e| AppError::storage(format!("写入本地缓存失败: {}", e)))?;
+        upsert_records(&tx, app_token, table_id, upserts)?;
+        for record_id in removed {
+            tx.execute(
+                "DELETE FROM records WHERE app_token = ?1 AND table_id = ?2 AND record_id = ?3",
+                params![app_token, table_id, record_id],
+            )
+            .map_err(|e| AppError::storage(format!("写入本地缓存失败: {}", e)))?;
+        }
+        save_sync_state(&tx, app_token, table_id, watermark)?;
+        tx.commit()
+            .map_err(|e| AppError::storage(format!("写入本地缓存失败: {}", e)))
+    }
+
+    pub fn sync_state(&self, app_token: &str, table_id: &str) -> AppResult<Option<SyncState>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT synced_at, watermark FROM sync_state WHERE app_token = ?1 AND table_id = ?2",
+            params![app_token, table_id],
+            |row| {
+                Ok(SyncState {
+                    synced_at: row.get(0)?,
+                    watermark: row.get(1)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| AppError::storage(format!("读取本地缓存失败: {}", e)))
+    }
+
+    pub fn record_ids(&self, app_token: &str, table_id: &str) -> AppResult<HashSet<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT record_id FROM records WHERE app_token = ?1 AND table_id = ?2")
+            .map_err(|e| AppError::storage(format!("读取本地缓存失败: {}", e)))?;
+        let ids = stmt
+            .query_map(params![app_token, table_id], |row| row.get::<_, String>(0))
+            .map_err(|e| AppError::storage(format!("读取本地缓存失败: {}", e)))?
+            .collect::<Result<HashSet<_>, _>>()
+            .map_err(|e| AppError::storage(format!("读取本地缓存失败: {}", e)))?;
+        Ok(ids)
+    }
+
+    // 读取整张表的缓存，从未同步过时返回 None
+    pub fn load_table(&self, app_token: &str, table_id: &str) -> AppResult<Option<CachedTable>> {
+        let conn = self.conn.lock().unwrap();
+        let synced_at: Option<i64> = conn
+            .query_row(
+                "SELECT synced_at FROM sync_state WHERE app_token = ?1 AND table_id = ?2",
+                params![app_token, table_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| AppError::storage(format!("读取本地缓存失败: {}", e)))?;
+        let Some(synced_at) = synced_at else {
+            return Ok(None);
+        };
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT record_id, fields, last_modified_time FROM records
+                 WHERE app_token = ?1 AND table_id = ?2 ORDER BY rowid",
+            )
+            .map_err(|e| AppError::storage(format!("读取本地缓存失败: {}", e)))?;
+        let rows = stmt
+            .query_map(params![app_token, table_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<i64>>(2)?,
+                ))
+            })
+            .map_err(|e| AppError::storage(format!("读取本地缓存失败: {}", e)))?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let (record_id, fields, last_modified_time) =
+                row.map_err(|e| AppError::storage(format!("读取本地缓存失败: {}", e)))?;
+            let fields: HashMap<String, serde_json::Value> = serde_json::from_str(&fields)
+                .map_err(|e| AppError::storage(format!("解析缓存记录失败: {}", e)))?;
+            records.push(AnswerRecord {
+                record_id,
+                fields,
+                last_modified_time,
+            });
+        }
+
+        Ok(Some(CachedTable { records, synced_at }))
+    }
+}
+
+// 按 PRAGMA user_version 依次执行的表结构变更，下标 + 1 即迁移后的版本号
+const MIGRATIONS: &[&str] = &[
+    // 增量同步：记录的最后修改时间和每张表的同步水位
+    "ALTER TABLE records ADD COLUMN last_modified_time INTEGER;
+     ALTER TABLE sync_state ADD COLUMN watermark INTEGER;",
+];
+
+fn migrate(conn: &Connection) -> AppResult<()> {
+    let version: usize = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| AppError::storage(format!("读取本地缓存版本失败: {}", e)))?;
+    for (index, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+        conn.execute_batch(&format!(
+            "BEGIN; {} PRAGMA user_version = {}; COMMIT;",
+            sql,
+            index + 1
+        ))
+        .map_err(|e| AppError::storage(format!("升级本地缓存失败: {}", e)))?;
+    }
+    Ok(())
+}
+
+fn upsert_records(
+    tx: &Transaction,
+    app_token: &str,
+    table_id: &str,
+    records: &[AnswerRecord],
+) -> AppResult<()> {
+    let mut stmt = tx
+        .prepare(
+            "INSERT OR REPLACE INTO records (app_token, table_id, record_id, fields, last_modified_time)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )
+        .map_err(|e| AppError::storage(format!("写入本地缓存失败: {}", e)))?;
+    for record in records {
+        let fields = serde_json::to_string(&record.fields)
+            .map_err(|e| AppError::storage(format!("序列化记录失败: {}", e)))?;
+        stmt.execute(params![
+            app_token,
+            table_id,
+            record.record_id,
+            fields,
+            record.last_modified_time
+        ])
+        .map_err(|e| AppError::storage(format!("写入本地缓存失败: {}", e)))?;
+    }
+    Ok(())
+}
+
+fn save_sync_state(
+    tx: &Transaction,
+    app_token: &str,
+    table_id: &str,
+    watermark: Option<i64>,
+) -> AppResult<()> {
+    tx.execute(
+        "INSERT OR REPLACE INTO sync_state (app_token, table_id, synced_at, watermark)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![app_token, table_id, unix_now(), watermark],
+    )
+    .map_err(|e| AppError::storage(format!("写入本地缓存失败: {}", e)))?;
+    Ok(())
+}
+
+pub fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(record_id: &str, question: &str, modified: i64) -> AnswerRecord {
+        AnswerRecord {
+            record_id: record_id.to_string(),
+            fields: HashMap::from([("问题".to_string(), serde_json::json!(question))]),
+            last_modified_time: Some(modified),
+        }
+    }
+
+    #[test]
+    fn apply_changes_upserts_removes_and_saves_watermark() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = AnswerCache::open(dir.path()).unwrap();
+        cache
+            .replace_table(
+                "app",
+                "tbl",
+                &[record("rec1", "a", 10), record("rec2", "b", 20)],
+            )
+            .unwrap();
+        assert_eq!(
+            cache.sync_state("app", "tbl").unwrap().unwrap().watermark,
+            Some(20)
+        );
+
+        cache
+            .apply_changes(
+                "app",
+                "tbl",
+                &[record("rec1", "a2", 30), record("rec3", "c", 40)],
+                &["rec2".to_string()],
+                Some(40),
+            )
+            .unwrap();
+
+        let table = cache.load_table("app", "tbl").unwrap().unwrap();
+        let questions: Vec<_> = table
+            .records
+            .iter()
+            .map(|r| (r.record_id.as_str(), r.fields["问题"].as_str().unwrap()))
+            .collect();
+        assert_eq!(questions, vec![("rec1", "a2"), ("rec3", "c")]);
+        assert_eq!(
+            cache.sync_state("app", "tbl").unwrap().unwrap().watermark,
+            Some(40)
+        );
+        assert!(cache.load_table("app", "other").unwrap().is_none());
+    }
+
+    #[test]
+    fn cache_created_before_incremental_sync_is_migrated() {
+        let dir = tempfile::tempdir().unwrap();
+        // 增量同步之前的表结构，没有 last_modified_time 和 watermark 列
+        let conn = Connection::open(dir.path().join(CACHE_FILE)).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE records (
+                app_token TEXT NOT NULL,
+                table_id TEXT NOT NULL,
+                record_id TEXT NOT NULL,
+                fields TEXT NOT NULL,
+                PRIMARY KEY (app_token, table_id, record_id)
+            );
+            CREATE TABLE sync_state (
+                app_token TEXT NOT NULL,
+                table_id TEXT NOT NULL,
+                synced_at INTEGER NOT NULL,
+                PRIMARY KEY (app_token, table_id)
+            );
+            INSERT INTO records VALUES ('app', 'tbl', 'rec1', '{\"问题\":\"a\"}');
+            INSERT INTO sync_state VALUES ('app', 'tbl', 100);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let cache = AnswerCache::open(dir.path()).unwrap();
+        let table = cache.load_table("app", "tbl").unwrap().unwrap();
+        assert_eq!(table.records.len(), 1);
+        assert_eq!(table.records[0].last_modified_time, None);
+        let state = cache.sync_state("app", "tbl").unwrap().unwrap();
+        assert_eq!((state.synced_at, state.watermark), (100, None));
+
+        cache
+            .apply_changes("app", "tbl", &[record("rec2", "b", 50)], &[], Some(50))
+            .unwrap();
+        drop(cache);
+        // 再次打开时不会重复执行迁移
+        let cache = AnswerCache::open(dir.path()).unwrap();
+        assert_eq!(
+            cache.sync_state("app", "tbl").unwrap().unwrap().watermark,
+            Some(50)
+        );
+    }
+}
@@ -1,8 +1,34 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Mutex;
+use tauri::{Emitter, Manager, State};
+
+use crate::ai_cache::{AiCache, CacheKey};
+use crate::ai_output::{self, OptimizationReport, ReviewReport, RiskReport};
+use crate::ai_provider::{
+    self, AiProvider, AiProviderKind, ChatOptions, Completion, OutputSchema, TokenUsage,
+};
+use crate::ai_requests::AiRequests;
+use crate::ai_stream::{read_chat_stream, StreamSink, WindowSink};
+use crate::answer_cache::{unix_now, AnswerCache};
+use crate::error::{AppError, AppResult, FieldDiff, UpdateConflict};
+use crate::feishu_client::{FeishuClient, FeishuEndpoint};
+use crate::feishu_http::RetryPolicy;
+use crate::field_mapping::{
+    FieldMapping, FieldMappingStore, FieldMappingValidation, TableFieldMapping,
+};
+use crate::field_value::{FieldValue, TYPE_MODIFIED_TIME, TYPE_MULTI_SELECT, TYPE_SINGLE_SELECT};
+use crate::prompt_templates::{PromptKind, PromptStore, PromptTemplate, TemplateRef};
+use crate::review_batch::{
+    self, BatchItem, BatchItemResult, BatchJob, BatchReviewEvent, BatchRunner, BatchStatus,
+    BatchStore, RunningJob, BATCH_REVIEW_EVENT, DEFAULT_CONCURRENCY,
+};
+use crate::revision_store::{NewRevision, Revision, RevisionInfo, RevisionStore};
+use crate::schema_cache::SchemaCache;
+use crate::secret_store::{SecretStore, UnreadableSecrets};
+use crate::usage_store::{ModelPrice, NewUsage, UsageStore, UsageSummary};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FeishuCredentials {
     pub app_id: String,
     pub app_secret: String,
@@ -38,6 +64,9 @@ pub struct BitableTablesData {
 pub struct AnswerRecord {
     pub record_id: String,
     pub fields: HashMap<String, serde_json::Value>,
+    // 记录最后修改时间（毫秒），请求时带 automatic_fields=true 才会返回
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified_time: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -54,387 +83,688 @@ pub struct BitableRecordsData {
     pub page_token: Option<String>,
 }
 
+// 单条记录的响应（获取 / 创建 / 更新记录）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BitableRecordResponse {
+    pub code: i32,
+    pub msg: String,
+    pub data: Option<BitableRecordData>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BitableRecordData {
+    pub record: AnswerRecord,
+}
+
+impl BitableRecordResponse {
+    // action 如"获取记录失败"，用于错误信息
+    pub fn into_record(self, action: &str) -> AppResult<AnswerRecord> {
+        if self.code != 0 {
+            return Err(AppError::feishu(self.code as i64, &self.msg, action));
+        }
+        self.data
+            .map(|data| data.record)
+            .ok_or_else(|| AppError::parse("响应中缺少记录数据"))
+    }
+}
+
+// 只有 code / msg 的响应（如删除记录）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BitableResponse {
+    pub code: i32,
+    pub msg: String,
+}
+
+// 批量创建 / 更新记录的响应
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BitableBatchRecordsResponse {
+    pub code: i32,
+    pub msg: String,
+    pub data: Option<BitableBatchRecordsData>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BitableBatchRecordsData {
+    pub records: Vec<AnswerRecord>,
+}
+
+impl BitableBatchRecordsResponse {
+    pub fn into_records(self, action: &str) -> AppResult<Vec<AnswerRecord>> {
+        if self.code != 0 {
+            return Err(AppError::feishu(self.code as i64, &self.msg, action));
+        }
+        self.data
+            .map(|data| data.records)
+            .ok_or_else(|| AppError::parse("响应中缺少记录数据"))
+    }
+}
+
+// 批量删除记录的响应
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BitableBatchDeleteResponse {
+    pub code: i32,
+    pub msg: String,
+    pub data: Option<BitableBatchDeleteData>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BitableBatchDeleteData {
+    pub records: Vec<DeletedRecord>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeletedRecord {
+    pub deleted: bool,
+    pub record_id: String,
+}
+
+// 批量更新时的单条记录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BatchUpdateItem {
+    pub record_id: String,
+    pub fields: HashMap<String, serde_json::Value>,
+}
+
+// 批量操作中单条记录的结果，index 为记录在请求列表中的位置
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchRecordResult {
+    pub index: usize,
+    pub record_id: Option<String>, // 创建失败时没有 record_id
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<AppError>,
+}
+
+impl BatchRecordResult {
+    pub fn ok(index: usize, record_id: String) -> Self {
+        Self {
+            index,
+            record_id: Some(record_id),
+            success: true,
+            error: None,
+        }
+    }
+
+    pub fn failed(index: usize, record_id: Option<String>, error: AppError) -> Self {
+        Self {
+            index,
+            record_id,
+            success: false,
+            error: Some(error),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchResult {
+    pub results: Vec<BatchRecordResult>,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub retries: u32, // 请求自动重试的总次数
+}
+
+impl BatchResult {
+    fn new(results: Vec<BatchRecordResult>, retries: u32) -> Self {
+        let succeeded = results.iter().filter(|r| r.success).count();
+        Self {
+            failed: results.len() - succeeded,
+            succeeded,
+            results,
+            retries,
+        }
+    }
+}
+
+// 单条记录、表格列表等命令的结果，附带请求自动重试的次数
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Retried<T> {
+    pub value: T,
+    pub retries: u32, // 请求自动重试的次数
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BitableField {
+    pub field_id: String,
+    pub field_name: String,
+    #[serde(rename = "type")]
+    pub field_type: i32,
+    pub property: Option<BitableFieldProperty>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BitableFieldProperty {
+    pub options: Option<Vec<SelectOption>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SelectOption {
+    pub id: Option<String>,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BitableFieldsResponse {
+    pub code: i32,
+    pub msg: String,
+    pub data: Option<BitableFieldsData>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BitableFieldsData {
+    pub items: Vec<BitableField>,
+    pub has_more: bool,
+    pub page_token: Option<String>,
+}
+
+// 表结构中的一个字段（选项类型字段附带选项列表）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TableField {
+    pub field_id: String,
+    pub field_name: String,
+    pub field_type: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<Vec<SelectOption>>,
+}
+
+impl From<BitableField> for TableField {
+    fn from(field: BitableField) -> Self {
+        let options = match field.field_type {
+            TYPE_SINGLE_SELECT | TYPE_MULTI_SELECT => {
+                Some(field.property.and_then(|p| p.options).unwrap_or_default())
+            }
+            _ => None,
+        };
+        TableField {
+            field_id: field.field_id,
+            field_name: field.field_name,
+            field_type: field.field_type,
+            options,
+        }
+    }
+}
+
 // Answers 表的结构化数据
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Answer {
     pub record_id: String,
     pub question: String,           // 问题
-    pub standard_answer: String,     // 标准回答
-    pub enable_status: String,       // 状态（启用 / 停用）
+    pub standard_answer: String,    // 标准回答
+    pub enable_status: String,      // 状态（启用 / 停用）
     pub scene: String,              // 使用场景
     pub tone: String,               // 语气
-    pub product_name: String,       // 对应产品
+    pub product_name: String,       // 对应产品（多个产品用"、"连接）
+    pub product_names: Vec<String>, // 对应的所有产品
     pub product_id: String,         // product_id
     #[serde(skip_serializing_if = "Option::is_none")]
     pub raw_fields: Option<HashMap<String, serde_json::Value>>, // 原始字段数据（用于调试）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified_time: Option<i64>, // 最后修改时间（毫秒），写回时用于冲突检测
 }
 
 // AI 配置结构
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AiConfig {
-    pub api_key: String,
+    #[serde(default)]
+    pub provider: AiProviderKind, // 旧版本保存的配置没有该字段，按 OpenAI 兼容接口处理
+    pub api_key: String, // Ollama 可为空
     pub api_base: String,
-    pub model: String,
+    pub model: String, // Azure 为部署名
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<String>, // 仅 Azure 使用，为空时使用默认版本
 }
 
-// 存储飞书凭证（内存中，实际应该持久化）
-static CREDENTIALS: Mutex<Option<FeishuCredentials>> = Mutex::new(None);
-static ACCESS_TOKEN: Mutex<Option<(String, i64)>> = Mutex::new(None); // (token, expire_timestamp)
-static AI_CONFIG: Mutex<Option<AiConfig>> = Mutex::new(None);
-
-const FEISHU_API_BASE: &str = "https://open.feishu.cn/open-apis";
+// 飞书凭证和 AI 配置持久化在 SecretStore 中，飞书请求和 token 缓存由 FeishuClient 负责
 
 #[tauri::command]
-pub async fn set_feishu_credentials(app_id: String, app_secret: String) -> Result<String, String> {
+pub async fn set_feishu_credentials(
+    store: State<'_, SecretStore>,
+    client: State<'_, FeishuClient>,
+    app_id: String,
+    app_secret: String,
+) -> AppResult<String> {
     let creds = FeishuCredentials { app_id, app_secret };
-    *CREDENTIALS.lock().unwrap() = Some(creds);
-    // 清除旧的 token
-    *ACCESS_TOKEN.lock().unwrap() = None;
+    store.set_feishu_credentials(creds.clone())?;
+    client.set_credentials(creds).await;
     Ok("凭证已保存".to_string())
 }
 
+// 是否已保存飞书凭证（不返回凭证内容），前端据此判断是否为本地模式
 #[tauri::command]
-pub async fn get_feishu_access_token() -> Result<String, String> {
-    // 检查是否有缓存的 token
-    {
-        let token_guard = ACCESS_TOKEN.lock().unwrap();
-        if let Some((token, expire_at)) = token_guard.as_ref() {
-            let now = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs() as i64;
-            if now < *expire_at - 60 {
-                // 提前 60 秒刷新
-                return Ok(token.clone());
-            }
-        }
-    }
+pub async fn has_feishu_credentials(store: State<'_, SecretStore>) -> AppResult<bool> {
+    Ok(store.feishu_credentials().is_some())
+}
 
-    // 获取凭证（在 await 之前克隆数据并释放锁）
-    let (app_id, app_secret) = {
-        let creds = CREDENTIALS.lock().unwrap();
-        let creds = creds.as_ref().ok_or("请先配置飞书凭证")?;
-        (creds.app_id.clone(), creds.app_secret.clone())
-    };
+// 启动时已保存的凭证无法读取（旧文件已移走）时返回原因，前端提示重新填写
+#[tauri::command]
+pub async fn get_secret_store_status(
+    store: State<'_, SecretStore>,
+) -> AppResult<Option<UnreadableSecrets>> {
+    Ok(store.unreadable())
+}
 
-    // 请求 access_token
-    let client = reqwest::Client::new();
-    let url = format!("{}/auth/v3/tenant_access_token/internal", FEISHU_API_BASE);
-    let body = serde_json::json!({
-        "app_id": app_id,
-        "app_secret": app_secret,
-    });
+#[tauri::command]
+pub async fn get_feishu_access_token(client: State<'_, FeishuClient>) -> AppResult<String> {
+    client.access_token().await
+}
 
-    let response = client
-        .post(&url)
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| {
-            // 提供更友好的错误信息
-            let error_msg = e.to_string();
-            if error_msg.contains("timeout") || error_msg.contains("timed out") {
-                "网络请求超时，请检查网络连接".to_string()
-            } else if error_msg.contains("resolve") || error_msg.contains("DNS") {
-                "无法解析域名，请检查网络连接或 DNS 设置".to_string()
-            } else if error_msg.contains("connection refused") || error_msg.contains("Connection refused") {
-                "连接被拒绝，请检查网络连接或防火墙设置".to_string()
-            } else {
-                format!("网络请求失败: {}", error_msg)
-            }
-        })?;
+#[tauri::command]
+pub async fn get_feishu_endpoint(store: State<'_, SecretStore>) -> AppResult<FeishuEndpoint> {
+    Ok(store.feishu_endpoint())
+}
 
-    let token_res: AccessTokenResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("解析响应失败: {}", e))?;
+// 保存开放平台地址（飞书 / Lark / 私有化部署），返回规范化后的配置
+#[tauri::command]
+pub async fn set_feishu_endpoint(
+    store: State<'_, SecretStore>,
+    client: State<'_, FeishuClient>,
+    endpoint: FeishuEndpoint,
+) -> AppResult<FeishuEndpoint> {
+    let endpoint = endpoint.normalize()?;
+    store.set_feishu_endpoint(endpoint.clone())?;
+    client.set_endpoint(&endpoint).await;
+    Ok(endpoint)
+}
 
-    if token_res.code != 0 {
-        return Err(format!("获取 token 失败: {}", token_res.msg));
+#[tauri::command]
+pub async fn get_retry_policy(client: State<'_, FeishuClient>) -> AppResult<RetryPolicy> {
+    Ok(client.retry_policy())
+}
+
+#[tauri::command]
+pub async fn set_retry_policy(
+    client: State<'_, FeishuClient>,
+    policy: RetryPolicy,
+) -> AppResult<RetryPolicy> {
+    if policy.base_delay_ms > policy.max_delay_ms {
+        return Err(AppError::invalid_input("重试初始等待时间不能大于等待上限"));
     }
+    client.set_retry_policy(policy);
+    Ok(policy)
+}
 
-    let token = token_res.tenant_access_token.ok_or("响应中缺少 token")?;
-    let expire = token_res.expire.unwrap_or(7200);
-    let expire_at = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs() as i64
-        + expire as i64;
+#[tauri::command]
+pub async fn get_bitable_tables(
+    client: State<'_, FeishuClient>,
+    app_token: String,
+) -> AppResult<Retried<Vec<BitableTable>>> {
+    let mut retries = 0;
+    let value = client.list_tables(&app_token, &mut retries).await?;
+    Ok(Retried { value, retries })
+}
 
-    // 缓存 token
-    *ACCESS_TOKEN.lock().unwrap() = Some((token.clone(), expire_at));
+#[tauri::command]
+pub async fn get_answers_data(
+    client: State<'_, FeishuClient>,
+    app_token: String,
+    table_id: String,
+) -> AppResult<Retried<Vec<AnswerRecord>>> {
+    let mut retries = 0;
+    let value = client
+        .list_records(&app_token, &table_id, &mut retries)
+        .await?;
+    Ok(Retried { value, retries })
+}
 
-    Ok(token)
+// 增量同步结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncSummary {
+    pub full_sync: bool,        // 是否为首次全量同步
+    pub added: Vec<String>,     // 新增的 record_id
+    pub updated: Vec<String>,   // 修改的 record_id
+    pub removed: Vec<String>,   // 上游已删除的 record_id
+    pub watermark: Option<i64>, // 本次同步后的水位（毫秒）
+    pub synced_at: i64,
+    pub retries: u32, // 请求自动重试的次数
 }
 
 #[tauri::command]
-pub async fn get_bitable_tables(app_token: String) -> Result<Vec<BitableTable>, String> {
-    let token = get_feishu_access_token().await?;
-    let client = reqwest::Client::new();
-    let url = format!(
-        "{}/bitable/v1/apps/{}/tables",
-        FEISHU_API_BASE, app_token
-    );
+pub async fn sync_answers(
+    client: State<'_, FeishuClient>,
+    cache: State<'_, AnswerCache>,
+    schemas: State<'_, SchemaCache>,
+    app_token: String,
+    table_id: String,
+    modified_field: Option<String>,
+) -> AppResult<SyncSummary> {
+    sync_table(
+        &client,
+        &cache,
+        &schemas,
+        &app_token,
+        &table_id,
+        modified_field,
+    )
+    .await
+}
 
-    let response = client
-        .get(&url)
-        .header("Authorization", format!("Bearer {}", token))
-        .send()
-        .await
-        .map_err(|e| format!("网络请求失败: {}", e))?;
+// 未指定过滤字段时，使用表结构中第一个"最后更新时间"类型的字段
+async fn resolve_modified_field(
+    client: &FeishuClient,
+    schemas: &SchemaCache,
+    app_token: &str,
+    table_id: &str,
+    retries: &mut u32,
+) -> AppResult<Option<String>> {
+    let schema = match schemas.get(app_token, table_id) {
+        Some(schema) => schema,
+        None => load_table_schema(schemas, client, app_token, table_id, retries).await?,
+    };
+    Ok(schema
+        .into_iter()
+        .find(|field| field.field_type == TYPE_MODIFIED_TIME)
+        .map(|field| field.field_name))
+}
 
-    let tables_res: BitableTablesResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("解析响应失败: {}", e))?;
+async fn sync_table(
+    client: &FeishuClient,
+    cache: &AnswerCache,
+    schemas: &SchemaCache,
+    app_token: &str,
+    table_id: &str,
+    modified_field: Option<String>,
+) -> AppResult<SyncSummary> {
+    let mut retries = 0;
+
+    // 没有同步水位时做一次全量同步
+    let watermark = cache
+        .sync_state(app_token, table_id)?
+        .and_then(|state| state.watermark);
+    let Some(watermark) = watermark else {
+        let records = client
+            .list_records(app_token, table_id, &mut retries)
+            .await?;
+        cache.replace_table(app_token, table_id, &records)?;
+        let state = cache.sync_state(app_token, table_id)?;
+        return Ok(SyncSummary {
+            full_sync: true,
+            added: records.into_iter().map(|r| r.record_id).collect(),
+            updated: Vec::new(),
+            removed: Vec::new(),
+            watermark: state.as_ref().and_then(|s| s.watermark),
+            synced_at: state.map(|s| s.synced_at).unwrap_or_else(unix_now),
+            retries,
+        });
+    };
 
-    if tables_res.code != 0 {
-        return Err(format!("获取表格列表失败: {}", tables_res.msg));
-    }
+    let modified_field = match modified_field {
+        Some(field) => Some(field),
+        None => resolve_modified_field(client, schemas, app_token, table_id, &mut retries).await?,
+    };
+
+    let (changed, upstream_ids) = match modified_field {
+        Some(modified_field) => {
+            // ExactDate 只精确到天，因此向前多取一天，再按记录的 last_modified_time 精确过滤
+            let since_day = watermark - 24 * 60 * 60 * 1000;
+            let changed_body = serde_json::json!({
+                "filter": {
+                    "conjunction": "and",
+                    "conditions": [{
+                        "field_name": modified_field,
+                        "operator": "isGreater",
+                        "value": ["ExactDate", since_day.to_string()],
+                    }],
+                },
+                "automatic_fields": true,
+            });
+            let changed = client
+                .search_records(app_token, table_id, &changed_body, &mut retries)
+                .await?;
+
+            // 增量过滤无法发现删除，只拉取 record_id（仅带一个字段）与本地比对
+            let ids_body = serde_json::json!({
+                "field_names": [modified_field],
+                "automatic_fields": false,
+            });
+            let upstream_ids: HashSet<String> = client
+                .search_records(app_token, table_id, &ids_body, &mut retries)
+                .await?
+                .into_iter()
+                .map(|r| r.record_id)
+                .collect();
+            (changed, upstream_ids)
+        }
+        None => {
+            // 表中没有"最后更新时间"字段，无法在服务端过滤，拉取全部记录后按 last_modified_time 比对
+            let body = serde_json::json!({ "automatic_fields": true });
+            let records = client
+                .search_records(app_token, table_id, &body, &mut retries)
+                .await?;
+            let upstream_ids = records.iter().map(|r| r.record_id.clone()).collect();
+            (records, upstream_ids)
+        }
+    };
+    let changed: Vec<AnswerRecord> = changed
+        .into_iter()
+        .filter(|r| !matches!(r.last_modified_time, Some(t) if t <= watermark))
+        .collect();
 
-    let items = tables_res
-        .data
-        .ok_or("响应中缺少数据")?
-        .items;
+    let local_ids = cache.record_ids(app_token, table_id)?;
+    let mut added = Vec::new();
+    let mut updated = Vec::new();
+    for record in &changed {
+        if local_ids.contains(&record.record_id) {
+            updated.push(record.record_id.clone());
+        } else {
+            added.push(record.record_id.clone());
+        }
+    }
+    let mut removed: Vec<String> = local_ids.difference(&upstream_ids).cloned().collect();
+    removed.sort();
+
+    let new_watermark = changed
+        .iter()
+        .filter_map(|r| r.last_modified_time)
+        .chain(std::iter::once(watermark))
+        .max();
+    cache.apply_changes(app_token, table_id, &changed, &removed, new_watermark)?;
+
+    Ok(SyncSummary {
+        full_sync: false,
+        added,
+        updated,
+        removed,
+        watermark: new_watermark,
+        synced_at: unix_now(),
+        retries,
+    })
+}
 
-    Ok(items)
+// 拉取表结构并刷新缓存
+async fn load_table_schema(
+    schemas: &SchemaCache,
+    client: &FeishuClient,
+    app_token: &str,
+    table_id: &str,
+    retries: &mut u32,
+) -> AppResult<Vec<TableField>> {
+    let fields: Vec<TableField> = client
+        .list_fields(app_token, table_id, retries)
+        .await?
+        .into_iter()
+        .map(TableField::from)
+        .collect();
+    schemas.insert(app_token, table_id, fields.clone());
+    Ok(fields)
 }
 
+// 获取表的字段列表（字段 id、名称、类型、单选/多选的选项），默认读取缓存
 #[tauri::command]
-pub async fn get_answers_data(
+pub async fn get_table_schema(
+    client: State<'_, FeishuClient>,
+    schemas: State<'_, SchemaCache>,
     app_token: String,
     table_id: String,
-) -> Result<Vec<AnswerRecord>, String> {
-    let token = get_feishu_access_token().await?;
-    let client = reqwest::Client::new();
-    let url = format!(
-        "{}/bitable/v1/apps/{}/tables/{}/records",
-        FEISHU_API_BASE, app_token, table_id
-    );
+    refresh: Option<bool>,
+) -> AppResult<Retried<Vec<TableField>>> {
+    if !refresh.unwrap_or(false) {
+        if let Some(value) = schemas.get(&app_token, &table_id) {
+            return Ok(Retried { value, retries: 0 });
+        }
+    }
+    let mut retries = 0;
+    let value = load_table_schema(&schemas, &client, &app_token, &table_id, &mut retries).await?;
+    Ok(Retried { value, retries })
+}
 
-    let mut all_records = Vec::new();
-    let mut page_token: Option<String> = None;
+// 按别名顺序取第一个非空字段值，有表结构时按字段类型解码
+fn get_field_value<K: AsRef<str>>(
+    fields: &HashMap<String, serde_json::Value>,
+    keys: &[K],
+    field_types: &HashMap<String, i32>,
+) -> Option<FieldValue> {
+    keys.iter().find_map(|key| {
+        let key = key.as_ref();
+        let value = fields.get(key)?;
+        FieldValue::decode(field_types.get(key).copied(), value)
+    })
+}
 
-    loop {
-        let mut request = client
-            .get(&url)
-            .header("Authorization", format!("Bearer {}", token));
+// 字段的文本值（多值字段用"、"连接），缺失时返回 "-"
+fn get_field_string<K: AsRef<str>>(
+    fields: &HashMap<String, serde_json::Value>,
+    keys: &[K],
+    field_types: &HashMap<String, i32>,
+) -> String {
+    get_field_value(fields, keys, field_types)
+        .map(|value| value.text())
+        .filter(|text| !text.is_empty())
+        .unwrap_or_else(|| "-".to_string())
+}
 
-        if let Some(token) = &page_token {
-            request = request.query(&[("page_token", token)]);
-        }
+// 字段的所有值，多选 / 关联等多值字段不会丢失后面的值
+fn get_field_strings<K: AsRef<str>>(
+    fields: &HashMap<String, serde_json::Value>,
+    keys: &[K],
+    field_types: &HashMap<String, i32>,
+) -> Vec<String> {
+    get_field_value(fields, keys, field_types)
+        .map(|value| value.texts())
+        .unwrap_or_default()
+}
 
-        let response = request
-            .send()
-            .await
-            .map_err(|e| format!("网络请求失败: {}", e))?;
+// 字段名 -> 字段类型
+fn field_types(schema: &[TableField]) -> HashMap<String, i32> {
+    schema
+        .iter()
+        .map(|field| (field.field_name.clone(), field.field_type))
+        .collect()
+}
 
-        let records_res: BitableRecordsResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("解析响应失败: {}", e))?;
-
-        if records_res.code != 0 {
-            return Err(format!("获取记录失败: {}", records_res.msg));
-        }
-
-        let data = records_res.data.ok_or("响应中缺少数据")?;
-        all_records.extend(data.items);
-
-        if !data.has_more {
-            break;
-        }
-
-        page_token = data.page_token;
-        if page_token.is_none() {
-            break;
-        }
-    }
-
-    Ok(all_records)
-}
-
-// 从字段中安全获取字符串值，缺失时返回 "-"
-fn get_field_string(fields: &HashMap<String, serde_json::Value>, keys: &[&str]) -> String {
-    for key in keys {
-        if let Some(value) = fields.get(*key) {
-            match value {
-                serde_json::Value::String(s) if !s.is_empty() => return s.clone(),
-                serde_json::Value::Number(n) => return n.to_string(),
-                serde_json::Value::Bool(b) => return b.to_string(),
-                serde_json::Value::Null => continue,
-                _ => {
-                    // 尝试从数组中提取文本（处理选项类型字段，如"状态"字段）
-                    if let serde_json::Value::Array(arr) = value {
-                        if !arr.is_empty() {
-                            // 遍历数组中的所有元素
-                            for item in arr {
-                                // 如果是对象，尝试提取各种可能的属性
-                                if let serde_json::Value::Object(obj) = item {
-                                    // 优先尝试 text 属性（飞书选项字段常用）
-                                    if let Some(text_val) = obj.get("text") {
-                                        if let serde_json::Value::String(s) = text_val {
-                                            if !s.is_empty() {
-                                                return s.clone();
-                                            }
-                                        }
-                                    }
-                                    // 尝试 name 属性
-                                    if let Some(name_val) = obj.get("name") {
-                                        if let serde_json::Value::String(s) = name_val {
-                                            if !s.is_empty() {
-                                                return s.clone();
-                                            }
-                                        }
-                                    }
-                                    // 尝试 option_name 属性
-                                    if let Some(option_name_val) = obj.get("option_name") {
-                                        if let serde_json::Value::String(s) = option_name_val {
-                                            if !s.is_empty() {
-                                                return s.clone();
-                                            }
-                                        }
-                                    }
-                                    // 尝试 label 属性
-                                    if let Some(label_val) = obj.get("label") {
-                                        if let serde_json::Value::String(s) = label_val {
-                                            if !s.is_empty() {
-                                                return s.clone();
-                                            }
-                                        }
-                                    }
-                                }
-                                // 如果数组元素本身就是字符串，直接返回
-                                if let serde_json::Value::String(s) = item {
-                                    if !s.is_empty() {
-                                        return s.clone();
-                                    }
-                                }
-                            }
-                            // 如果数组不为空但没找到有效值，尝试提取第一个元素的字符串表示
-                            if let Some(first) = arr.first() {
-                                if first.is_object() {
-                                    // 尝试序列化为字符串（作为最后手段）
-                                    if let Ok(json_str) = serde_json::to_string(first) {
-                                        if !json_str.is_empty() && json_str != "{}" {
-                                            return json_str;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    // 尝试从对象中提取文本
-                    if let serde_json::Value::Object(obj) = value {
-                        // 飞书选项字段可能的结构
-                        if let Some(text) = obj.get("text") {
-                            if let Some(s) = text.as_str() {
-                                if !s.is_empty() {
-                                    return s.to_string();
-                                }
-                            }
-                        }
-                        if let Some(name) = obj.get("name") {
-                            if let Some(s) = name.as_str() {
-                                if !s.is_empty() {
-                                    return s.to_string();
-                                }
-                            }
-                        }
-                        // 尝试提取 option_id 或 option_name
-                        if let Some(option_name) = obj.get("option_name") {
-                            if let Some(s) = option_name.as_str() {
-                                if !s.is_empty() {
-                                    return s.to_string();
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-    "-".to_string()
+// list_answers 的返回结果，附带数据来源和缓存时间
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AnswersResult {
+    pub answers: Vec<Answer>,
+    pub from_cache: bool,                  // 是否来自本地缓存
+    pub synced_at: Option<i64>,            // 缓存最后同步时间（秒级时间戳）
+    pub cache_age_secs: Option<i64>,       // 缓存距今的秒数
+    pub fallback_reason: Option<AppError>, // 在线拉取失败而回退到缓存时的原因
+    #[serde(default)]
+    pub retries: u32, // 请求自动重试的次数
 }
 
 #[tauri::command]
 pub async fn list_answers(
+    client: State<'_, FeishuClient>,
+    cache: State<'_, AnswerCache>,
+    mappings: State<'_, FieldMappingStore>,
+    schemas: State<'_, SchemaCache>,
     app_token: String,
     table_id: String,
-) -> Result<Vec<Answer>, String> {
-    // 获取 access token
-    let token = get_feishu_access_token().await?;
-    
-    // 构建 API URL
-    let client = reqwest::Client::new();
-    let url = format!(
-        "{}/bitable/v1/apps/{}/tables/{}/records",
-        FEISHU_API_BASE, app_token, table_id
-    );
-
-    let mut all_records = Vec::new();
-    let mut page_token: Option<String> = None;
+    offline: Option<bool>,
+) -> AppResult<AnswersResult> {
+    load_answers(
+        &client,
+        &cache,
+        &mappings,
+        &schemas,
+        &app_token,
+        &table_id,
+        offline.unwrap_or(false),
+    )
+    .await
+}
 
-    // 分页获取所有记录
-    loop {
-        let mut request = client
-            .get(&url)
-            .header("Authorization", format!("Bearer {}", token));
+// list_answers 的实现（不依赖 Tauri State，便于测试）
+async fn load_answers(
+    client: &FeishuClient,
+    cache: &AnswerCache,
+    mappings: &FieldMappingStore,
+    schemas: &SchemaCache,
+    app_token: &str,
+    table_id: &str,
+    offline: bool,
+) -> AppResult<AnswersResult> {
+    let mapping = mappings.get(app_token, table_id).mapping;
+    let cached_schema = schemas.get(app_token, table_id);
+
+    // 离线模式或未配置凭证时直接读取本地缓存
+    if offline || !client.has_credentials() {
+        let types = field_types(&cached_schema.unwrap_or_default());
+        return answers_from_cache(cache, &mapping, &types, app_token, table_id, None);
+    }
 
-        if let Some(token) = &page_token {
-            request = request.query(&[("page_token", token)]);
+    let mut retries = 0;
+    let records = match client.list_records(app_token, table_id, &mut retries).await {
+        Ok(records) => records,
+        // 在线拉取失败时，有缓存则回退到缓存
+        Err(e) => {
+            let types = field_types(&cached_schema.unwrap_or_default());
+            return answers_from_cache(cache, &mapping, &types, app_token, table_id, Some(e));
         }
+    };
 
-        let response = request
-            .send()
-            .await
-            .map_err(|e| format!("网络请求失败: {}", e))?;
+    // 本地缓存只用于离线读取，写入失败（如磁盘只读）不影响已拉取到的结果
+    let _ = cache.replace_table(app_token, table_id, &records);
 
-        let records_res: BitableRecordsResponse = response
-            .json()
+    // 表结构用于按字段类型解码，获取失败时按值的形状推断
+    let schema = match cached_schema {
+        Some(schema) => schema,
+        None => load_table_schema(schemas, client, app_token, table_id, &mut retries)
             .await
-            .map_err(|e| format!("解析响应失败: {}", e))?;
-
-        if records_res.code != 0 {
-            return Err(format!("获取记录失败: {}", records_res.msg));
-        }
-
-        let data = records_res.data.ok_or("响应中缺少数据")?;
-        all_records.extend(data.items);
+            .unwrap_or_default(),
+    };
 
-        if !data.has_more {
-            break;
-        }
+    Ok(AnswersResult {
+        answers: to_answers(records, &mapping, &field_types(&schema)),
+        from_cache: false,
+        synced_at: Some(unix_now()),
+        cache_age_secs: Some(0),
+        fallback_reason: None,
+        retries,
+    })
+}
 
-        page_token = data.page_token;
-        if page_token.is_none() {
-            break;
-        }
-    }
+fn answers_from_cache(
+    cache: &AnswerCache,
+    mapping: &FieldMapping,
+    field_types: &HashMap<String, i32>,
+    app_token: &str,
+    table_id: &str,
+    fallback_reason: Option<AppError>,
+) -> AppResult<AnswersResult> {
+    let Some(cached) = cache.load_table(app_token, table_id)? else {
+        return Err(fallback_reason
+            .unwrap_or_else(|| AppError::not_configured("本地缓存中没有数据，请先联网同步")));
+    };
+    Ok(AnswersResult {
+        answers: to_answers(cached.records, mapping, field_types),
+        from_cache: true,
+        synced_at: Some(cached.synced_at),
+        cache_age_secs: Some((unix_now() - cached.synced_at).max(0)),
+        retries: fallback_reason.as_ref().map(|e| e.retries).unwrap_or(0),
+        fallback_reason,
+    })
+}
 
-    // 字段映射和容错处理（使用飞书实际字段名）
-    let answers: Vec<Answer> = all_records
+// 字段映射和容错处理（按表配置的字段映射取值）
+fn to_answers(
+    records: Vec<AnswerRecord>,
+    mapping: &FieldMapping,
+    field_types: &HashMap<String, i32>,
+) -> Vec<Answer> {
+    records
         .into_iter()
-        .map(|record| {
-            Answer {
-                record_id: record.record_id.clone(),
-                // 问题字段
-                question: get_field_string(&record.fields, &["问题"]),
-                // 标准回答字段
-                standard_answer: get_field_string(&record.fields, &["标准回答"]),
-                // 状态字段（选项类型，可能是数组格式）
-                enable_status: get_field_string(&record.fields, &["状态"]),
-                // 使用场景字段
-                scene: get_field_string(&record.fields, &["使用场景"]),
-                // 语气字段
-                tone: get_field_string(&record.fields, &["语气"]),
-                // 对应产品字段
-                product_name: get_field_string(&record.fields, &["对应产品"]),
-                // product_id字段
-                product_id: get_field_string(&record.fields, &["product_id"]),
-                raw_fields: Some(record.fields), // 保存原始字段数据用于调试
-            }
-        })
+        .map(|record| to_answer(record, mapping, field_types))
         // 过滤掉只有问题没有答复的记录
         .filter(|answer| {
             let has_question = !answer.question.is_empty() && answer.question != "-";
@@ -442,511 +772,2687 @@ pub async fn list_answers(
             // 必须同时有问题和答复才同步
             has_question && has_answer
         })
-        .collect();
-
-    Ok(answers)
+        .collect()
 }
 
-// AI 相关命令
-
-#[derive(Debug, Serialize, Deserialize)]
-struct ChatMessage {
-    role: String,
-    content: String,
+fn to_answer(
+    record: AnswerRecord,
+    mapping: &FieldMapping,
+    field_types: &HashMap<String, i32>,
+) -> Answer {
+    let fields = &record.fields;
+    Answer {
+        record_id: record.record_id.clone(),
+        // 问题字段
+        question: get_field_string(fields, &mapping.question, field_types),
+        // 标准回答字段
+        standard_answer: get_field_string(fields, &mapping.standard_answer, field_types),
+        // 状态字段（选项类型）
+        enable_status: get_field_string(fields, &mapping.enable_status, field_types),
+        // 使用场景字段
+        scene: get_field_string(fields, &mapping.scene, field_types),
+        // 语气字段
+        tone: get_field_string(fields, &mapping.tone, field_types),
+        // 对应产品字段（可能是多选或关联字段）
+        product_name: get_field_string(fields, &mapping.product_name, field_types),
+        product_names: get_field_strings(fields, &mapping.product_name, field_types),
+        // product_id字段
+        product_id: get_field_string(fields, &mapping.product_id, field_types),
+        raw_fields: Some(record.fields), // 保存原始字段数据用于调试
+        last_modified_time: record.last_modified_time,
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ChatRequest {
-    model: String,
-    messages: Vec<ChatMessage>,
-    temperature: Option<f64>,
-    max_tokens: Option<u32>,
+#[tauri::command]
+pub async fn get_field_mapping(
+    mappings: State<'_, FieldMappingStore>,
+    app_token: String,
+    table_id: String,
+) -> AppResult<TableFieldMapping> {
+    Ok(mappings.get(&app_token, &table_id))
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ChatResponse {
-    choices: Option<Vec<ChatChoice>>,
-    error: Option<ChatError>,
+// 用表格实际字段校验映射，必填字段都能匹配、别名不为空且各字段不共用同一列时才保存
+#[tauri::command]
+pub async fn set_field_mapping(
+    client: State<'_, FeishuClient>,
+    mappings: State<'_, FieldMappingStore>,
+    schemas: State<'_, SchemaCache>,
+    app_token: String,
+    table_id: String,
+    mapping: FieldMapping,
+) -> AppResult<Retried<FieldMappingValidation>> {
+    save_field_mapping(&client, &mappings, &schemas, &app_token, &table_id, mapping).await
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ChatChoice {
-    message: Option<ChatMessage>,
-    finish_reason: Option<String>,
+async fn save_field_mapping(
+    client: &FeishuClient,
+    mappings: &FieldMappingStore,
+    schemas: &SchemaCache,
+    app_token: &str,
+    table_id: &str,
+    mapping: FieldMapping,
+) -> AppResult<Retried<FieldMappingValidation>> {
+    // 校验时总是使用最新的表结构
+    let mut retries = 0;
+    let table_fields: Vec<String> =
+        load_table_schema(schemas, client, app_token, table_id, &mut retries)
+            .await?
+            .into_iter()
+            .map(|field| field.field_name)
+            .collect();
+
+    let validation = mapping.validate(&table_fields);
+    if let Some(message) = validation.error_message() {
+        return Err(AppError::invalid_input(message));
+    }
+
+    mappings.set(
+        app_token,
+        table_id,
+        TableFieldMapping {
+            mapping,
+            columns: validation.columns.clone(),
+        },
+    )?;
+    Ok(Retried {
+        value: validation,
+        retries,
+    })
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ChatError {
-    message: String,
-    code: Option<String>,
+// AI 相关命令
+
+// 调用 AI 时如何使用响应缓存，bypass 为 true 时不读取缓存（仍写入新的回复）
+#[derive(Clone, Copy)]
+struct CachePolicy<'a> {
+    cache: &'a AiCache,
+    bypass: bool,
 }
 
-async fn call_ai_api(prompt: String) -> Result<String, String> {
-    // 在 await 之前克隆配置数据
-    let (api_base, api_key, model) = {
-        let config_guard = AI_CONFIG.lock().unwrap();
-        let config = config_guard.as_ref().ok_or("请先配置 AI 设置")?;
-        (config.api_base.clone(), config.api_key.clone(), config.model.clone())
-    };
+// 按配置的接口类型调用 AI。传入 sink 时使用流式输出，逐段推送增量文本；
+// 传入 schema 时要求结构化输出，接口拒绝（4xx）时去掉 schema 重试一次，由调用方容错解析文本；
+// 传入缓存时按接口类型、模型、模板版本和提示词查找未过期的回复，命中时整段推送且不调用接口；
+// 传入 usage 时每次实际调用接口（包括去掉 schema 的重试）都记录一条用量
+async fn call_ai_api(
+    store: &SecretStore,
+    prompt: String,
+    schema: Option<&OutputSchema>,
+    cache: Option<(CachePolicy<'_>, TemplateRef)>,
+    usage: Option<UsageContext<'_>>,
+    sink: Option<&dyn StreamSink>,
+) -> AppResult<Completion> {
+    let config = store
+        .ai_config()
+        .ok_or_else(|| AppError::not_configured("请先配置 AI 设置"))?;
+    let cache = cache.map(|(policy, template)| {
+        let key = CacheKey::new(config.provider, &config.model, template, &prompt);
+        (policy, key)
+    });
+    if let Some((policy, key)) = cache.as_ref().filter(|(policy, _)| !policy.bypass) {
+        // 未命中或缓存不可用（读取失败）时照常调用接口
+        if let Ok(Some(text)) = policy.cache.get(key) {
+            if let Some(sink) = sink {
+                sink.delta(&text);
+            }
+            return Ok(Completion {
+                text,
+                usage: TokenUsage::default(),
+                cached: true,
+                model: None,
+            });
+        }
+    }
 
+    let configured_model = config.model.clone();
+    let provider = ai_provider::provider(config);
     let client = reqwest::Client::new();
-    let url = format!("{}/chat/completions", api_base);
-
-    let messages = vec![ChatMessage {
-        role: "user".to_string(),
-        content: prompt,
-    }];
-
-    let request = ChatRequest {
-        model: model.clone(),
-        messages,
-        temperature: Some(0.7),
-        max_tokens: Some(2000),
+    let options = ChatOptions {
+        stream: sink.is_some(),
+        schema,
     };
 
-    let response = client
-        .post(&url)
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .json(&request)
+    let completion = match send_chat(&client, provider.as_ref(), &prompt, options, sink).await {
+        Err(err) if schema.is_some() && matches!(err.status, Some(400 | 422)) => {
+            let options = ChatOptions {
+                schema: None,
+                ..options
+            };
+            send_chat(&client, provider.as_ref(), &prompt, options, sink).await
+        }
+        result => result,
+    }?;
+    // 按响应中实际提供服务的模型记录用量（Azure 配置的是部署名，OpenAI 可能是别名），
+    // 接口未返回模型时退回到配置的模型
+    if let Some(usage) = usage {
+        let model = completion.model.as_deref().unwrap_or(&configured_model);
+        usage.record(model, completion.usage);
+    }
+    if let Some((policy, key)) = &cache {
+        // 缓存写入失败只影响下次是否命中，不影响本次结果
+        let _ = policy.cache.put(key, &completion.text);
+    }
+    Ok(completion)
+}
+
+async fn send_chat(
+    client: &reqwest::Client,
+    provider: &dyn AiProvider,
+    prompt: &str,
+    options: ChatOptions<'_>,
+    sink: Option<&dyn StreamSink>,
+) -> AppResult<Completion> {
+    let response = provider
+        .request(client, prompt, options)
         .send()
         .await
-        .map_err(|e| format!("网络请求失败: {}", e))?;
+        .map_err(AppError::network)?;
 
     let status = response.status();
-    let response_text = response.text().await.map_err(|e| format!("读取响应失败: {}", e))?;
-
-    if !status.is_success() {
-        return Err(format!("API 请求失败 ({}): {}", status, response_text));
+    let stream_content_type = provider.stream_format().content_type();
+    let is_stream = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with(stream_content_type));
+
+    if let Some(sink) = sink.filter(|_| status.is_success() && is_stream) {
+        return read_chat_stream(response, provider, sink).await;
     }
 
-    let chat_response: ChatResponse = serde_json::from_str(&response_text)
-        .map_err(|e| format!("解析响应失败: {}", e))?;
+    let response_text = response.text().await.map_err(AppError::network)?;
 
-    if let Some(error) = chat_response.error {
-        return Err(format!("API 错误: {}", error.message));
+    if !status.is_success() {
+        return Err(AppError::http(
+            status,
+            format!("API 请求失败 ({}): {}", status, response_text),
+        ));
     }
 
-    if let Some(choices) = chat_response.choices {
-        if let Some(choice) = choices.first() {
-            if let Some(message) = &choice.message {
-                return Ok(message.content.clone());
-            }
-        }
+    let completion = provider.parse_response(&response_text)?;
+    // 不支持流式输出的接口忽略 stream 参数，整段作为一次增量推送
+    if let Some(sink) = sink {
+        sink.delta(&completion.text);
     }
+    Ok(completion)
+}
+
+// 记录 AI 用量时的调用来源
+#[derive(Clone, Copy)]
+struct UsageContext<'a> {
+    log: &'a UsageStore,
+    command: &'a str,
+    record_id: Option<&'a str>,
+    user: Option<&'a str>,
+}
 
-    Err("API 响应格式错误".to_string())
+impl UsageContext<'_> {
+    // 记录一次接口调用消耗的 token 用量，model 为本次调用实际使用的模型
+    fn record(&self, model: &str, usage: TokenUsage) {
+        // 用量统计失败不影响 AI 调用结果
+        let _ = self.log.record(NewUsage {
+            command: self.command,
+            model,
+            record_id: self.record_id,
+            user: self.user,
+            usage,
+        });
+    }
 }
 
+// 带 request_id 时以流式输出推送到发起请求的窗口（ai-stream 事件），结束时推送最终结果；
+// 请求进行中可用同一个 request_id 调用 cancel_ai_request 取消
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn optimize_answer_with_ai(
+    window: tauri::Window,
+    store: State<'_, SecretStore>,
+    prompts: State<'_, PromptStore>,
+    requests: State<'_, AiRequests>,
+    usage_log: State<'_, UsageStore>,
+    cache: State<'_, AiCache>,
+    answer: String,
+    context: Option<String>,
+    request_id: Option<String>,
+    record_id: Option<String>,
+    user: Option<String>,
+    bypass_cache: Option<bool>,
+) -> AppResult<OptimizationResult> {
+    let template = prompts.active(PromptKind::Optimize)?;
+    let compress_template = prompts.active(PromptKind::Compress)?;
+    let sink = request_id.clone().map(|id| WindowSink::new(window, id));
+    let usage = UsageContext {
+        log: &usage_log,
+        command: "optimize_answer_with_ai",
+        record_id: record_id.as_deref(),
+        user: user.as_deref(),
+    };
+    let result = requests
+        .run(
+            request_id.as_deref(),
+            optimize_answer(
+                &store,
+                &template,
+                &compress_template,
+                answer,
+                context,
+                Some(CachePolicy {
+                    cache: &cache,
+                    bypass: bypass_cache.unwrap_or(false),
+                }),
+                Some(usage),
+                sink_ref(&sink),
+            ),
+        )
+        .await;
+    if let Some(sink) = &sink {
+        sink.finish(&result);
+    }
+    result
+}
+
+// 优化结果超出字数上限时最多要求 AI 压缩的次数
+const MAX_COMPRESS_ATTEMPTS: usize = 2;
+
+// AI 优化结果，字数均按字符计
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OptimizationResult {
+    #[serde(flatten)]
+    pub report: OptimizationReport,
+    pub original_length: usize,
+    pub reply_length: usize,
+    pub max_length: usize, // 原回复字数的 150%
+    pub attempts: usize,   // 调用 AI 的次数，大于 1 表示经过压缩
+    pub structured: bool,
+    pub template: TemplateRef,
+    pub usage: TokenUsage, // 包括压缩在内所有调用的用量
+    pub cached: bool,      // 所有调用都命中了本地缓存
+}
+
+// AI 审核结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReviewResult {
+    #[serde(flatten)]
+    pub report: ReviewReport,
+    pub structured: bool, // false 表示接口未返回 JSON，由文本解析得到
+    pub raw_text: String, // AI 原始回复
+    pub template: TemplateRef,
+    pub usage: TokenUsage,
+    pub cached: bool,
+}
+
+// AI 风险检测结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RiskResult {
+    #[serde(flatten)]
+    pub report: RiskReport,
+    pub structured: bool,
+    pub template: TemplateRef,
+    pub usage: TokenUsage,
+    pub cached: bool,
+}
+
+fn sink_ref(sink: &Option<WindowSink>) -> Option<&dyn StreamSink> {
+    sink.as_ref().map(|s| s as &dyn StreamSink)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn optimize_answer(
+    store: &SecretStore,
+    template: &PromptTemplate,
+    compress_template: &PromptTemplate,
     answer: String,
     context: Option<String>,
-) -> Result<String, String> {
+    cache: Option<CachePolicy<'_>>,
+    usage_context: Option<UsageContext<'_>>,
+    sink: Option<&dyn StreamSink>,
+) -> AppResult<OptimizationResult> {
     let context_str = context.unwrap_or_default();
-    
+
     // 计算原回复字数（中文字符数）
     let original_char_count = answer.chars().count();
     let max_char_count = (original_char_count as f64 * 1.5) as usize;
-    
-    let prompt = format!(
-        r#"你是一位专业的客服回复优化专家。这是一项"保守编辑任务（Conservative Editing）"，不是重写。
-
-【核心原则】
-1. 保持原有结论与核心语义不变
-2. 仅优化表达、语气、专业边界
-3. 禁止无理由改变原回复结论
-4. 只有在以下情况允许纠正原回复：
-   - 明显事实错误
-   - 食品/营养专业不严谨
-   - 合规或误导风险
-   如纠正，必须在【内部优化说明】中标注"纠正原因"
-
-【字数限制（严格）】
-- 原回复字数：{} 字
-- 优化后回复字数上限：{} 字（不超过原字数的150%）
-- 超出上限将被拒绝，请务必控制字数
-
-上下文信息：
-{}
-
-原始客服回复：
-{}
-
-请按照以下格式输出：
-【最终客服回复】
-<优化后的客服回复内容（字数≤{}字）>
-
-【内部优化说明】
-<优化说明，包括优化点、改进原因等>
-<如纠正原回复，必须标注"纠正原因：<具体原因>">
-
-要求：
-1. 保持原意的准确性（核心语义不变）
-2. 语言更加专业和友好
-3. 结构清晰，易于理解
-4. 符合客服场景的语气要求
-5. 严格控制在{}字以内"#,
-        original_char_count, max_char_count, context_str, answer, max_char_count, max_char_count
-    );
 
-    let result = call_ai_api(prompt).await?;
-    
-    // 后处理：检查字数是否超出限制
-    if let Some(answer_section) = result.find("【最终客服回复】") {
-        let answer_start = answer_section + "【最终客服回复】".len();
-        let answer_end = result[answer_start..].find("【内部优化说明】")
-            .map(|i| answer_start + i)
-            .unwrap_or(result.len());
-        let optimized_answer = result[answer_start..answer_end].trim();
-        let optimized_char_count = optimized_answer.chars().count();
-        
-        if optimized_char_count > max_char_count {
-            return Err(format!(
-                "优化后回复字数（{}字）超出限制（{}字），超出{}%。请压缩内容或重新优化。",
-                optimized_char_count,
+    let prompt = template.render(&[
+        ("original_length", &original_char_count.to_string()),
+        ("max_length", &max_char_count.to_string()),
+        ("context", &context_str),
+        ("answer", &answer),
+    ])?;
+
+    let schema = ai_output::optimization_schema();
+    let lookup = cache.map(|policy| (policy, template.reference()));
+    let completion = call_ai_api(store, prompt, Some(&schema), lookup, usage_context, sink).await?;
+    let (mut report, structured) = ai_output::parse_optimization(&completion.text)?;
+    let mut usage = completion.usage;
+    let mut cached = completion.cached;
+
+    // 超出字数上限时要求 AI 压缩，压缩过程不再推送流式输出
+    let mut attempts = 1;
+    let mut reply_char_count = report.final_reply.chars().count();
+    while reply_char_count > max_char_count {
+        if attempts > MAX_COMPRESS_ATTEMPTS {
+            return Err(AppError::ai(format!(
+                "优化后回复字数（{}字）超出限制（{}字），超出{}%，压缩 {} 次后仍未达标。请精简原回复或重新优化。",
+                reply_char_count,
                 max_char_count,
-                ((optimized_char_count as f64 / max_char_count as f64 - 1.0) * 100.0) as usize
-            ));
+                ((reply_char_count as f64 / max_char_count as f64 - 1.0) * 100.0) as usize,
+                MAX_COMPRESS_ATTEMPTS
+            )));
         }
+        let prompt = compress_template.render(&[
+            ("reply_length", &reply_char_count.to_string()),
+            ("max_length", &max_char_count.to_string()),
+            ("context", &context_str),
+            ("reply", &report.final_reply),
+        ])?;
+        let lookup = cache.map(|policy| (policy, compress_template.reference()));
+        let compressed = call_ai_api(store, prompt, None, lookup, usage_context, None).await?;
+        usage += compressed.usage;
+        cached &= compressed.cached;
+        report.final_reply = ai_output::parse_optimization(&compressed.text)?
+            .0
+            .final_reply;
+        reply_char_count = report.final_reply.chars().count();
+        attempts += 1;
     }
-    
-    Ok(result)
+
+    Ok(OptimizationResult {
+        report,
+        original_length: original_char_count,
+        reply_length: reply_char_count,
+        max_length: max_char_count,
+        attempts,
+        structured,
+        template: template.reference(),
+        usage,
+        cached,
+    })
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn review_answer_with_ai(
+    window: tauri::Window,
+    store: State<'_, SecretStore>,
+    prompts: State<'_, PromptStore>,
+    requests: State<'_, AiRequests>,
+    usage_log: State<'_, UsageStore>,
+    cache: State<'_, AiCache>,
     answer: String,
     context: Option<String>,
-) -> Result<String, String> {
-    let context_str = context.unwrap_or_default();
-    let prompt = format!(
-        r#"你是一位专业的客服回复审核专家。请审核以下客服回复，判断其是否合理、专业、准确。
+    request_id: Option<String>,
+    record_id: Option<String>,
+    user: Option<String>,
+    bypass_cache: Option<bool>,
+) -> AppResult<ReviewResult> {
+    let template = prompts.active(PromptKind::Review)?;
+    let sink = request_id.clone().map(|id| WindowSink::new(window, id));
+    let policy = CachePolicy {
+        cache: &cache,
+        bypass: bypass_cache.unwrap_or(false),
+    };
+    let usage = UsageContext {
+        log: &usage_log,
+        command: "review_answer_with_ai",
+        record_id: record_id.as_deref(),
+        user: user.as_deref(),
+    };
+    let result = requests
+        .run(
+            request_id.as_deref(),
+            review_answer(
+                &store,
+                &template,
+                answer,
+                context,
+                Some(policy),
+                Some(usage),
+                sink_ref(&sink),
+            ),
+        )
+        .await;
+    if let Some(sink) = &sink {
+        sink.finish(&result);
+    }
+    result
+}
 
-上下文信息：
-{}
+async fn review_answer(
+    store: &SecretStore,
+    template: &PromptTemplate,
+    answer: String,
+    context: Option<String>,
+    cache: Option<CachePolicy<'_>>,
+    usage: Option<UsageContext<'_>>,
+    sink: Option<&dyn StreamSink>,
+) -> AppResult<ReviewResult> {
+    let context_str = context.unwrap_or_default();
+    let prompt = template.render(&[("context", &context_str), ("answer", &answer)])?;
+
+    let schema = ai_output::review_schema();
+    let lookup = cache.map(|policy| (policy, template.reference()));
+    let completion = call_ai_api(store, prompt, Some(&schema), lookup, usage, sink).await?;
+    let (report, structured) = ai_output::parse_review(&completion.text)?;
+    Ok(ReviewResult {
+        report,
+        structured,
+        raw_text: completion.text,
+        template: template.reference(),
+        usage: completion.usage,
+        cached: completion.cached,
+    })
+}
 
-待审核的客服回复：
-{}
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn check_answer_risk(
+    window: tauri::Window,
+    store: State<'_, SecretStore>,
+    prompts: State<'_, PromptStore>,
+    requests: State<'_, AiRequests>,
+    usage_log: State<'_, UsageStore>,
+    cache: State<'_, AiCache>,
+    answer: String,
+    request_id: Option<String>,
+    record_id: Option<String>,
+    user: Option<String>,
+    bypass_cache: Option<bool>,
+) -> AppResult<RiskResult> {
+    let template = prompts.active(PromptKind::Risk)?;
+    let sink = request_id.clone().map(|id| WindowSink::new(window, id));
+    let policy = CachePolicy {
+        cache: &cache,
+        bypass: bypass_cache.unwrap_or(false),
+    };
+    let usage = UsageContext {
+        log: &usage_log,
+        command: "check_answer_risk",
+        record_id: record_id.as_deref(),
+        user: user.as_deref(),
+    };
+    let result = requests
+        .run(
+            request_id.as_deref(),
+            check_risk(
+                &store,
+                &template,
+                answer,
+                Some(policy),
+                Some(usage),
+                sink_ref(&sink),
+            ),
+        )
+        .await;
+    if let Some(sink) = &sink {
+        sink.finish(&result);
+    }
+    result
+}
 
-请按照以下格式输出：
-【审核结论】= 合理 / 基本合理 / 需修改
+async fn check_risk(
+    store: &SecretStore,
+    template: &PromptTemplate,
+    answer: String,
+    cache: Option<CachePolicy<'_>>,
+    usage: Option<UsageContext<'_>>,
+    sink: Option<&dyn StreamSink>,
+) -> AppResult<RiskResult> {
+    let prompt = template.render(&[("answer", &answer)])?;
+
+    let schema = ai_output::risk_schema();
+    let lookup = cache.map(|policy| (policy, template.reference()));
+    let completion = call_ai_api(store, prompt, Some(&schema), lookup, usage, sink).await?;
+    let (report, structured) = ai_output::parse_risk(&completion.text)?;
+    Ok(RiskResult {
+        report,
+        structured,
+        template: template.reference(),
+        usage: completion.usage,
+        cached: completion.cached,
+    })
+}
 
-【专业判断说明】
-<详细说明专业判断的理由，包括回复的准确性、专业性、友好度等方面的评估>
+// 提示词模板：每个 AI 功能当前生效的模板
+#[tauri::command]
+pub async fn list_prompt_templates(
+    prompts: State<'_, PromptStore>,
+) -> AppResult<Vec<PromptTemplate>> {
+    PromptKind::ALL
+        .into_iter()
+        .map(|kind| prompts.active(kind))
+        .collect()
+}
 
-【潜在风险或注意点】
-<列出可能存在的风险、问题或需要注意的地方>
+#[tauri::command]
+pub async fn get_prompt_template_history(
+    prompts: State<'_, PromptStore>,
+    kind: PromptKind,
+) -> AppResult<Vec<PromptTemplate>> {
+    prompts.history(kind)
+}
 
-【修改建议】（仅在"需修改"或"基本合理但可优化"时提供）
-<具体的修改建议>
+// 校验占位符后保存为新版本，立即生效
+#[tauri::command]
+pub async fn save_prompt_template(
+    prompts: State<'_, PromptStore>,
+    kind: PromptKind,
+    body: String,
+    author: Option<String>,
+) -> AppResult<PromptTemplate> {
+    prompts.save(kind, &body, author.as_deref())
+}
 
-【需修改原因】（仅在"需修改"时提供）
-<详细说明为什么需要修改，指出具体的问题>
+#[tauri::command]
+pub async fn reset_prompt_template(
+    prompts: State<'_, PromptStore>,
+    kind: PromptKind,
+    author: Option<String>,
+) -> AppResult<PromptTemplate> {
+    prompts.reset(kind, author.as_deref())
+}
 
-【修改后推荐回复】（仅在"需修改"时提供）
-<提供修改后的推荐回复内容>
+// 批量审核：对表中所有启用的答案执行 AI 审核和风险检测，在后台运行，
+// 进度以 batch-review-progress 事件推送到发起任务的窗口，结果保存在本地
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn start_batch_review(
+    window: tauri::Window,
+    client: State<'_, FeishuClient>,
+    cache: State<'_, AnswerCache>,
+    mappings: State<'_, FieldMappingStore>,
+    schemas: State<'_, SchemaCache>,
+    batches: State<'_, BatchStore>,
+    runner: State<'_, BatchRunner>,
+    app_token: String,
+    table_id: String,
+    offline: Option<bool>,
+    concurrency: Option<usize>,
+    user: Option<String>,
+) -> AppResult<BatchJob> {
+    let answers = load_answers(
+        &client,
+        &cache,
+        &mappings,
+        &schemas,
+        &app_token,
+        &table_id,
+        offline.unwrap_or(false),
+    )
+    .await?
+    .answers;
+    let items: Vec<BatchItem> = answers
+        .into_iter()
+        .filter(|answer| is_enabled(&answer.enable_status))
+        .map(batch_item)
+        .collect();
+    if items.is_empty() {
+        return Err(AppError::invalid_input("没有状态为启用的答案"));
+    }
 
-【修改依据（专家原则）】
-<说明修改依据的专业原则和标准>
+    let job = batches.create_job(&app_token, &table_id, &items)?;
+    let running = runner.register(job.id)?;
+    spawn_batch_review(
+        window,
+        running,
+        job.clone(),
+        concurrency.unwrap_or(DEFAULT_CONCURRENCY),
+        user,
+    );
+    Ok(job)
+}
 
-要求：
-1. 严格审核回复的准确性和专业性
-2. 识别潜在的风险和问题
-3. 如需修改，必须提供明确的修改原因和推荐回复
-4. 判断要客观、专业"#,
-        context_str, answer
+// 继续暂停或中断的任务，并重试审核出错的条目
+#[tauri::command]
+pub async fn resume_batch_review(
+    window: tauri::Window,
+    batches: State<'_, BatchStore>,
+    runner: State<'_, BatchRunner>,
+    job_id: i64,
+    concurrency: Option<usize>,
+    user: Option<String>,
+) -> AppResult<BatchJob> {
+    let job = batches.job(job_id)?;
+    if job.status == BatchStatus::Completed && job.failed == 0 {
+        return Ok(job);
+    }
+    let running = runner.register(job_id)?;
+    spawn_batch_review(
+        window,
+        running,
+        job.clone(),
+        concurrency.unwrap_or(DEFAULT_CONCURRENCY),
+        user,
     );
+    Ok(job)
+}
 
-    call_ai_api(prompt).await
+// 暂停任务：进行中的审核完成后停止。任务不在运行时返回 false
+#[tauri::command]
+pub async fn pause_batch_review(runner: State<'_, BatchRunner>, job_id: i64) -> AppResult<bool> {
+    Ok(runner.pause(job_id))
 }
 
+// 某张表的批量审核任务，最新的在前
 #[tauri::command]
-pub async fn check_answer_risk(
-    answer: String,
-) -> Result<HashMap<String, serde_json::Value>, String> {
-    let prompt = format!(
-        r#"你是一位专业的风险检测专家。请快速检测以下客服回复是否存在风险。
-
-待检测的客服回复：
-{}
-
-请按照以下格式输出（必须严格遵循）：
-RISK = YES / NO
-REASON = 一句话原因说明
-
-要求：
-1. 如果存在风险（如误导、错误信息、不当表述等），输出 RISK = YES
-2. 如果无风险，输出 RISK = NO
-3. REASON 必须是一句话简要说明原因
-4. 只输出这两行，不要有其他内容"#,
-        answer
-    );
+pub async fn list_batch_reviews(
+    batches: State<'_, BatchStore>,
+    app_token: String,
+    table_id: String,
+) -> AppResult<Vec<BatchJob>> {
+    batches.jobs(&app_token, &table_id)
+}
 
-    let result = call_ai_api(prompt).await?;
-    
-    let mut response = HashMap::new();
-    let mut has_risk = false;
-    let mut reason = String::new();
-
-    for line in result.lines() {
-        let line = line.trim();
-        if line.starts_with("RISK") {
-            if line.contains("YES") || line.contains("yes") || line.contains("Yes") {
-                has_risk = true;
-            }
-        } else if line.starts_with("REASON") {
-            if let Some(colon_idx) = line.find('=') {
-                reason = line[colon_idx + 1..].trim().to_string();
-            }
-        }
+// 任务的审核结果，默认只返回需修改或存在风险（RISK=YES）的答案
+#[tauri::command]
+pub async fn get_batch_review_results(
+    batches: State<'_, BatchStore>,
+    job_id: i64,
+    flagged_only: Option<bool>,
+) -> AppResult<Vec<BatchItemResult>> {
+    batches.results(job_id, flagged_only.unwrap_or(true))
+}
+
+// 状态为启用的答案，与答案列表的默认过滤一致
+fn is_enabled(status: &str) -> bool {
+    matches!(
+        status.trim().to_lowercase().as_str(),
+        "启用"
+            | "enable"
+            | "enabled"
+            | "true"
+            | "1"
+            | "是"
+            | "yes"
+            | "已启用"
+            | "启用中"
+            | "active"
+    )
+}
+
+fn batch_item(answer: Answer) -> BatchItem {
+    BatchItem {
+        context: format!(
+            "问题：{}\n产品：{}\n场景：{}\n语气：{}",
+            answer.question, answer.product_name, answer.scene, answer.tone
+        ),
+        record_id: answer.record_id,
+        question: answer.question,
+        answer: answer.standard_answer,
     }
+}
 
-    response.insert("hasRisk".to_string(), serde_json::json!(has_risk));
-    response.insert("reason".to_string(), serde_json::json!(reason));
+// 在后台运行任务。任务因错误停止时，以带 error 的进度事件通知窗口，
+// 进度为最近一次推送的状态（snapshot 为启动时的任务状态）
+fn spawn_batch_review(
+    window: tauri::Window,
+    job: RunningJob,
+    snapshot: BatchJob,
+    concurrency: usize,
+    user: Option<String>,
+) {
+    tauri::async_runtime::spawn(async move {
+        let app = window.app_handle();
+        let last = Mutex::new(snapshot);
+        let emit = |job: BatchJob, error: Option<AppError>| {
+            // 窗口已关闭时推送失败，忽略即可
+            let event = BatchReviewEvent { job, error };
+            let _ = window.emit_to(window.label(), BATCH_REVIEW_EVENT, event);
+        };
+        let progress = |job: &BatchJob| {
+            *last.lock().unwrap() = job.clone();
+            emit(job.clone(), None);
+        };
+        let result = run_batch_review(
+            &app.state::<SecretStore>(),
+            &app.state::<PromptStore>(),
+            &app.state::<BatchStore>(),
+            &app.state::<UsageStore>(),
+            &app.state::<AiCache>(),
+            &job,
+            concurrency,
+            user.as_deref(),
+            progress,
+        )
+        .await;
+        if let Err(e) = result {
+            let mut job = last.into_inner().unwrap();
+            job.status = BatchStatus::Paused;
+            emit(job, Some(e));
+        }
+    });
+}
 
-    Ok(response)
+// 批量审核的实现（不依赖 Tauri State，便于测试）
+#[allow(clippy::too_many_arguments)]
+async fn run_batch_review(
+    store: &SecretStore,
+    prompts: &PromptStore,
+    batches: &BatchStore,
+    usage_log: &UsageStore,
+    cache: &AiCache,
+    job: &RunningJob,
+    concurrency: usize,
+    user: Option<&str>,
+    progress: impl Fn(&BatchJob),
+) -> AppResult<BatchJob> {
+    let review_template = prompts.active(PromptKind::Review)?;
+    let risk_template = prompts.active(PromptKind::Risk)?;
+    let policy = CachePolicy {
+        cache,
+        bypass: false,
+    };
+    let review = |item: BatchItem| {
+        let (review_template, risk_template) = (&review_template, &risk_template);
+        async move {
+            // 审核和风险检测各记录一条用量，命中缓存的不记录
+            let usage = UsageContext {
+                log: usage_log,
+                command: "batch_review",
+                record_id: Some(item.record_id.as_str()),
+                user,
+            };
+            let review = review_answer(
+                store,
+                review_template,
+                item.answer.clone(),
+                Some(item.context),
+                Some(policy),
+                Some(usage),
+                None,
+            )
+            .await?;
+            let risk = check_risk(
+                store,
+                risk_template,
+                item.answer,
+                Some(policy),
+                Some(usage),
+                None,
+            )
+            .await?;
+            Ok((review.report, risk.report))
+        }
+    };
+    review_batch::run_job(batches, job, concurrency, review, progress).await
 }
 
+// 未传入 provider 时沿用已保存的接口类型和 api_version（旧版设置页只提交前三项）
 #[tauri::command]
 pub async fn set_ai_config(
+    store: State<'_, SecretStore>,
     api_key: String,
     api_base: String,
     model: String,
-) -> Result<String, String> {
+    provider: Option<AiProviderKind>,
+    api_version: Option<String>,
+) -> AppResult<String> {
+    let saved = store.ai_config();
+    let (provider, api_version) = match provider {
+        Some(provider) => (provider, api_version),
+        None => saved
+            .map(|c| (c.provider, c.api_version))
+            .unwrap_or_default(),
+    };
     let config = AiConfig {
-        api_key,
-        api_base,
-        model,
+        provider,
+        api_key: api_key.trim().to_string(),
+        api_base: api_base.trim().to_string(),
+        model: model.trim().to_string(),
+        api_version: api_version.filter(|v| !v.trim().is_empty()),
     };
-    *AI_CONFIG.lock().unwrap() = Some(config);
+    validate_ai_config(&config)?;
+    store.set_ai_config(config)?;
     Ok("AI 配置已保存".to_string())
 }
 
+fn validate_ai_config(config: &AiConfig) -> AppResult<()> {
+    if config.api_base.is_empty() {
+        return Err(AppError::invalid_input("请填写 API 地址"));
+    }
+    if config.model.is_empty() {
+        return Err(AppError::invalid_input(match config.provider {
+            AiProviderKind::Azure => "请填写部署名称",
+            _ => "请填写模型名称",
+        }));
+    }
+    if config.api_key.is_empty() && config.provider != AiProviderKind::Ollama {
+        return Err(AppError::invalid_input("请填写 API Key"));
+    }
+    Ok(())
+}
+
 #[tauri::command]
-pub async fn get_ai_config() -> Result<Option<AiConfig>, String> {
-    Ok(AI_CONFIG.lock().unwrap().clone())
+pub async fn get_ai_config(store: State<'_, SecretStore>) -> AppResult<Option<AiConfig>> {
+    Ok(store.ai_config())
+}
+
+// 旧版本把凭证明文保存在 localStorage，前端启动时调用一次导入，成功后由前端清除
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LegacySecretsMigration {
+    pub feishu_imported: bool,
+    pub ai_imported: bool,
 }
 
 #[tauri::command]
-pub async fn test_feishu_connection(app_id: String, app_secret: String) -> Result<String, String> {
-    if app_id.is_empty() || app_secret.is_empty() {
-        return Err("请先填写 App ID 和 App Secret".to_string());
+pub async fn migrate_legacy_secrets(
+    store: State<'_, SecretStore>,
+    client: State<'_, FeishuClient>,
+    app_id: Option<String>,
+    app_secret: Option<String>,
+    ai_api_key: Option<String>,
+    ai_api_base: Option<String>,
+    ai_model: Option<String>,
+) -> AppResult<LegacySecretsMigration> {
+    let non_empty = |v: Option<String>| v.filter(|s| !s.trim().is_empty());
+
+    // 已有持久化配置时不覆盖，只做一次性导入
+    let mut feishu_imported = false;
+    if store.feishu_credentials().is_none() {
+        if let (Some(app_id), Some(app_secret)) = (non_empty(app_id), non_empty(app_secret)) {
+            let creds = FeishuCredentials { app_id, app_secret };
+            store.set_feishu_credentials(creds.clone())?;
+            client.set_credentials(creds).await;
+            feishu_imported = true;
+        }
     }
 
-    // 直接请求 access_token 来测试连接
-    let client = reqwest::Client::new();
-    let url = format!("{}/auth/v3/tenant_access_token/internal", FEISHU_API_BASE);
-    let body = serde_json::json!({
-        "app_id": app_id,
-        "app_secret": app_secret,
-    });
+    let mut ai_imported = false;
+    if store.ai_config().is_none() {
+        if let (Some(api_key), Some(api_base), Some(model)) = (
+            non_empty(ai_api_key),
+            non_empty(ai_api_base),
+            non_empty(ai_model),
+        ) {
+            store.set_ai_config(AiConfig {
+                provider: AiProviderKind::OpenAi,
+                api_key,
+                api_base,
+                model,
+                api_version: None,
+            })?;
+            ai_imported = true;
+        }
+    }
 
-    let response = client
-        .post(&url)
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| {
-            // 提供更友好的错误信息
-            let error_msg = e.to_string();
-            if error_msg.contains("timeout") || error_msg.contains("timed out") {
-                "连接失败: 网络请求超时，请检查网络连接".to_string()
-            } else if error_msg.contains("resolve") || error_msg.contains("DNS") {
-                "连接失败: 无法解析域名，请检查网络连接或 DNS 设置".to_string()
-            } else if error_msg.contains("connection refused") || error_msg.contains("Connection refused") {
-                "连接失败: 连接被拒绝，请检查网络连接或防火墙设置".to_string()
-            } else {
-                format!("连接失败: {}", error_msg)
-            }
-        })?;
+    Ok(LegacySecretsMigration {
+        feishu_imported,
+        ai_imported,
+    })
+}
 
-    let token_res: AccessTokenResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("连接失败: 解析响应失败 - {}", e))?;
-
-    if token_res.code != 0 {
-        // 根据错误代码提供更详细的说明
-        let error_msg = match token_res.code {
-            99991663 => format!("连接失败: App ID 无效或不存在 (错误代码: {})", token_res.code),
-            99991664 => format!("连接失败: App Secret 无效或错误 (错误代码: {})", token_res.code),
-            _ => format!("连接失败: {} (错误代码: {})", token_res.msg, token_res.code),
-        };
-        return Err(error_msg);
+#[tauri::command]
+pub async fn test_feishu_connection(
+    client: State<'_, FeishuClient>,
+    app_id: String,
+    app_secret: String,
+    endpoint: Option<FeishuEndpoint>,
+) -> AppResult<String> {
+    if app_id.is_empty() || app_secret.is_empty() {
+        return Err(AppError::invalid_input("请先填写 App ID 和 App Secret"));
     }
 
-    if token_res.tenant_access_token.is_none() {
-        return Err("连接失败: 响应中缺少 tenant_access_token".to_string());
-    }
+    // 直接请求 access_token 来测试连接（不影响已缓存的 token），
+    // 传入 endpoint 时测试尚未保存的地址，否则使用当前配置的地址
+    let base_url = match endpoint {
+        Some(endpoint) => endpoint.normalize()?.base_url(),
+        None => client.base_url(),
+    };
+    let creds = FeishuCredentials { app_id, app_secret };
+    client
+        .request_token(&base_url, &creds)
+        .await
+        .map_err(|e| e.context("连接失败"))?;
 
     Ok("连接测试成功！已成功获取 tenant_access_token".to_string())
 }
 
+// 取消进行中的 AI 请求，原调用方收到 cancelled 错误；请求已结束时返回 false
 #[tauri::command]
-pub async fn test_ai_connection() -> Result<String, String> {
-    // 在 await 之前检查配置
-    {
-        let config_guard = AI_CONFIG.lock().unwrap();
-        if config_guard.is_none() {
-            return Err("请先配置 AI 设置".to_string());
-        }
+pub async fn cancel_ai_request(
+    requests: State<'_, AiRequests>,
+    request_id: String,
+) -> AppResult<bool> {
+    Ok(requests.cancel(&request_id))
+}
+
+#[tauri::command]
+pub async fn test_ai_connection(
+    store: State<'_, SecretStore>,
+    usage_log: State<'_, UsageStore>,
+) -> AppResult<String> {
+    if store.ai_config().is_none() {
+        return Err(AppError::not_configured("请先配置 AI 设置"));
     }
 
     let prompt = "请回复：连接成功".to_string();
-    let result = call_ai_api(prompt).await?;
-    Ok(format!("AI 连接测试成功！模型回复：{}", result))
+    let usage = UsageContext {
+        log: &usage_log,
+        command: "test_ai_connection",
+        record_id: None,
+        user: None,
+    };
+    let result = call_ai_api(&store, prompt, None, None, Some(usage), None).await?;
+    Ok(format!("AI 连接测试成功！模型回复：{}", result.text))
 }
 
+// 清空 AI 响应缓存，返回删除的条数
 #[tauri::command]
-pub async fn get_bitable_record(
-    app_token: String,
-    table_id: String,
-    record_id: String,
-) -> Result<AnswerRecord, String> {
-    let token = get_feishu_access_token().await?;
-    let client = reqwest::Client::new();
-    let url = format!(
-        "{}/bitable/v1/apps/{}/tables/{}/records/{}",
-        FEISHU_API_BASE, app_token, table_id, record_id
-    );
+pub async fn clear_ai_cache(cache: State<'_, AiCache>) -> AppResult<usize> {
+    cache.clear()
+}
 
-    let response = client
-        .get(&url)
-        .header("Authorization", format!("Bearer {}", token))
-        .send()
-        .await
-        .map_err(|e| format!("网络请求失败: {}", e))?;
+// 按日期、用户和模型汇总 AI 用量及费用，since / until 为秒级时间戳
+#[tauri::command]
+pub async fn get_ai_usage(
+    usage_log: State<'_, UsageStore>,
+    since: Option<i64>,
+    until: Option<i64>,
+) -> AppResult<Vec<UsageSummary>> {
+    usage_log.summary(since, until)
+}
 
-    let response_text = response.text().await.map_err(|e| format!("读取响应失败: {}", e))?;
-    
-    let result: serde_json::Value = serde_json::from_str(&response_text)
-        .map_err(|e| format!("解析响应失败: {}", e))?;
+#[tauri::command]
+pub async fn get_model_prices(usage_log: State<'_, UsageStore>) -> AppResult<Vec<ModelPrice>> {
+    usage_log.prices()
+}
 
-    if let Some(code) = result.get("code").and_then(|v| v.as_i64()) {
-        if code != 0 {
-            let msg = result.get("msg")
-                .and_then(|v| v.as_str())
-                .unwrap_or("未知错误");
-            return Err(format!("获取记录失败: {}", msg));
-        }
+// 设置模型价格（每百万 token），价格均为空时删除该模型的价格
+#[tauri::command]
+pub async fn set_model_price(
+    usage_log: State<'_, UsageStore>,
+    model: String,
+    prompt_price: Option<f64>,
+    completion_price: Option<f64>,
+) -> AppResult<Vec<ModelPrice>> {
+    match (prompt_price, completion_price) {
+        (None, None) => usage_log.remove_price(&model)?,
+        (prompt_price, completion_price) => usage_log.set_price(&ModelPrice {
+            model,
+            prompt_price: prompt_price.unwrap_or(0.0),
+            completion_price: completion_price.unwrap_or(0.0),
+        })?,
     }
+    usage_log.prices()
+}
 
-    let record_data = result.get("data")
-        .and_then(|v| v.get("record"))
-        .ok_or("响应中缺少记录数据")?;
-
-    let record: AnswerRecord = serde_json::from_value(record_data.clone())
-        .map_err(|e| format!("解析记录失败: {}", e))?;
+#[tauri::command]
+pub async fn get_bitable_record(
+    client: State<'_, FeishuClient>,
+    app_token: String,
+    table_id: String,
+    record_id: String,
+) -> AppResult<Retried<AnswerRecord>> {
+    let mut retries = 0;
+    let value = client
+        .get_record(&app_token, &table_id, &record_id, &mut retries)
+        .await?;
+    Ok(Retried { value, retries })
+}
 
-    Ok(record)
+// 一次写回：要写入的字段（逻辑字段名或列名）、调用方读取记录时看到的最后修改时间，
+// 以及保存到本地修订记录中的作者和 AI 操作
+struct AnswerUpdate {
+    record_id: String,
+    fields: HashMap<String, serde_json::Value>,
+    expected_modified_time: Option<i64>,
+    revision: RevisionInfo,
+    restored_from: Option<i64>,
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn update_answer_to_feishu(
+    client: State<'_, FeishuClient>,
+    mappings: State<'_, FieldMappingStore>,
+    schemas: State<'_, SchemaCache>,
+    revisions: State<'_, RevisionStore>,
     app_token: String,
     table_id: String,
     record_id: String,
     fields: HashMap<String, serde_json::Value>,
-) -> Result<String, String> {
-    let token = get_feishu_access_token().await?;
-    let client = reqwest::Client::new();
-    let url = format!(
-        "{}/bitable/v1/apps/{}/tables/{}/records/{}",
-        FEISHU_API_BASE, app_token, table_id, record_id
-    );
+    expected_modified_time: Option<i64>,
+    revision: Option<RevisionInfo>,
+) -> AppResult<Retried<Answer>> {
+    let update = AnswerUpdate {
+        record_id,
+        fields,
+        expected_modified_time,
+        revision: revision.unwrap_or_default(),
+        restored_from: None,
+    };
+    update_answer(
+        &client, &mappings, &schemas, &revisions, &app_token, &table_id, update,
+    )
+    .await
+}
 
-    // 构建更新请求体
-    let update_body = serde_json::json!({
-        "fields": fields
-    });
+// 更新记录并返回按字段映射解析后的完整记录。
+// 写入前先重新获取记录：带 expected_modified_time 时已被他人修改则拒绝写入并返回字段差异
+// （飞书没有条件更新接口，校验和写入之间仍有很小的窗口，但能挡住绝大多数覆盖），
+// 然后把被覆盖的旧值保存为一条本地修订，写入失败时再删除这条修订。
+async fn update_answer(
+    client: &FeishuClient,
+    mappings: &FieldMappingStore,
+    schemas: &SchemaCache,
+    revisions: &RevisionStore,
+    app_token: &str,
+    table_id: &str,
+    update: AnswerUpdate,
+) -> AppResult<Retried<Answer>> {
+    // 逻辑字段名转换为表格实际列名
+    let table_mapping = mappings.get(app_token, table_id);
+    let fields = table_mapping.to_columns(update.fields);
+    let record_id = update.record_id.as_str();
+    let mut retries = 0;
+
+    let current = client
+        .get_record(app_token, table_id, record_id, &mut retries)
+        .await
+        .map_err(|e| e.context("更新失败"))?;
+    if let Some(expected) = update.expected_modified_time {
+        if current.last_modified_time != Some(expected) {
+            return Err(AppError::conflict(UpdateConflict {
+                record_id: record_id.to_string(),
+                expected_modified_time: Some(expected),
+                current_modified_time: current.last_modified_time,
+                fields: diff_fields(&current.fields, &fields),
+            }));
+        }
+    }
 
-    let response = client
-        .put(&url)
-        .header("Authorization", format!("Bearer {}", token))
-        .header("Content-Type", "application/json")
-        .json(&update_body)
-        .send()
+    // 只保存本次写入的列，写入前为空的列记为 null，恢复时会清空它
+    let old_fields: HashMap<String, serde_json::Value> = fields
+        .keys()
+        .map(|name| {
+            let old = current
+                .fields
+                .get(name)
+                .cloned()
+                .unwrap_or(serde_json::Value::Null);
+            (name.clone(), old)
+        })
+        .collect();
+    let revision_id = revisions.insert(NewRevision {
+        app_token,
+        table_id,
+        record_id,
+        old_fields: &old_fields,
+        new_fields: &fields,
+        info: &update.revision,
+        restored_from: update.restored_from,
+    })?;
+
+    let updated = match client
+        .update_record(app_token, table_id, record_id, &fields, &mut retries)
         .await
-        .map_err(|e| format!("网络请求失败: {}", e))?;
+    {
+        Ok(updated) => updated,
+        Err(e) => {
+            revisions.remove(revision_id)?;
+            return Err(e);
+        }
+    };
+    // 更新接口只返回本次写入的字段，重新获取完整记录；获取失败时退回到更新结果
+    let record = match client
+        .get_record(app_token, table_id, record_id, &mut retries)
+        .await
+    {
+        Ok(record) => record,
+        Err(_) => updated,
+    };
+    let mapping = table_mapping.mapping;
+    let value = normalize_record(
+        client,
+        schemas,
+        &mapping,
+        app_token,
+        table_id,
+        record,
+        &mut retries,
+    )
+    .await;
+    Ok(Retried { value, retries })
+}
 
-    let status = response.status();
-    let response_text = response.text().await.map_err(|e| format!("读取响应失败: {}", e))?;
+// 某条记录的本地修订历史，最新的在前
+#[tauri::command]
+pub async fn list_answer_revisions(
+    revisions: State<'_, RevisionStore>,
+    app_token: String,
+    table_id: String,
+    record_id: String,
+) -> AppResult<Vec<Revision>> {
+    revisions.list(&app_token, &table_id, &record_id)
+}
 
-    if !status.is_success() {
-        return Err(format!("更新失败 ({}): {}", status, response_text));
-    }
+// 把记录恢复到某条修订写入之前的内容，恢复本身也会生成一条新的修订。
+// 记录中这些列已不是该修订写入的值（之后被他人或其他修订改过）时拒绝恢复并返回字段差异
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn restore_answer_revision(
+    client: State<'_, FeishuClient>,
+    mappings: State<'_, FieldMappingStore>,
+    schemas: State<'_, SchemaCache>,
+    revisions: State<'_, RevisionStore>,
+    app_token: String,
+    table_id: String,
+    revision_id: i64,
+    author: Option<String>,
+) -> AppResult<Retried<Answer>> {
+    restore_revision(
+        &client,
+        &mappings,
+        &schemas,
+        &revisions,
+        &app_token,
+        &table_id,
+        revision_id,
+        author,
+    )
+    .await
+}
 
-    // 解析响应
-    let result: serde_json::Value = serde_json::from_str(&response_text)
-        .map_err(|e| format!("解析响应失败: {}", e))?;
+#[allow(clippy::too_many_arguments)]
+async fn restore_revision(
+    client: &FeishuClient,
+    mappings: &FieldMappingStore,
+    schemas: &SchemaCache,
+    revisions: &RevisionStore,
+    app_token: &str,
+    table_id: &str,
+    revision_id: i64,
+    author: Option<String>,
+) -> AppResult<Retried<Answer>> {
+    let revision = revisions
+        .get(revision_id)?
+        .filter(|r| r.app_token == app_token && r.table_id == table_id)
+        .ok_or_else(|| AppError::invalid_input(format!("修订不存在: {}", revision_id)))?;
+    let mut retries = 0;
+    let schema = match schemas.get(app_token, table_id) {
+        Some(schema) => schema,
+        None => load_table_schema(schemas, client, app_token, table_id, &mut retries).await?,
+    };
+    let types = field_types(&schema);
+    // 修订中保存的是读取接口返回的值，按字段类型转换为写入格式
+    let fields = write_fields(&revision.old_fields, &types);
 
-    if let Some(code) = result.get("code").and_then(|v| v.as_i64()) {
-        if code != 0 {
-            let msg = result.get("msg")
-                .and_then(|v| v.as_str())
-                .unwrap_or("未知错误");
-            return Err(format!("更新失败: {}", msg));
-        }
+    let current = client
+        .get_record(app_token, table_id, &revision.record_id, &mut retries)
+        .await
+        .map_err(|e| e.context("恢复失败"))?;
+    let current_fields: HashMap<String, serde_json::Value> = revision
+        .new_fields
+        .keys()
+        .map(|name| {
+            let value = current
+                .fields
+                .get(name)
+                .cloned()
+                .unwrap_or(serde_json::Value::Null);
+            (name.clone(), value)
+        })
+        .collect();
+    if write_fields(&current_fields, &types) != write_fields(&revision.new_fields, &types) {
+        return Err(AppError::conflict(UpdateConflict {
+            record_id: revision.record_id,
+            expected_modified_time: None,
+            current_modified_time: current.last_modified_time,
+            fields: diff_fields(&current.fields, &fields),
+        }));
     }
 
-    Ok("更新成功".to_string())
+    let update = AnswerUpdate {
+        record_id: revision.record_id,
+        fields,
+        // 校验之后到写入之前的修改仍按最后修改时间拦截
+        expected_modified_time: current.last_modified_time,
+        revision: RevisionInfo {
+            author,
+            ai_action: None,
+        },
+        restored_from: Some(revision_id),
+    };
+    let mut restored = update_answer(
+        client, mappings, schemas, revisions, app_token, table_id, update,
+    )
+    .await?;
+    restored.retries += retries;
+    Ok(restored)
+}
+
+// 把读取接口返回的字段值转换为写入格式，空值写为 null（清空该列）
+fn write_fields(
+    fields: &HashMap<String, serde_json::Value>,
+    types: &HashMap<String, i32>,
+) -> HashMap<String, serde_json::Value> {
+    fields
+        .iter()
+        .map(|(name, value)| {
+            let value = FieldValue::decode(types.get(name).copied(), value)
+                .map(|v| v.to_write_value())
+                .unwrap_or(serde_json::Value::Null);
+            (name.clone(), value)
+        })
+        .collect()
+}
+
+// 本次要写入的字段中，飞书当前值与写入值不同的部分（按解码后的文本比较，忽略富文本等格式差异）
+fn diff_fields(
+    current: &HashMap<String, serde_json::Value>,
+    incoming: &HashMap<String, serde_json::Value>,
+) -> Vec<FieldDiff> {
+    // 空值解码为 None，缺失的字段与写入空值视为相同
+    let texts = |value: Option<&serde_json::Value>| {
+        value
+            .and_then(|v| FieldValue::decode(None, v))
+            .map(|v| v.texts())
+    };
+    let mut diffs: Vec<FieldDiff> = incoming
+        .iter()
+        .filter(|(name, value)| {
+            let existing = current.get(*name);
+            existing != Some(*value) && texts(existing) != texts(Some(*value))
+        })
+        .map(|(name, value)| FieldDiff {
+            field: name.clone(),
+            current: current.get(name).cloned(),
+            incoming: value.clone(),
+        })
+        .collect();
+    diffs.sort_by(|a, b| a.field.cmp(&b.field));
+    diffs
 }
 
 #[tauri::command]
 pub async fn create_answer_to_feishu(
+    client: State<'_, FeishuClient>,
+    mappings: State<'_, FieldMappingStore>,
+    schemas: State<'_, SchemaCache>,
     app_token: String,
     table_id: String,
     fields: HashMap<String, serde_json::Value>,
-) -> Result<String, String> {
-    let token = get_feishu_access_token().await?;
-    let client = reqwest::Client::new();
-    let url = format!(
-        "{}/bitable/v1/apps/{}/tables/{}/records",
-        FEISHU_API_BASE, app_token, table_id
-    );
+) -> AppResult<Retried<Answer>> {
+    create_answer(&client, &mappings, &schemas, &app_token, &table_id, fields).await
+}
 
-    // 构建创建请求体
-    let create_body = serde_json::json!({
-        "fields": fields
-    });
+// 创建记录并返回按字段映射解析后的记录（含新的 record_id）
+async fn create_answer(
+    client: &FeishuClient,
+    mappings: &FieldMappingStore,
+    schemas: &SchemaCache,
+    app_token: &str,
+    table_id: &str,
+    fields: HashMap<String, serde_json::Value>,
+) -> AppResult<Retried<Answer>> {
+    // 逻辑字段名转换为表格实际列名
+    let mapping = mappings.get(app_token, table_id).mapping;
+    let fields = mappings.get(app_token, table_id).to_columns(fields);
+    let mut retries = 0;
+    let record = client
+        .create_record(app_token, table_id, &fields, &mut retries)
+        .await?;
+    let value = normalize_record(
+        client,
+        schemas,
+        &mapping,
+        app_token,
+        table_id,
+        record,
+        &mut retries,
+    )
+    .await;
+    Ok(Retried { value, retries })
+}
 
-    let response = client
-        .post(&url)
-        .header("Authorization", format!("Bearer {}", token))
-        .header("Content-Type", "application/json")
-        .json(&create_body)
-        .send()
-        .await
-        .map_err(|e| format!("网络请求失败: {}", e))?;
+// 与 list_answers 相同的方式解析单条记录，表结构优先读缓存，获取失败时按值的形状推断
+async fn normalize_record(
+    client: &FeishuClient,
+    schemas: &SchemaCache,
+    mapping: &FieldMapping,
+    app_token: &str,
+    table_id: &str,
+    record: AnswerRecord,
+    retries: &mut u32,
+) -> Answer {
+    let schema = match schemas.get(app_token, table_id) {
+        Some(schema) => schema,
+        None => load_table_schema(schemas, client, app_token, table_id, retries)
+            .await
+            .unwrap_or_default(),
+    };
+    to_answer(record, mapping, &field_types(&schema))
+}
 
-    let status = response.status();
-    let response_text = response.text().await.map_err(|e| format!("读取响应失败: {}", e))?;
+#[tauri::command]
+pub async fn delete_answer_from_feishu(
+    client: State<'_, FeishuClient>,
+    app_token: String,
+    table_id: String,
+    record_id: String,
+) -> AppResult<Retried<String>> {
+    let mut retries = 0;
+    client
+        .delete_record(&app_token, &table_id, &record_id, &mut retries)
+        .await?;
+    Ok(Retried {
+        value: "删除成功".to_string(),
+        retries,
+    })
+}
 
-    if !status.is_success() {
-        return Err(format!("创建失败 ({}): {}", status, response_text));
-    }
+#[tauri::command]
+pub async fn batch_create_answers_to_feishu(
+    client: State<'_, FeishuClient>,
+    mappings: State<'_, FieldMappingStore>,
+    app_token: String,
+    table_id: String,
+    records: Vec<HashMap<String, serde_json::Value>>,
+) -> AppResult<BatchResult> {
+    let mapping = mappings.get(&app_token, &table_id);
+    let records: Vec<_> = records
+        .into_iter()
+        .map(|fields| mapping.to_columns(fields))
+        .collect();
+    let mut retries = 0;
+    let results = client
+        .batch_create_records(&app_token, &table_id, &records, &mut retries)
+        .await?;
+    Ok(BatchResult::new(results, retries))
+}
 
-    // 解析响应
-    let result: serde_json::Value = serde_json::from_str(&response_text)
-        .map_err(|e| format!("解析响应失败: {}", e))?;
+#[tauri::command]
+pub async fn batch_update_answers_to_feishu(
+    client: State<'_, FeishuClient>,
+    mappings: State<'_, FieldMappingStore>,
+    app_token: String,
+    table_id: String,
+    records: Vec<BatchUpdateItem>,
+) -> AppResult<BatchResult> {
+    batch_update_answers(&client, &mappings, &app_token, &table_id, records).await
+}
 
-    if let Some(code) = result.get("code").and_then(|v| v.as_i64()) {
-        if code != 0 {
-            let msg = result.get("msg")
-                .and_then(|v| v.as_str())
-                .unwrap_or("未知错误");
-            return Err(format!("创建失败: {}", msg));
-        }
-    }
+async fn batch_update_answers(
+    client: &FeishuClient,
+    mappings: &FieldMappingStore,
+    app_token: &str,
+    table_id: &str,
+    records: Vec<BatchUpdateItem>,
+) -> AppResult<BatchResult> {
+    // 逻辑字段名转换为表格实际列名
+    let mapping = mappings.get(app_token, table_id);
+    let records: Vec<_> = records
+        .into_iter()
+        .map(|item| BatchUpdateItem {
+            record_id: item.record_id,
+            fields: mapping.to_columns(item.fields),
+        })
+        .collect();
+    let mut retries = 0;
+    let results = client
+        .batch_update_records(app_token, table_id, &records, &mut retries)
+        .await?;
+    Ok(BatchResult::new(results, retries))
+}
 
-    Ok("创建成功".to_string())
+#[tauri::command]
+pub async fn batch_delete_answers_from_feishu(
+    client: State<'_, FeishuClient>,
+    app_token: String,
+    table_id: String,
+    record_ids: Vec<String>,
+) -> AppResult<BatchResult> {
+    let mut retries = 0;
+    let results = client
+        .batch_delete_records(&app_token, &table_id, &record_ids, &mut retries)
+        .await?;
+    Ok(BatchResult::new(results, retries))
 }
 
 #[tauri::command]
-pub async fn open_external_url(app: tauri::AppHandle, url: String) -> Result<(), String> {
+pub async fn open_external_url(app: tauri::AppHandle, url: String) -> AppResult<()> {
     use tauri_plugin_shell::ShellExt;
     let shell = app.shell();
-    shell.open(url, None).map_err(|e| format!("打开链接失败: {}", e))?;
+    shell
+        .open(url, None)
+        .map_err(|e| AppError::internal(format!("打开链接失败: {}", e)))?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai_output::ReviewVerdict;
+    use crate::error::ErrorKind;
+    use crate::mock_ai::{MockAi, PROMPT_TOKENS, SERVED_MODEL};
+    use crate::mock_feishu::{MockFeishu, APP_TOKEN, CODE_RECORD_NOT_FOUND};
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    const TABLE: &str = "tblAnswers";
+
+    struct Harness {
+        mock: MockFeishu,
+        client: FeishuClient,
+        cache: AnswerCache,
+        mappings: FieldMappingStore,
+        schemas: SchemaCache,
+        revisions: RevisionStore,
+        _dir: tempfile::TempDir,
+    }
+
+    impl Harness {
+        async fn new() -> Self {
+            let dir = tempfile::tempdir().unwrap();
+            let mock = MockFeishu::start().await;
+            Self {
+                client: mock.client(),
+                cache: AnswerCache::open(dir.path()).unwrap(),
+                mappings: FieldMappingStore::load(dir.path()).unwrap(),
+                schemas: SchemaCache::default(),
+                revisions: RevisionStore::open(dir.path()).unwrap(),
+                mock,
+                _dir: dir,
+            }
+        }
+
+        async fn list(&self, offline: bool) -> AppResult<AnswersResult> {
+            load_answers(
+                &self.client,
+                &self.cache,
+                &self.mappings,
+                &self.schemas,
+                APP_TOKEN,
+                TABLE,
+                offline,
+            )
+            .await
+        }
+
+        async fn update(
+            &self,
+            record_id: &str,
+            fields: HashMap<String, serde_json::Value>,
+            expected_modified_time: Option<i64>,
+        ) -> AppResult<Retried<Answer>> {
+            let update = AnswerUpdate {
+                record_id: record_id.to_string(),
+                fields,
+                expected_modified_time,
+                revision: RevisionInfo {
+                    author: Some("admin".to_string()),
+                    ai_action: Some("AI优化".to_string()),
+                },
+                restored_from: None,
+            };
+            update_answer(
+                &self.client,
+                &self.mappings,
+                &self.schemas,
+                &self.revisions,
+                APP_TOKEN,
+                TABLE,
+                update,
+            )
+            .await
+        }
+
+        async fn sync(&self) -> AppResult<SyncSummary> {
+            sync_table(
+                &self.client,
+                &self.cache,
+                &self.schemas,
+                APP_TOKEN,
+                TABLE,
+                None,
+            )
+            .await
+        }
+
+        fn revisions(&self, record_id: &str) -> Vec<Revision> {
+            self.revisions.list(APP_TOKEN, TABLE, record_id).unwrap()
+        }
+    }
+
+    #[tokio::test]
+    async fn get_answers_data_reads_every_page() {
+        let h = Harness::new().await;
+        h.mock.set_page_size(3);
+        for i in 0..7 {
+            h.mock
+                .add_record(TABLE, json!({ "问题": format!("q{}", i) }));
+        }
+
+        let records = h
+            .client
+            .list_records(APP_TOKEN, TABLE, &mut 0)
+            .await
+            .unwrap();
+
+        assert_eq!(records.len(), 7);
+        let path = format!("GET /apps/{}/tables/{}/records", APP_TOKEN, TABLE);
+        assert_eq!(h.mock.request_count(&path), 3);
+    }
+
+    #[tokio::test]
+    async fn list_answers_skips_records_without_question_or_answer() {
+        let h = Harness::new().await;
+        h.mock
+            .add_record(TABLE, json!({ "问题": "有问有答", "标准回答": "答案" }));
+        h.mock.add_record(TABLE, json!({ "问题": "只有问题" }));
+        h.mock.add_record(TABLE, json!({ "标准回答": "只有回答" }));
+        h.mock
+            .add_record(TABLE, json!({ "问题": "", "标准回答": "空问题" }));
+
+        let result = h.list(false).await.unwrap();
+
+        assert!(!result.from_cache);
+        let questions: Vec<_> = result.answers.iter().map(|a| a.question.as_str()).collect();
+        assert_eq!(questions, vec!["有问有答"]);
+    }
+
+    #[tokio::test]
+    async fn list_answers_decodes_fields_by_schema_type() {
+        let h = Harness::new().await;
+        h.mock.add_field(TABLE, "问题", 1);
+        h.mock.add_field(TABLE, "标准回答", 1);
+        h.mock.add_field(TABLE, "对应产品", 4);
+        h.mock.add_record(
+            TABLE,
+            json!({
+                "问题": [{ "type": "text", "text": "富文本" }, { "type": "text", "text": "问题" }],
+                "标准回答": "答案",
+                "对应产品": ["产品A", "产品B"],
+            }),
+        );
+
+        let result = h.list(false).await.unwrap();
+
+        let answer = &result.answers[0];
+        assert_eq!(answer.question, "富文本问题");
+        assert_eq!(answer.product_names, vec!["产品A", "产品B"]);
+        assert_eq!(answer.product_name, "产品A、产品B");
+    }
+
+    #[tokio::test]
+    async fn list_answers_uses_configured_field_mapping() {
+        let h = Harness::new().await;
+        h.mock.add_field(TABLE, "Question", 1);
+        h.mock.add_field(TABLE, "Answer", 1);
+        let mapping = FieldMapping {
+            question: vec!["Question".to_string()],
+            standard_answer: vec!["Answer".to_string()],
+            ..FieldMapping::default()
+        };
+        let validation = save_field_mapping(
+            &h.client,
+            &h.mappings,
+            &h.schemas,
+            APP_TOKEN,
+            TABLE,
+            mapping,
+        )
+        .await
+        .unwrap()
+        .value;
+        assert_eq!(validation.columns["standard_answer"], "Answer");
+        h.mock
+            .add_record(TABLE, json!({ "Question": "q", "Answer": "a" }));
+
+        let result = h.list(false).await.unwrap();
+
+        assert_eq!(result.answers.len(), 1);
+        assert_eq!(result.answers[0].standard_answer, "a");
+    }
+
+    #[tokio::test]
+    async fn set_field_mapping_rejects_invalid_mapping() {
+        let h = Harness::new().await;
+        h.mock.add_field(TABLE, "Question", 1);
+        h.mock.add_field(TABLE, "Answer", 1);
+        let invalid = [
+            // 必填字段匹配不到任何列
+            FieldMapping {
+                question: vec!["Question".to_string()],
+                ..FieldMapping::default()
+            },
+            // 空白别名
+            FieldMapping {
+                question: vec!["Question".to_string()],
+                standard_answer: vec!["Answer".to_string(), "".to_string()],
+                ..FieldMapping::default()
+            },
+            // 两个字段共用同一列
+            FieldMapping {
+                question: vec!["Question".to_string()],
+                standard_answer: vec!["Question".to_string()],
+                ..FieldMapping::default()
+            },
+        ];
+
+        for mapping in invalid {
+            let err = save_field_mapping(
+                &h.client,
+                &h.mappings,
+                &h.schemas,
+                APP_TOKEN,
+                TABLE,
+                mapping,
+            )
+            .await
+            .unwrap_err();
+            assert_eq!(err.kind, ErrorKind::InvalidInput);
+            assert!(err.message.starts_with("字段映射无效"), "{}", err.message);
+        }
+        // 无效的映射不会被保存
+        assert_eq!(
+            h.mappings.get(APP_TOKEN, TABLE).mapping.question,
+            vec!["问题"]
+        );
+    }
+
+    #[tokio::test]
+    async fn list_answers_falls_back_to_cache_when_upstream_fails() {
+        let h = Harness::new().await;
+        h.mock
+            .add_record(TABLE, json!({ "问题": "q", "标准回答": "a" }));
+        h.list(false).await.unwrap();
+
+        for _ in 0..3 {
+            h.mock
+                .push_failure(StatusCode::SERVICE_UNAVAILABLE, "unavailable");
+        }
+        let result = h.list(false).await.unwrap();
+
+        assert!(result.from_cache);
+        assert_eq!(result.answers.len(), 1);
+        let reason = result.fallback_reason.unwrap();
+        assert_eq!(reason.status, Some(503));
+        assert_eq!(result.retries, 2);
+    }
+
+    #[tokio::test]
+    async fn list_answers_ignores_cache_write_failure() {
+        let h = Harness::new().await;
+        h.mock
+            .add_record(TABLE, json!({ "问题": "q", "标准回答": "a" }));
+        // 模拟本地缓存损坏，写入时报错
+        let conn = rusqlite::Connection::open(h._dir.path().join("answers_cache.db")).unwrap();
+        conn.execute_batch("DROP TABLE records").unwrap();
+
+        let result = h.list(false).await.unwrap();
+
+        assert!(!result.from_cache);
+        assert_eq!(result.answers.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn list_answers_offline_without_cache_fails() {
+        let h = Harness::new().await;
+
+        let err = h.list(true).await.unwrap_err();
+
+        assert_eq!(err.kind, ErrorKind::NotConfigured);
+    }
+
+    // 首次全量同步后修改、新增、删除各一条记录，再做一次增量同步，返回两次同步的结果
+    async fn sync_after_changes(h: &Harness) -> (SyncSummary, SyncSummary) {
+        let first = h.mock.add_record(TABLE, json!({ "问题": "q1" }));
+        let second = h.mock.add_record(TABLE, json!({ "问题": "q2" }));
+        let full = h.sync().await.unwrap();
+        assert!(full.full_sync);
+        assert_eq!(full.added, vec![first.clone(), second.clone()]);
+
+        let fields = HashMap::from([("问题".to_string(), json!("q1 改"))]);
+        h.client
+            .update_record(APP_TOKEN, TABLE, &first, &fields, &mut 0)
+            .await
+            .unwrap();
+        let third = h.mock.add_record(TABLE, json!({ "问题": "q3" }));
+        h.client
+            .delete_record(APP_TOKEN, TABLE, &second, &mut 0)
+            .await
+            .unwrap();
+
+        let summary = h.sync().await.unwrap();
+        assert!(!summary.full_sync);
+        assert_eq!(summary.updated, vec![first.clone()]);
+        assert_eq!(summary.added, vec![third.clone()]);
+        assert_eq!(summary.removed, vec![second]);
+        assert!(summary.watermark > full.watermark);
+        assert_eq!(
+            summary.watermark,
+            h.mock.record(TABLE, &third).unwrap()["last_modified_time"].as_i64()
+        );
+
+        let cached = h.cache.load_table(APP_TOKEN, TABLE).unwrap().unwrap();
+        let questions: Vec<_> = cached
+            .records
+            .iter()
+            .map(|r| r.fields["问题"].as_str().unwrap())
+            .collect();
+        assert_eq!(questions, vec!["q1 改", "q3"]);
+        (full, summary)
+    }
+
+    #[tokio::test]
+    async fn sync_filters_on_modified_time_field_from_schema() {
+        let h = Harness::new().await;
+        h.mock.add_field(TABLE, "问题", 1);
+        h.mock.add_field(TABLE, "修改时间", TYPE_MODIFIED_TIME);
+
+        let (full, summary) = sync_after_changes(&h).await;
+
+        // 一次按"修改时间"过滤的查询，一次只取 record_id 的查询
+        let search = format!("POST /apps/{}/tables/{}/records/search", APP_TOKEN, TABLE);
+        assert_eq!(h.mock.request_count(&search), 2);
+        let filtered: Vec<_> = h
+            .mock
+            .searches()
+            .into_iter()
+            .filter(|body| !body["filter"].is_null())
+            .collect();
+        assert_eq!(filtered.len(), 1);
+        let condition = &filtered[0]["filter"]["conditions"][0];
+        assert_eq!(condition["field_name"], "修改时间");
+        assert_eq!(condition["operator"], "isGreater");
+        // 日期过滤只精确到天，上次的水位往前退一天
+        let since = full.watermark.unwrap() - 86_400_000;
+        assert_eq!(condition["value"], json!(["ExactDate", since.to_string()]));
+
+        // 没有新的修改时水位不变
+        let unchanged = h.sync().await.unwrap();
+        assert!(unchanged.added.is_empty() && unchanged.updated.is_empty());
+        assert!(unchanged.removed.is_empty());
+        assert_eq!(unchanged.watermark, summary.watermark);
+    }
+
+    #[tokio::test]
+    async fn sync_filter_skips_records_unchanged_for_days() {
+        let h = Harness::new().await;
+        h.mock.add_field(TABLE, "问题", 1);
+        h.mock.add_field(TABLE, "修改时间", TYPE_MODIFIED_TIME);
+        let old = h.mock.add_record(TABLE, json!({ "问题": "旧" }));
+        let stale = h.mock.add_record(TABLE, json!({ "问题": "久未修改" }));
+        let full = h.sync().await.unwrap();
+
+        // 三天后只改动一条，服务端过滤只返回这一条
+        h.mock.advance_clock(3 * 86_400_000);
+        let fields = HashMap::from([("问题".to_string(), json!("新"))]);
+        h.client
+            .update_record(APP_TOKEN, TABLE, &old, &fields, &mut 0)
+            .await
+            .unwrap();
+        let summary = h.sync().await.unwrap();
+        assert_eq!(summary.updated, vec![old.clone()]);
+        assert!(summary.added.is_empty() && summary.removed.is_empty());
+        assert_eq!(
+            summary.watermark,
+            h.mock.record(TABLE, &old).unwrap()["last_modified_time"].as_i64()
+        );
+        let condition = &h.mock.searches()[0]["filter"]["conditions"][0];
+        assert_eq!(
+            condition["value"],
+            json!([
+                "ExactDate",
+                (full.watermark.unwrap() - 86_400_000).to_string()
+            ])
+        );
+
+        // 没出现在过滤结果里的记录仍然留在缓存中
+        let cached = h.cache.load_table(APP_TOKEN, TABLE).unwrap().unwrap();
+        let mut ids: Vec<_> = cached
+            .records
+            .iter()
+            .map(|r| r.record_id.as_str())
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec![old.as_str(), stale.as_str()]);
+    }
+
+    #[tokio::test]
+    async fn sync_without_modified_time_field_compares_last_modified_time() {
+        let h = Harness::new().await;
+        h.mock.add_field(TABLE, "问题", 1);
+
+        sync_after_changes(&h).await;
+
+        let search = format!("POST /apps/{}/tables/{}/records/search", APP_TOKEN, TABLE);
+        assert_eq!(h.mock.request_count(&search), 1);
+    }
+
+    #[tokio::test]
+    async fn update_answer_writes_mapped_columns() {
+        let h = Harness::new().await;
+        let record_id = h
+            .mock
+            .add_record(TABLE, json!({ "问题": "q", "标准回答": "旧" }));
+
+        let fields = HashMap::from([("standard_answer".to_string(), json!("新"))]);
+        let answer = h.update(&record_id, fields, None).await.unwrap().value;
+
+        let record = h.mock.record(TABLE, &record_id).unwrap();
+        assert_eq!(record["fields"]["标准回答"], "新");
+        assert_eq!(record["fields"]["问题"], "q");
+        // 返回完整记录，而不只是本次写入的字段
+        assert_eq!(answer.record_id, record_id);
+        assert_eq!(answer.question, "q");
+        assert_eq!(answer.standard_answer, "新");
+    }
+
+    #[tokio::test]
+    async fn create_answer_returns_normalized_record() {
+        let h = Harness::new().await;
+        h.mock.add_field(TABLE, "问题", 1);
+        h.mock.add_field(TABLE, "标准回答", 1);
+        h.mock.add_field(TABLE, "对应产品", 4);
+
+        let fields = HashMap::from([
+            ("question".to_string(), json!("新问题")),
+            ("对应产品".to_string(), json!(["产品A", "产品B"])),
+        ]);
+        let answer = create_answer(&h.client, &h.mappings, &h.schemas, APP_TOKEN, TABLE, fields)
+            .await
+            .unwrap()
+            .value;
+
+        assert!(answer.record_id.starts_with("rec"));
+        assert_eq!(answer.question, "新问题");
+        assert_eq!(answer.standard_answer, "-");
+        assert_eq!(answer.product_names, vec!["产品A", "产品B"]);
+        assert!(h.mock.record(TABLE, &answer.record_id).is_some());
+    }
+
+    #[tokio::test]
+    async fn batch_update_answers_writes_mapped_columns() {
+        let h = Harness::new().await;
+        let record_id = h
+            .mock
+            .add_record(TABLE, json!({ "问题": "q", "标准回答": "旧" }));
+
+        let records = vec![BatchUpdateItem {
+            record_id: record_id.clone(),
+            fields: HashMap::from([("standard_answer".to_string(), json!("新"))]),
+        }];
+        let result = batch_update_answers(&h.client, &h.mappings, APP_TOKEN, TABLE, records)
+            .await
+            .unwrap();
+
+        assert_eq!((result.succeeded, result.failed), (1, 0));
+        let record = h.mock.record(TABLE, &record_id).unwrap();
+        assert_eq!(record["fields"]["标准回答"], "新");
+    }
+
+    #[tokio::test]
+    async fn update_answer_with_current_version_succeeds() {
+        let h = Harness::new().await;
+        let record_id = h
+            .mock
+            .add_record(TABLE, json!({ "问题": "q", "标准回答": "旧" }));
+        let seen = h.list(false).await.unwrap().answers[0].last_modified_time;
+
+        let fields = HashMap::from([("标准回答".to_string(), json!("新"))]);
+        let answer = h.update(&record_id, fields, seen).await.unwrap().value;
+
+        assert_eq!(answer.standard_answer, "新");
+        // 返回新的修改时间，可以继续基于它写回
+        assert!(answer.last_modified_time > seen);
+    }
+
+    #[tokio::test]
+    async fn update_answer_refuses_stale_version() {
+        let h = Harness::new().await;
+        let record_id = h.mock.add_record(
+            TABLE,
+            json!({ "问题": "q", "标准回答": "A 的回答", "状态": "启用" }),
+        );
+        let seen = h.list(false).await.unwrap().answers[0].last_modified_time;
+
+        // 另一位管理员先写回
+        let fields = HashMap::from([("标准回答".to_string(), json!("B 的回答"))]);
+        h.update(&record_id, fields, seen).await.unwrap();
+
+        let fields = HashMap::from([
+            ("标准回答".to_string(), json!("A 的新回答")),
+            ("状态".to_string(), json!("启用")),
+        ]);
+        let err = h.update(&record_id, fields, seen).await.unwrap_err();
+
+        assert_eq!(err.kind, ErrorKind::Conflict);
+        let conflict = err.conflict.unwrap();
+        assert_eq!(conflict.expected_modified_time, seen);
+        assert!(conflict.current_modified_time > seen);
+        // 只列出会被覆盖的字段，值相同的"状态"不在其中
+        assert_eq!(conflict.fields.len(), 1);
+        assert_eq!(conflict.fields[0].field, "标准回答");
+        assert_eq!(conflict.fields[0].current, Some(json!("B 的回答")));
+        assert_eq!(conflict.fields[0].incoming, json!("A 的新回答"));
+        let record = h.mock.record(TABLE, &record_id).unwrap();
+        assert_eq!(record["fields"]["标准回答"], "B 的回答");
+    }
+
+    #[test]
+    fn diff_fields_ignores_formatting_differences() {
+        let current = HashMap::from([
+            (
+                "问题".to_string(),
+                json!([{ "type": "text", "text": "同一个问题" }]),
+            ),
+            ("标准回答".to_string(), json!("旧")),
+        ]);
+        let incoming = HashMap::from([
+            ("问题".to_string(), json!("同一个问题")),
+            ("标准回答".to_string(), json!("新")),
+            ("备注".to_string(), json!("")),
+        ]);
+
+        let diffs = diff_fields(&current, &incoming);
+
+        let names: Vec<_> = diffs.iter().map(|d| d.field.as_str()).collect();
+        assert_eq!(names, vec!["标准回答"]);
+    }
+
+    #[tokio::test]
+    async fn update_answer_records_revision() {
+        let h = Harness::new().await;
+        let record_id = h
+            .mock
+            .add_record(TABLE, json!({ "问题": "q", "标准回答": "旧" }));
+
+        let fields = HashMap::from([
+            ("standard_answer".to_string(), json!("新")),
+            ("最新版本来源".to_string(), json!("AI优化")),
+        ]);
+        h.update(&record_id, fields, None).await.unwrap();
+
+        let revisions = h.revisions(&record_id);
+        assert_eq!(revisions.len(), 1);
+        let revision = &revisions[0];
+        assert_eq!(revision.old_fields["标准回答"], "旧");
+        assert_eq!(revision.old_fields["最新版本来源"], serde_json::Value::Null);
+        assert_eq!(revision.new_fields["标准回答"], "新");
+        assert_eq!(revision.author.as_deref(), Some("admin"));
+        assert_eq!(revision.ai_action.as_deref(), Some("AI优化"));
+    }
+
+    #[tokio::test]
+    async fn failed_update_leaves_no_revision() {
+        let h = Harness::new().await;
+        h.mock.add_field(TABLE, "标准回答", 1);
+        let record_id = h.mock.add_record(TABLE, json!({ "标准回答": "旧" }));
+
+        let fields = HashMap::from([("不存在的列".to_string(), json!("x"))]);
+        h.update(&record_id, fields, None).await.unwrap_err();
+
+        assert!(h.revisions(&record_id).is_empty());
+    }
+
+    #[tokio::test]
+    async fn restore_revision_writes_old_values_back() {
+        let h = Harness::new().await;
+        let record_id = h
+            .mock
+            .add_record(TABLE, json!({ "问题": "q", "标准回答": "原始回答" }));
+        let fields = HashMap::from([
+            ("标准回答".to_string(), json!("AI 回答")),
+            ("最新版本来源".to_string(), json!("AI优化")),
+        ]);
+        h.update(&record_id, fields, None).await.unwrap();
+        let revision_id = h.revisions(&record_id)[0].id;
+
+        let answer = restore_revision(
+            &h.client,
+            &h.mappings,
+            &h.schemas,
+            &h.revisions,
+            APP_TOKEN,
+            TABLE,
+            revision_id,
+            Some("admin".to_string()),
+        )
+        .await
+        .unwrap()
+        .value;
+
+        assert_eq!(answer.standard_answer, "原始回答");
+        let record = h.mock.record(TABLE, &record_id).unwrap();
+        assert_eq!(record["fields"]["最新版本来源"], serde_json::Value::Null);
+        let revisions = h.revisions(&record_id);
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0].restored_from, Some(revision_id));
+        assert_eq!(revisions[0].old_fields["标准回答"], "AI 回答");
+    }
+
+    #[tokio::test]
+    async fn restore_revision_writes_rich_text_as_plain_text() {
+        let h = Harness::new().await;
+        h.mock.add_field(TABLE, "标准回答", 1);
+        let segments = json!([
+            { "type": "text", "text": "详见" },
+            { "type": "url", "text": "帮助中心", "link": "https://example.com/help" },
+        ]);
+        let record_id = h.mock.add_record(TABLE, json!({ "标准回答": segments }));
+        let fields = HashMap::from([("标准回答".to_string(), json!("AI 回答"))]);
+        h.update(&record_id, fields, None).await.unwrap();
+        let revision = h.revisions(&record_id).remove(0);
+        assert_eq!(revision.old_fields["标准回答"], segments);
+
+        // 模拟服务和飞书一样拒绝把分段数组写入文本字段，恢复时要先转换为纯文本
+        let answer = restore_revision(
+            &h.client,
+            &h.mappings,
+            &h.schemas,
+            &h.revisions,
+            APP_TOKEN,
+            TABLE,
+            revision.id,
+            None,
+        )
+        .await
+        .unwrap()
+        .value;
+
+        assert_eq!(answer.standard_answer, "详见帮助中心");
+        let record = h.mock.record(TABLE, &record_id).unwrap();
+        assert_eq!(record["fields"]["标准回答"], "详见帮助中心");
+    }
+
+    #[tokio::test]
+    async fn restore_revision_refuses_to_overwrite_newer_edit() {
+        let h = Harness::new().await;
+        let record_id = h.mock.add_record(TABLE, json!({ "标准回答": "原始回答" }));
+        let fields = HashMap::from([("标准回答".to_string(), json!("AI 回答"))]);
+        h.update(&record_id, fields, None).await.unwrap();
+        let revision_id = h.revisions(&record_id)[0].id;
+
+        // 之后有人直接在表格里改了这一列
+        let edit = HashMap::from([("标准回答".to_string(), json!("B 的回答"))]);
+        h.client
+            .update_record(APP_TOKEN, TABLE, &record_id, &edit, &mut 0)
+            .await
+            .unwrap();
+
+        let err = restore_revision(
+            &h.client,
+            &h.mappings,
+            &h.schemas,
+            &h.revisions,
+            APP_TOKEN,
+            TABLE,
+            revision_id,
+            None,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(err.kind, ErrorKind::Conflict);
+        let conflict = err.conflict.unwrap();
+        assert_eq!(conflict.expected_modified_time, None);
+        assert_eq!(conflict.fields[0].current, Some(json!("B 的回答")));
+        assert_eq!(conflict.fields[0].incoming, json!("原始回答"));
+        let record = h.mock.record(TABLE, &record_id).unwrap();
+        assert_eq!(record["fields"]["标准回答"], "B 的回答");
+        assert_eq!(h.revisions(&record_id).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn restore_revision_rejects_other_table() {
+        let h = Harness::new().await;
+        let record_id = h.mock.add_record(TABLE, json!({ "标准回答": "旧" }));
+        let fields = HashMap::from([("标准回答".to_string(), json!("新"))]);
+        h.update(&record_id, fields, None).await.unwrap();
+        let revision_id = h.revisions(&record_id)[0].id;
+
+        let err = restore_revision(
+            &h.client,
+            &h.mappings,
+            &h.schemas,
+            &h.revisions,
+            APP_TOKEN,
+            "tblOther",
+            revision_id,
+            None,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(err.kind, ErrorKind::InvalidInput);
+    }
+
+    #[tokio::test]
+    async fn update_answer_reports_missing_record() {
+        let h = Harness::new().await;
+
+        let fields = HashMap::from([("标准回答".to_string(), json!("新"))]);
+        let err = h.update("recMissing", fields, None).await.unwrap_err();
+
+        assert_eq!(err.kind, ErrorKind::Feishu);
+        assert_eq!(err.code, Some(CODE_RECORD_NOT_FOUND));
+        assert!(err.message.starts_with("更新失败"));
+    }
+
+    #[tokio::test]
+    async fn update_answer_reports_non_json_http_error() {
+        let h = Harness::new().await;
+        let record_id = h.mock.add_record(TABLE, json!({ "问题": "q" }));
+        h.client.access_token().await.unwrap();
+
+        h.mock.push_failure(StatusCode::FORBIDDEN, "forbidden");
+        let fields = HashMap::from([("问题".to_string(), json!("q2"))]);
+        let err = h.update(&record_id, fields, None).await.unwrap_err();
+
+        assert_eq!(err.kind, ErrorKind::Http);
+        assert_eq!(err.status, Some(403));
+        assert!(!err.retryable);
+    }
+
+    #[tokio::test]
+    async fn update_answer_reports_retries() {
+        let h = Harness::new().await;
+        let record_id = h.mock.add_record(TABLE, json!({ "问题": "q" }));
+        h.client.access_token().await.unwrap();
+
+        h.mock.push_failure(StatusCode::SERVICE_UNAVAILABLE, "busy");
+        let fields = HashMap::from([("问题".to_string(), json!("q2"))]);
+        let result = h.update(&record_id, fields, None).await.unwrap();
+
+        assert_eq!(result.retries, 1);
+        assert_eq!(result.value.question, "q2");
+    }
+
+    #[tokio::test]
+    async fn update_answer_without_credentials_is_not_configured() {
+        let h = Harness::new().await;
+        let client = FeishuClient::new(None, &h.mock.endpoint()).unwrap();
+
+        let update = AnswerUpdate {
+            record_id: "rec".to_string(),
+            fields: HashMap::new(),
+            expected_modified_time: None,
+            revision: RevisionInfo::default(),
+            restored_from: None,
+        };
+        let err = update_answer(
+            &client,
+            &h.mappings,
+            &h.schemas,
+            &h.revisions,
+            APP_TOKEN,
+            TABLE,
+            update,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(err.kind, ErrorKind::NotConfigured);
+    }
+
+    #[derive(Default)]
+    struct RecordingSink(Mutex<Vec<String>>);
+
+    impl StreamSink for RecordingSink {
+        fn delta(&self, text: &str) {
+            self.0.lock().unwrap().push(text.to_string());
+        }
+    }
+
+    impl RecordingSink {
+        fn deltas(&self) -> Vec<String> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn review_answer_streams_deltas() {
+        let dir = tempfile::tempdir().unwrap();
+        let ai = MockAi::start().await;
+        let store = ai.store(dir.path());
+        ai.reply_stream(&[r#"{"verdict":"#, r#""reasonable","explanation":"准确"}"#]);
+        let sink = RecordingSink::default();
+
+        let result = review_answer(
+            &store,
+            &PromptKind::Review.builtin(),
+            "回复".to_string(),
+            None,
+            None,
+            None,
+            Some(&sink),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.report.verdict, ReviewVerdict::Reasonable);
+        assert_eq!(result.report.explanation, "准确");
+        assert!(result.structured);
+        assert_eq!(sink.deltas().concat(), result.raw_text);
+        assert_eq!(ai.requests()[0]["stream"], true);
+        assert_eq!(
+            ai.requests()[0]["response_format"]["json_schema"]["name"],
+            "review_report"
+        );
+    }
+
+    #[tokio::test]
+    async fn ai_request_without_sink_is_not_streamed() {
+        let dir = tempfile::tempdir().unwrap();
+        let ai = MockAi::start().await;
+        let store = ai.store(dir.path());
+        ai.reply_stream(&["RISK = NO\n", "REASON = 无风险"]);
+
+        let result = check_risk(
+            &store,
+            &PromptKind::Risk.builtin(),
+            "回复".to_string(),
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert!(!result.report.has_risk);
+        assert_eq!(result.report.reason, "无风险");
+        assert!(!result.structured);
+        assert!(ai.requests()[0].get("stream").is_none());
+    }
+
+    #[tokio::test]
+    async fn streaming_falls_back_to_plain_response() {
+        let dir = tempfile::tempdir().unwrap();
+        let ai = MockAi::start().await;
+        let store = ai.store(dir.path());
+        // 不支持流式输出的接口忽略 stream 参数
+        ai.reply_text("【审核结论】= 需修改\n【需修改原因】\n夸大功效");
+        let sink = RecordingSink::default();
+
+        let result = review_answer(
+            &store,
+            &PromptKind::Review.builtin(),
+            "回复".to_string(),
+            None,
+            None,
+            None,
+            Some(&sink),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.report.verdict, ReviewVerdict::NeedsRevision);
+        assert_eq!(result.report.revision_reason.as_deref(), Some("夸大功效"));
+        assert!(!result.structured);
+        assert_eq!(sink.deltas(), vec![result.raw_text]);
+    }
+
+    #[tokio::test]
+    async fn structured_output_rejected_retries_without_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let ai = MockAi::start().await;
+        let store = ai.store(dir.path());
+        ai.reply_status(StatusCode::BAD_REQUEST, "response_format is not supported");
+        ai.reply_text("RISK = YES\nREASON = 承诺疗效");
+
+        let result = check_risk(
+            &store,
+            &PromptKind::Risk.builtin(),
+            "回复".to_string(),
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert!(result.report.has_risk);
+        assert_eq!(result.report.reason, "承诺疗效");
+        let requests = ai.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0]["response_format"]["type"], "json_schema");
+        assert!(requests[1].get("response_format").is_none());
+    }
+
+    #[tokio::test]
+    async fn streaming_reports_http_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let ai = MockAi::start().await;
+        let store = ai.store(dir.path());
+        ai.reply_status(StatusCode::UNAUTHORIZED, "invalid api key");
+        let sink = RecordingSink::default();
+
+        let err = review_answer(
+            &store,
+            &PromptKind::Review.builtin(),
+            "回复".to_string(),
+            None,
+            None,
+            None,
+            Some(&sink),
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(err.kind, ErrorKind::Http);
+        assert_eq!(err.status, Some(401));
+        assert!(sink.deltas().is_empty());
+    }
+
+    #[tokio::test]
+    async fn cancelled_ai_request_returns_cancelled_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let ai = MockAi::start().await;
+        let store = std::sync::Arc::new(ai.store(dir.path()));
+        let requests = std::sync::Arc::new(AiRequests::default());
+        ai.reply_hang();
+
+        let running = {
+            let (store, requests) = (store.clone(), requests.clone());
+            tokio::spawn(async move {
+                requests
+                    .run(
+                        Some("req-1"),
+                        optimize_answer(
+                            &store,
+                            &PromptKind::Optimize.builtin(),
+                            &PromptKind::Compress.builtin(),
+                            "回复".to_string(),
+                            None,
+                            None,
+                            None,
+                            None,
+                        ),
+                    )
+                    .await
+            })
+        };
+        while ai.requests().is_empty() {
+            tokio::task::yield_now().await;
+        }
+
+        assert!(requests.cancel("req-1"));
+        let err = running.await.unwrap().unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Cancelled);
+        assert!(!requests.cancel("req-1"));
+    }
+
+    #[tokio::test]
+    async fn every_provider_supports_plain_and_streamed_replies() {
+        for provider in [
+            AiProviderKind::OpenAi,
+            AiProviderKind::Azure,
+            AiProviderKind::Anthropic,
+            AiProviderKind::Ollama,
+        ] {
+            let dir = tempfile::tempdir().unwrap();
+            let ai = MockAi::start().await;
+            let store = ai.store_for(dir.path(), provider);
+            ai.reply_stream(&["你", "好"]);
+            ai.reply_stream(&["整段", "回复"]);
+            let sink = RecordingSink::default();
+
+            let streamed = call_ai_api(&store, "提示".to_string(), None, None, None, Some(&sink))
+                .await
+                .unwrap();
+            let plain = call_ai_api(&store, "提示".to_string(), None, None, None, None)
+                .await
+                .unwrap();
+
+            assert_eq!(streamed.text, "你好", "{:?}", provider);
+            assert_eq!(sink.deltas(), vec!["你", "好"], "{:?}", provider);
+            assert_eq!(plain.text, "整段回复", "{:?}", provider);
+            // Azure 流式响应不返回用量
+            let streamed_usage = match provider {
+                AiProviderKind::Azure => TokenUsage::default(),
+                _ => TokenUsage {
+                    prompt_tokens: PROMPT_TOKENS,
+                    completion_tokens: 2,
+                },
+            };
+            assert_eq!(streamed.usage, streamed_usage, "{:?}", provider);
+            assert_eq!(
+                plain.usage,
+                TokenUsage {
+                    prompt_tokens: PROMPT_TOKENS,
+                    completion_tokens: 4,
+                },
+                "{:?}",
+                provider
+            );
+            assert_eq!(
+                streamed.model.as_deref(),
+                Some(SERVED_MODEL),
+                "{:?}",
+                provider
+            );
+            assert_eq!(plain.model.as_deref(), Some(SERVED_MODEL), "{:?}", provider);
+            let request = &ai.received()[0];
+            let key_header = match provider {
+                AiProviderKind::Azure => "api-key",
+                AiProviderKind::Anthropic => "x-api-key",
+                _ => "authorization",
+            };
+            assert!(request.headers.contains_key(key_header), "{:?}", provider);
+            if provider == AiProviderKind::Azure {
+                assert!(request.uri.ends_with("?api-version=2024-06-01"));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn optimize_uses_saved_template_and_reports_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let ai = MockAi::start().await;
+        let store = ai.store(dir.path());
+        let prompts = PromptStore::open(dir.path()).unwrap();
+        prompts
+            .save(PromptKind::Optimize, "上限 {max_length} 字：{answer}", None)
+            .unwrap();
+        let template = prompts.active(PromptKind::Optimize).unwrap();
+
+        let result = optimize_answer(
+            &store,
+            &template,
+            &PromptKind::Compress.builtin(),
+            "一二三四".to_string(),
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            ai.requests()[0]["messages"][0]["content"],
+            "上限 6 字：一二三四"
+        );
+        assert_eq!(
+            result.template,
+            TemplateRef {
+                kind: PromptKind::Optimize,
+                version: 1
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn optimize_compresses_reply_over_budget() {
+        let dir = tempfile::tempdir().unwrap();
+        let ai = MockAi::start().await;
+        let store = ai.store(dir.path());
+        ai.reply_text(r#"{"final_reply":"一二三四五六七八","explanation":"更完整","corrections":["原回复有误"]}"#);
+        ai.reply_text("一二三四五");
+        let usage_log = UsageStore::open(dir.path()).unwrap();
+        let usage = UsageContext {
+            log: &usage_log,
+            command: "optimize_answer_with_ai",
+            record_id: Some("rec1"),
+            user: Some("alice"),
+        };
+
+        let result = optimize_answer(
+            &store,
+            &PromptKind::Optimize.builtin(),
+            &PromptKind::Compress.builtin(),
+            "甲乙丙丁".to_string(),
+            None,
+            None,
+            Some(usage),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.report.final_reply, "一二三四五");
+        assert_eq!(result.report.corrections, vec!["原回复有误"]);
+        assert_eq!(
+            (
+                result.original_length,
+                result.reply_length,
+                result.max_length
+            ),
+            (4, 5, 6)
+        );
+        assert_eq!(result.attempts, 2);
+        let requests = ai.requests();
+        assert_eq!(
+            requests[0]["response_format"]["json_schema"]["name"],
+            "optimization_report"
+        );
+        let compress_prompt = requests[1]["messages"][0]["content"].as_str().unwrap();
+        assert!(compress_prompt.contains("一二三四五六七八"));
+        assert!(requests[1].get("response_format").is_none());
+        // 优化和压缩各记录一条用量，模型取响应中实际提供服务的模型而不是配置的模型名
+        let summary = usage_log.summary(None, None).unwrap();
+        assert_eq!(summary.len(), 1);
+        assert_eq!(summary[0].model, SERVED_MODEL);
+        assert_eq!(summary[0].calls, 2);
+        assert_eq!(summary[0].prompt_tokens, 2 * PROMPT_TOKENS);
+    }
+
+    #[tokio::test]
+    async fn optimize_gives_up_after_compress_attempts() {
+        let dir = tempfile::tempdir().unwrap();
+        let ai = MockAi::start().await;
+        let store = ai.store(dir.path());
+        for _ in 0..=MAX_COMPRESS_ATTEMPTS {
+            ai.reply_text("一二三四五六七八");
+        }
+
+        let err = optimize_answer(
+            &store,
+            &PromptKind::Optimize.builtin(),
+            &PromptKind::Compress.builtin(),
+            "甲乙丙丁".to_string(),
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(err.kind, ErrorKind::Ai);
+        assert_eq!(ai.requests().len(), MAX_COMPRESS_ATTEMPTS + 1);
+    }
+
+    #[tokio::test]
+    async fn repeated_optimize_uses_cached_reply_unless_bypassed() {
+        let dir = tempfile::tempdir().unwrap();
+        let ai = MockAi::start().await;
+        let store = ai.store(dir.path());
+        let cache = AiCache::open(dir.path()).unwrap();
+        let reply = r#"{"final_reply":"一二三","explanation":"更清楚","corrections":[]}"#;
+        ai.reply_text(reply);
+        ai.reply_text(reply);
+        let (template, compress_template) = (
+            PromptKind::Optimize.builtin(),
+            PromptKind::Compress.builtin(),
+        );
+        let sink = RecordingSink::default();
+        let optimize = |bypass: bool, stream: bool| {
+            optimize_answer(
+                &store,
+                &template,
+                &compress_template,
+                "甲乙丙丁".to_string(),
+                None,
+                Some(CachePolicy {
+                    cache: &cache,
+                    bypass,
+                }),
+                None,
+                stream.then_some(&sink as &dyn StreamSink),
+            )
+        };
+
+        let first = optimize(false, false).await.unwrap();
+        let second = optimize(false, true).await.unwrap();
+
+        assert!(!first.cached);
+        assert!(second.cached);
+        assert_eq!(second.report.final_reply, "一二三");
+        assert_eq!(second.usage, TokenUsage::default());
+        assert_eq!(sink.deltas(), vec![reply]);
+        assert_eq!(ai.requests().len(), 1);
+
+        let bypassed = optimize(true, false).await.unwrap();
+        assert!(!bypassed.cached);
+        assert_eq!(ai.requests().len(), 2);
+        assert_eq!(cache.clear().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn batch_review_runs_review_and_risk_for_each_answer() {
+        let dir = tempfile::tempdir().unwrap();
+        let ai = MockAi::start().await;
+        let store = ai.store(dir.path());
+        let prompts = PromptStore::open(dir.path()).unwrap();
+        let batches = BatchStore::open(dir.path()).unwrap();
+        let usage_log = UsageStore::open(dir.path()).unwrap();
+        let cache = AiCache::open(dir.path()).unwrap();
+        let runner = BatchRunner::default();
+        let item = BatchItem {
+            record_id: "rec1".to_string(),
+            question: "能空腹吃吗".to_string(),
+            answer: "可以空腹吃".to_string(),
+            context: "问题：能空腹吃吗".to_string(),
+        };
+        let job = batches.create_job("app", "tbl", &[item]).unwrap();
+        ai.reply_text(r#"{"verdict":"needs_revision","explanation":"不严谨"}"#);
+        ai.reply_text(r#"{"has_risk":true,"reason":"可能误导"}"#);
+
+        let finished = run_batch_review(
+            &store,
+            &prompts,
+            &batches,
+            &usage_log,
+            &cache,
+            &runner.register(job.id).unwrap(),
+            DEFAULT_CONCURRENCY,
+            Some("alice"),
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(finished.status, BatchStatus::Completed);
+        assert_eq!((finished.done, finished.flagged), (1, 1));
+        let results = batches.results(job.id, true).unwrap();
+        assert_eq!(results[0].risk.as_ref().unwrap().reason, "可能误导");
+        let requests = ai.requests();
+        assert!(requests[0]["messages"][0]["content"]
+            .as_str()
+            .unwrap()
+            .contains("问题：能空腹吃吗"));
+        assert_eq!(
+            requests[1]["response_format"]["json_schema"]["name"],
+            "risk_report"
+        );
+        let usage = usage_log.summary(None, None).unwrap();
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].user.as_deref(), Some("alice"));
+        // 审核和风险检测各一条
+        assert_eq!(usage[0].calls, 2);
+        assert_eq!(usage[0].prompt_tokens, 2 * PROMPT_TOKENS);
+    }
+}
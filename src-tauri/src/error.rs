@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// 错误分类，序列化后的字符串是前端依赖的稳定标识，不要随意改名
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    Network,          // 网络不可达、DNS、连接被拒绝
+    Timeout,          // 请求超时
+    NotConfigured,    // 未配置飞书凭证 / AI 设置
+    InvalidAppId,     // App ID 无效（99991663）
+    InvalidAppSecret, // App Secret 无效（99991664）
+    TokenInvalid,     // tenant_access_token 失效
+    RateLimited,      // 触发频率限制
+    Feishu,           // 其他飞书业务错误
+    Http,             // 非 2xx 的 HTTP 响应
+    Parse,            // 响应解析失败
+    InvalidInput,     // 参数或配置校验失败
+    Storage,          // 本地存储读写失败
+    Ai,               // AI 接口返回错误
+    Conflict,         // 写回时记录已被他人修改
+    Cancelled,        // 请求已被用户取消
+    Internal,
+}
+
+// 所有命令统一返回的错误类型
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppError {
+    pub kind: ErrorKind,
+    pub message: String, // 本地化（中文）错误信息，可直接展示给用户
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<i64>, // 飞书业务错误码
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>, // HTTP 状态码
+    pub retryable: bool,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub retries: u32, // 失败前已自动重试的次数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conflict: Option<Box<UpdateConflict>>, // kind 为 conflict 时的详细信息
+}
+
+// 乐观并发校验失败：调用方看到的版本与飞书中的当前版本不一致
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateConflict {
+    pub record_id: String,
+    pub expected_modified_time: Option<i64>, // 调用方读取记录时的最后修改时间（毫秒），恢复修订时为空
+    pub current_modified_time: Option<i64>,  // 飞书中记录当前的最后修改时间
+    pub fields: Vec<FieldDiff>,              // 本次要写入、且当前值与写入值不同的字段
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldDiff {
+    pub field: String,                      // 表格中的实际列名
+    pub current: Option<serde_json::Value>, // 飞书中的当前值，字段为空时为 None
+    pub incoming: serde_json::Value,        // 本次要写入的值
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+// 飞书错误码
+pub const CODE_INVALID_APP_ID: i64 = 99991663;
+pub const CODE_INVALID_APP_SECRET: i64 = 99991664;
+pub const CODE_RATE_LIMITED: i64 = 99991400;
+// tenant_access_token 无效或过期。99991663 在获取 token 的接口上表示 App ID 无效，由 feishu_auth 先行区分
+pub const CODE_TOKEN_INVALID: &[i64] = &[99991661, 99991663, 99991668, 99991677];
+
+impl AppError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        let retryable = matches!(
+            kind,
+            ErrorKind::Network | ErrorKind::Timeout | ErrorKind::RateLimited
+        );
+        Self {
+            kind,
+            message: message.into(),
+            code: None,
+            status: None,
+            retryable,
+            retries: 0,
+            conflict: None,
+        }
+    }
+
+    pub fn not_configured(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::NotConfigured, message)
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::InvalidInput, message)
+    }
+
+    pub fn storage(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Storage, message)
+    }
+
+    pub fn parse(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Parse, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Internal, message)
+    }
+
+    // 网络层错误，按超时 / DNS / 连接被拒绝给出更友好的信息
+    pub fn network(err: reqwest::Error) -> Self {
+        let error_msg = err.to_string();
+        if err.is_timeout() || error_msg.contains("timeout") || error_msg.contains("timed out") {
+            Self::new(ErrorKind::Timeout, "网络请求超时，请检查网络连接")
+        } else if error_msg.contains("resolve") || error_msg.contains("DNS") {
+            Self::new(
+                ErrorKind::Network,
+                "无法解析域名，请检查网络连接或 DNS 设置",
+            )
+        } else if error_msg.contains("connection refused")
+            || error_msg.contains("Connection refused")
+        {
+            Self::new(ErrorKind::Network, "连接被拒绝，请检查网络连接或防火墙设置")
+        } else {
+            Self::new(ErrorKind::Network, format!("网络请求失败: {}", error_msg))
+        }
+    }
+
+    // 非 2xx 响应，5xx 和 429 可重试
+    pub fn http(status: reqwest::StatusCode, message: impl Into<String>) -> Self {
+        let kind = if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            ErrorKind::RateLimited
+        } else {
+            ErrorKind::Http
+        };
+        let mut err = Self::new(kind, message).with_status(status.as_u16());
+        err.retryable = err.retryable || status.is_server_error();
+        err
+    }
+
+    // 飞书业务错误（响应中 code != 0），action 如"获取记录失败"
+    pub fn feishu(code: i64, msg: &str, action: &str) -> Self {
+        let kind = match code {
+            CODE_RATE_LIMITED => ErrorKind::RateLimited,
+            c if CODE_TOKEN_INVALID.contains(&c) => ErrorKind::TokenInvalid,
+            _ => ErrorKind::Feishu,
+        };
+        Self::new(kind, format!("{}: {}", action, msg)).with_code(code)
+    }
+
+    // 获取 tenant_access_token 时的错误，区分 App ID / App Secret 无效
+    pub fn feishu_auth(code: i64, msg: &str) -> Self {
+        match code {
+            CODE_INVALID_APP_ID => Self::new(
+                ErrorKind::InvalidAppId,
+                format!("App ID 无效或不存在 (错误代码: {})", code),
+            )
+            .with_code(code),
+            CODE_INVALID_APP_SECRET => Self::new(
+                ErrorKind::InvalidAppSecret,
+                format!("App Secret 无效或错误 (错误代码: {})", code),
+            )
+            .with_code(code),
+            _ => Self::feishu(code, msg, "获取 token 失败"),
+        }
+    }
+
+    pub fn conflict(conflict: UpdateConflict) -> Self {
+        let mut err = Self::new(
+            ErrorKind::Conflict,
+            format!(
+                "记录已被他人修改（{} 个字段与当前内容不同），请刷新后重试",
+                conflict.fields.len()
+            ),
+        );
+        err.conflict = Some(Box::new(conflict));
+        err
+    }
+
+    pub fn ai(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Ai, message)
+    }
+
+    pub fn cancelled() -> Self {
+        Self::new(ErrorKind::Cancelled, "请求已取消")
+    }
+
+    pub fn with_code(mut self, code: i64) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    // 在信息前加上上下文，如"连接失败: "
+    pub fn context(mut self, prefix: &str) -> Self {
+        self.message = format!("{}: {}", prefix, self.message);
+        self
+    }
+}
+
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
@@ -0,0 +1,1020 @@
+use reqwest::{Client, RequestBuilder};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::answer_cache::unix_now;
+use crate::commands::{
+    AccessTokenResponse, AnswerRecord, BatchRecordResult, BatchUpdateItem,
+    BitableBatchDeleteResponse, BitableBatchRecordsResponse, BitableField, BitableFieldsResponse,
+    BitableRecordResponse, BitableRecordsResponse, BitableResponse, BitableTable,
+    BitableTablesResponse, FeishuCredentials,
+};
+use crate::error::{AppError, AppResult, ErrorKind};
+use crate::feishu_http::{send_json, RetryPolicy};
+
+const FEISHU_API_BASE: &str = "https://open.feishu.cn/open-apis";
+const LARK_API_BASE: &str = "https://open.larksuite.com/open-apis";
+
+// 批量接口单次请求最多处理的记录数
+pub const BATCH_LIMIT: usize = 500;
+
+// 开放平台地址：飞书（国内）、Lark（海外）或私有化部署的自定义地址
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "preset", rename_all = "snake_case")]
+pub enum FeishuEndpoint {
+    #[default]
+    Feishu,
+    Lark,
+    Custom {
+        base_url: String,
+    },
+}
+
+impl FeishuEndpoint {
+    // Open API 根地址，如 https://open.feishu.cn/open-apis
+    pub fn base_url(&self) -> String {
+        match self {
+            FeishuEndpoint::Feishu => FEISHU_API_BASE.to_string(),
+            FeishuEndpoint::Lark => LARK_API_BASE.to_string(),
+            FeishuEndpoint::Custom { base_url } => base_url.clone(),
+        }
+    }
+
+    // 校验并规范化自定义地址：去掉末尾的 /，只填了域名时补全 /open-apis
+    pub fn normalize(self) -> AppResult<Self> {
+        let FeishuEndpoint::Custom { base_url } = self else {
+            return Ok(self);
+        };
+        let base_url = base_url.trim().trim_end_matches('/');
+        let url = reqwest::Url::parse(base_url)
+            .map_err(|_| AppError::invalid_input(format!("开放平台地址无效: {}", base_url)))?;
+        if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+            return Err(AppError::invalid_input(format!(
+                "开放平台地址必须以 http:// 或 https:// 开头: {}",
+                base_url
+            )));
+        }
+        let base_url = if base_url.ends_with("/open-apis") {
+            base_url.to_string()
+        } else {
+            format!("{}/open-apis", base_url)
+        };
+        Ok(FeishuEndpoint::Custom { base_url })
+    }
+}
+
+// 飞书开放平台客户端（Tauri 托管状态），所有命令共享连接池、token 缓存和重试策略。
+// 各方法的 retries 参数累加请求自动重试的次数，由命令汇总后返回给前端。
+pub struct FeishuClient {
+    http: Client,
+    base_url: Mutex<String>,
+    credentials: Mutex<Option<FeishuCredentials>>,
+    // 使用异步锁并在刷新期间一直持有，并发的命令会等待同一次刷新而不是各自请求新 token
+    token: tokio::sync::Mutex<Option<(String, i64)>>, // (token, expire_timestamp)
+    retry_policy: Mutex<RetryPolicy>,
+}
+
+impl FeishuClient {
+    pub fn new(
+        credentials: Option<FeishuCredentials>,
+        endpoint: &FeishuEndpoint,
+    ) -> AppResult<Self> {
+        let http = Client::builder()
+            .connect_timeout(Duration::from_secs(10))
+            .timeout(Duration::from_secs(30))
+            .user_agent(concat!("A3/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| AppError::internal(format!("创建 HTTP 客户端失败: {}", e)))?;
+        Ok(Self {
+            http,
+            base_url: Mutex::new(endpoint.base_url()),
+            credentials: Mutex::new(credentials),
+            token: tokio::sync::Mutex::const_new(None),
+            retry_policy: Mutex::new(RetryPolicy::default()),
+        })
+    }
+
+    pub fn has_credentials(&self) -> bool {
+        self.credentials.lock().unwrap().is_some()
+    }
+
+    // 更换凭证时清除旧的 token
+    pub async fn set_credentials(&self, credentials: FeishuCredentials) {
+        *self.credentials.lock().unwrap() = Some(credentials);
+        *self.token.lock().await = None;
+    }
+
+    pub fn base_url(&self) -> String {
+        self.base_url.lock().unwrap().clone()
+    }
+
+    // 切换开放平台地址，token 只在签发它的平台有效，一并清除
+    pub async fn set_endpoint(&self, endpoint: &FeishuEndpoint) {
+        *self.base_url.lock().unwrap() = endpoint.base_url();
+        *self.token.lock().await = None;
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        *self.retry_policy.lock().unwrap()
+    }
+
+    pub fn set_retry_policy(&self, policy: RetryPolicy) {
+        *self.retry_policy.lock().unwrap() = policy;
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url(), path)
+    }
+
+    fn records_url(&self, app_token: &str, table_id: &str) -> String {
+        self.url(&format!(
+            "/bitable/v1/apps/{}/tables/{}/records",
+            app_token, table_id
+        ))
+    }
+
+    // 用给定凭证向 base_url 换取 tenant_access_token，返回 (token, 有效秒数)，不写入缓存
+    pub async fn request_token(
+        &self,
+        base_url: &str,
+        credentials: &FeishuCredentials,
+    ) -> AppResult<(String, i64)> {
+        let url = format!("{}/auth/v3/tenant_access_token/internal", base_url);
+        let body = serde_json::json!({
+            "app_id": credentials.app_id,
+            "app_secret": credentials.app_secret,
+        });
+
+        // token 为多个命令共享，这里的重试不计入单个命令的重试次数
+        let token_res: AccessTokenResponse = send_json(
+            self.http.post(&url).json(&body),
+            self.retry_policy(),
+            true,
+            &mut 0,
+        )
+        .await
+        .map_err(|e| match (e.kind, e.code) {
+            (ErrorKind::TokenInvalid, Some(code)) => AppError::feishu_auth(code, &e.message),
+            _ => e,
+        })?;
+
+        if token_res.code != 0 {
+            return Err(AppError::feishu_auth(token_res.code as i64, &token_res.msg));
+        }
+
+        let token = token_res
+            .tenant_access_token
+            .ok_or_else(|| AppError::parse("响应中缺少 tenant_access_token"))?;
+        Ok((token, token_res.expire.unwrap_or(7200) as i64))
+    }
+
+    pub async fn access_token(&self) -> AppResult<String> {
+        // 检查是否有缓存的 token，锁一直持有到刷新完成
+        let mut token_guard = self.token.lock().await;
+        if let Some((token, expire_at)) = token_guard.as_ref() {
+            if unix_now() < *expire_at - 60 {
+                // 提前 60 秒刷新
+                return Ok(token.clone());
+            }
+        }
+
+        let credentials = self
+            .credentials
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| AppError::not_configured("请先配置飞书凭证"))?;
+        let (token, expire) = self.request_token(&self.base_url(), &credentials).await?;
+
+        *token_guard = Some((token.clone(), unix_now() + expire));
+        Ok(token)
+    }
+
+    // 飞书返回 token 失效时丢弃缓存。其他命令可能已经刷新过，只在缓存的仍是失效的 token 时清除
+    async fn invalidate_token(&self, stale: &str) {
+        let mut token_guard = self.token.lock().await;
+        if matches!(token_guard.as_ref(), Some((token, _)) if token == stale) {
+            *token_guard = None;
+        }
+    }
+
+    // 带上 tenant_access_token 发送请求，token 失效时强制刷新并重放一次
+    // （失效的请求没有被执行，非幂等请求也可以安全重放）
+    async fn send<T: DeserializeOwned>(
+        &self,
+        build: impl Fn() -> RequestBuilder,
+        idempotent: bool,
+        retries: &mut u32,
+    ) -> AppResult<T> {
+        let policy = self.retry_policy();
+        let token = self.access_token().await?;
+        let request = build().bearer_auth(&token);
+        match send_json(request, policy, idempotent, retries).await {
+            Err(e) if e.kind == ErrorKind::TokenInvalid => {
+                self.invalidate_token(&token).await;
+                let token = self.access_token().await?;
+                *retries += 1;
+                send_json(build().bearer_auth(&token), policy, idempotent, retries).await
+            }
+            result => result,
+        }
+    }
+
+    pub async fn list_tables(
+        &self,
+        app_token: &str,
+        retries: &mut u32,
+    ) -> AppResult<Vec<BitableTable>> {
+        let url = self.url(&format!("/bitable/v1/apps/{}/tables", app_token));
+        let tables_res: BitableTablesResponse =
+            self.send(|| self.http.get(&url), true, retries).await?;
+
+        if tables_res.code != 0 {
+            return Err(AppError::feishu(
+                tables_res.code as i64,
+                &tables_res.msg,
+                "获取表格列表失败",
+            ));
+        }
+
+        Ok(tables_res
+            .data
+            .ok_or_else(|| AppError::parse("响应中缺少数据"))?
+            .items)
+    }
+
+    // 分页获取表中的所有记录
+    pub async fn list_records(
+        &self,
+        app_token: &str,
+        table_id: &str,
+        retries: &mut u32,
+    ) -> AppResult<Vec<AnswerRecord>> {
+        let url = self.records_url(app_token, table_id);
+        let mut all_records = Vec::new();
+        let mut page_token: Option<String> = None;
+
+        loop {
+            let mut query = vec![("automatic_fields", "true")];
+            if let Some(token) = &page_token {
+                query.push(("page_token", token));
+            }
+
+            let records_res: BitableRecordsResponse = self
+                .send(|| self.http.get(&url).query(&query), true, retries)
+                .await?;
+
+            if records_res.code != 0 {
+                return Err(AppError::feishu(
+                    records_res.code as i64,
+                    &records_res.msg,
+                    "获取记录失败",
+                ));
+            }
+
+            let data = records_res
+                .data
+                .ok_or_else(|| AppError::parse("响应中缺少数据"))?;
+            all_records.extend(data.items);
+
+            if !data.has_more {
+                break;
+            }
+
+            page_token = data.page_token;
+            if page_token.is_none() {
+                break;
+            }
+        }
+
+        Ok(all_records)
+    }
+
+    // 通过 records/search 接口分页查询记录（只读，按幂等请求重试）
+    pub async fn search_records(
+        &self,
+        app_token: &str,
+        table_id: &str,
+        body: &serde_json::Value,
+        retries: &mut u32,
+    ) -> AppResult<Vec<AnswerRecord>> {
+        let url = format!("{}/search", self.records_url(app_token, table_id));
+        let mut all_records = Vec::new();
+        let mut page_token: Option<String> = None;
+
+        loop {
+            let mut query = vec![("page_size", "500")];
+            if let Some(token) = &page_token {
+                query.push(("page_token", token));
+            }
+
+            let request = || self.http.post(&url).query(&query).json(body);
+            let records_res: BitableRecordsResponse = self.send(request, true, retries).await?;
+
+            if records_res.code != 0 {
+                return Err(AppError::feishu(
+                    records_res.code as i64,
+                    &records_res.msg,
+                    "查询记录失败",
+                ));
+            }
+
+            let data = records_res
+                .data
+                .ok_or_else(|| AppError::parse("响应中缺少数据"))?;
+            all_records.extend(data.items);
+
+            if !data.has_more {
+                break;
+            }
+
+            page_token = data.page_token;
+            if page_token.is_none() {
+                break;
+            }
+        }
+
+        Ok(all_records)
+    }
+
+    pub async fn get_record(
+        &self,
+        app_token: &str,
+        table_id: &str,
+        record_id: &str,
+        retries: &mut u32,
+    ) -> AppResult<AnswerRecord> {
+        let url = format!("{}/{}", self.records_url(app_token, table_id), record_id);
+        // 带上 last_modified_time，写回前的冲突检测依赖它
+        let query = [("automatic_fields", "true")];
+        let record_res: BitableRecordResponse = self
+            .send(|| self.http.get(&url).query(&query), true, retries)
+            .await?;
+        record_res.into_record("获取记录失败")
+    }
+
+    // 创建不是幂等操作，只在被限流或 token 失效时重试
+    pub async fn create_record(
+        &self,
+        app_token: &str,
+        table_id: &str,
+        fields: &HashMap<String, serde_json::Value>,
+        retries: &mut u32,
+    ) -> AppResult<AnswerRecord> {
+        let url = self.records_url(app_token, table_id);
+        let body = serde_json::json!({ "fields": fields });
+        let record_res: BitableRecordResponse = self
+            .send(|| self.http.post(&url).json(&body), false, retries)
+            .await
+            .map_err(|e| e.context("创建失败"))?;
+        record_res.into_record("创建失败")
+    }
+
+    pub async fn update_record(
+        &self,
+        app_token: &str,
+        table_id: &str,
+        record_id: &str,
+        fields: &HashMap<String, serde_json::Value>,
+        retries: &mut u32,
+    ) -> AppResult<AnswerRecord> {
+        let url = format!("{}/{}", self.records_url(app_token, table_id), record_id);
+        let body = serde_json::json!({ "fields": fields });
+        let record_res: BitableRecordResponse = self
+            .send(|| self.http.put(&url).json(&body), true, retries)
+            .await
+            .map_err(|e| e.context("更新失败"))?;
+        record_res.into_record("更新失败")
+    }
+
+    pub async fn delete_record(
+        &self,
+        app_token: &str,
+        table_id: &str,
+        record_id: &str,
+        retries: &mut u32,
+    ) -> AppResult<()> {
+        let url = format!("{}/{}", self.records_url(app_token, table_id), record_id);
+        let delete_res: BitableResponse = self
+            .send(|| self.http.delete(&url), true, retries)
+            .await
+            .map_err(|e| e.context("删除失败"))?;
+        if delete_res.code != 0 {
+            return Err(AppError::feishu(
+                delete_res.code as i64,
+                &delete_res.msg,
+                "删除失败",
+            ));
+        }
+        Ok(())
+    }
+
+    // 批量创建记录，按 BATCH_LIMIT 分批，返回每条记录的结果（顺序与 records 一致）。
+    // 飞书对整批记录统一校验，一条出错整批都不会写入，此时逐条重试以找出出错的记录。
+    pub async fn batch_create_records(
+        &self,
+        app_token: &str,
+        table_id: &str,
+        records: &[HashMap<String, serde_json::Value>],
+        retries: &mut u32,
+    ) -> AppResult<Vec<BatchRecordResult>> {
+        // 凭证问题对所有批次都一样，直接返回错误而不是标记每条记录失败
+        self.access_token().await?;
+
+        let url = format!("{}/batch_create", self.records_url(app_token, table_id));
+        let mut results = Vec::with_capacity(records.len());
+
+        for (n, chunk) in records.chunks(BATCH_LIMIT).enumerate() {
+            let offset = n * BATCH_LIMIT;
+            let items: Vec<_> = chunk
+                .iter()
+                .map(|fields| serde_json::json!({ "fields": fields }))
+                .collect();
+            let body = serde_json::json!({ "records": items });
+
+            let response: AppResult<BitableBatchRecordsResponse> = self
+                .send(|| self.http.post(&url).json(&body), false, retries)
+                .await;
+            match response.and_then(|res| res.into_records("批量创建失败")) {
+                Ok(created) => results.extend(
+                    created
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, record)| BatchRecordResult::ok(offset + i, record.record_id)),
+                ),
+                Err(e) if e.kind == ErrorKind::Feishu && chunk.len() > 1 => {
+                    for (i, fields) in chunk.iter().enumerate() {
+                        let result = self
+                            .create_record(app_token, table_id, fields, retries)
+                            .await;
+                        results.push(match result {
+                            Ok(record) => BatchRecordResult::ok(offset + i, record.record_id),
+                            Err(e) => BatchRecordResult::failed(offset + i, None, e),
+                        });
+                    }
+                }
+                Err(e) => results.extend(
+                    (0..chunk.len())
+                        .map(|i| BatchRecordResult::failed(offset + i, None, e.clone())),
+                ),
+            }
+        }
+
+        Ok(results)
+    }
+
+    // 批量更新记录，分批和逐条重试的规则同 batch_create_records
+    pub async fn batch_update_records(
+        &self,
+        app_token: &str,
+        table_id: &str,
+        records: &[BatchUpdateItem],
+        retries: &mut u32,
+    ) -> AppResult<Vec<BatchRecordResult>> {
+        self.access_token().await?;
+
+        let url = format!("{}/batch_update", self.records_url(app_token, table_id));
+        let mut results = Vec::with_capacity(records.len());
+
+        for (n, chunk) in records.chunks(BATCH_LIMIT).enumerate() {
+            let offset = n * BATCH_LIMIT;
+            let body = serde_json::json!({ "records": chunk });
+
+            let response: AppResult<BitableBatchRecordsResponse> = self
+                .send(|| self.http.post(&url).json(&body), true, retries)
+                .await;
+            match response.and_then(|res| res.into_records("批量更新失败")) {
+                Ok(_) => results.extend(
+                    chunk
+                        .iter()
+                        .enumerate()
+                        .map(|(i, item)| BatchRecordResult::ok(offset + i, item.record_id.clone())),
+                ),
+                Err(e) if e.kind == ErrorKind::Feishu && chunk.len() > 1 => {
+                    for (i, item) in chunk.iter().enumerate() {
+                        let result = self
+                            .update_record(
+                                app_token,
+                                table_id,
+                                &item.record_id,
+                                &item.fields,
+                                retries,
+                            )
+                            .await;
+                        let record_id = Some(item.record_id.clone());
+                        results.push(match result {
+                            Ok(_) => BatchRecordResult::ok(offset + i, item.record_id.clone()),
+                            Err(e) => BatchRecordResult::failed(offset + i, record_id, e),
+                        });
+                    }
+                }
+                Err(e) => results.extend(chunk.iter().enumerate().map(|(i, item)| {
+                    BatchRecordResult::failed(offset + i, Some(item.record_id.clone()), e.clone())
+                })),
+            }
+        }
+
+        Ok(results)
+    }
+
+    // 批量删除记录，分批和逐条重试的规则同 batch_create_records
+    pub async fn batch_delete_records(
+        &self,
+        app_token: &str,
+        table_id: &str,
+        record_ids: &[String],
+        retries: &mut u32,
+    ) -> AppResult<Vec<BatchRecordResult>> {
+        self.access_token().await?;
+
+        let url = format!("{}/batch_delete", self.records_url(app_token, table_id));
+        let mut results = Vec::with_capacity(record_ids.len());
+
+        for (n, chunk) in record_ids.chunks(BATCH_LIMIT).enumerate() {
+            let offset = n * BATCH_LIMIT;
+            let body = serde_json::json!({ "records": chunk });
+
+            let response: AppResult<BitableBatchDeleteResponse> = self
+                .send(|| self.http.post(&url).json(&body), true, retries)
+                .await;
+            let response = response.and_then(|res| {
+                if res.code != 0 {
+                    return Err(AppError::feishu(res.code as i64, &res.msg, "批量删除失败"));
+                }
+                res.data.ok_or_else(|| AppError::parse("响应中缺少数据"))
+            });
+            match response {
+                Ok(data) => {
+                    let deleted: HashSet<_> = data
+                        .records
+                        .into_iter()
+                        .filter(|r| r.deleted)
+                        .map(|r| r.record_id)
+                        .collect();
+                    results.extend(chunk.iter().enumerate().map(|(i, record_id)| {
+                        if deleted.contains(record_id) {
+                            BatchRecordResult::ok(offset + i, record_id.clone())
+                        } else {
+                            let err =
+                                AppError::new(ErrorKind::Feishu, "批量删除失败: 记录未被删除");
+                            BatchRecordResult::failed(offset + i, Some(record_id.clone()), err)
+                        }
+                    }));
+                }
+                Err(e) if e.kind == ErrorKind::Feishu && chunk.len() > 1 => {
+                    for (i, record_id) in chunk.iter().enumerate() {
+                        let result = self
+                            .delete_record(app_token, table_id, record_id, retries)
+                            .await;
+                        results.push(match result {
+                            Ok(()) => BatchRecordResult::ok(offset + i, record_id.clone()),
+                            Err(e) => {
+                                BatchRecordResult::failed(offset + i, Some(record_id.clone()), e)
+                            }
+                        });
+                    }
+                }
+                Err(e) => results.extend(chunk.iter().enumerate().map(|(i, record_id)| {
+                    BatchRecordResult::failed(offset + i, Some(record_id.clone()), e.clone())
+                })),
+            }
+        }
+
+        Ok(results)
+    }
+
+    // 分页获取表的字段列表
+    pub async fn list_fields(
+        &self,
+        app_token: &str,
+        table_id: &str,
+        retries: &mut u32,
+    ) -> AppResult<Vec<BitableField>> {
+        let url = self.url(&format!(
+            "/bitable/v1/apps/{}/tables/{}/fields",
+            app_token, table_id
+        ));
+        let mut all_fields = Vec::new();
+        let mut page_token: Option<String> = None;
+
+        loop {
+            let mut query = Vec::new();
+            if let Some(token) = &page_token {
+                query.push(("page_token", token));
+            }
+
+            let fields_res: BitableFieldsResponse = self
+                .send(|| self.http.get(&url).query(&query), true, retries)
+                .await?;
+
+            if fields_res.code != 0 {
+                return Err(AppError::feishu(
+                    fields_res.code as i64,
+                    &fields_res.msg,
+                    "获取字段列表失败",
+                ));
+            }
+
+            let data = fields_res
+                .data
+                .ok_or_else(|| AppError::parse("响应中缺少数据"))?;
+            all_fields.extend(data.items);
+
+            if !data.has_more {
+                break;
+            }
+
+            page_token = data.page_token;
+            if page_token.is_none() {
+                break;
+            }
+        }
+
+        Ok(all_fields)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_feishu::{MockFeishu, APP_TOKEN, CODE_FIELD_NOT_FOUND, CODE_RECORD_NOT_FOUND};
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    const TABLE: &str = "tblAnswers";
+
+    #[tokio::test]
+    async fn token_is_cached_between_requests() {
+        let mock = MockFeishu::start().await;
+        mock.add_table(TABLE, "Answers");
+        let client = mock.client();
+
+        let tables = client.list_tables(APP_TOKEN, &mut 0).await.unwrap();
+        client.list_tables(APP_TOKEN, &mut 0).await.unwrap();
+
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].name, "Answers");
+        assert_eq!(mock.token_requests(), 1);
+    }
+
+    #[tokio::test]
+    async fn concurrent_requests_share_one_token_refresh() {
+        let mock = MockFeishu::start().await;
+        let client = mock.client();
+
+        let (mut ra, mut rb, mut rc) = (0, 0, 0);
+        let (a, b, c) = tokio::join!(
+            client.list_tables(APP_TOKEN, &mut ra),
+            client.list_tables(APP_TOKEN, &mut rb),
+            client.list_tables(APP_TOKEN, &mut rc),
+        );
+
+        assert!(a.is_ok() && b.is_ok() && c.is_ok());
+        assert_eq!(mock.token_requests(), 1);
+    }
+
+    #[tokio::test]
+    async fn invalid_token_is_refreshed_and_request_replayed() {
+        let mock = MockFeishu::start().await;
+        let client = mock.client();
+        client.list_tables(APP_TOKEN, &mut 0).await.unwrap();
+
+        mock.revoke_tokens();
+        let mut retries = 0;
+        client.list_tables(APP_TOKEN, &mut retries).await.unwrap();
+
+        assert_eq!(mock.token_requests(), 2);
+        assert_eq!(retries, 1);
+    }
+
+    #[tokio::test]
+    async fn token_invalid_codes_on_data_apis_are_replayed_once() {
+        for code in [99991663, 99991677] {
+            let mock = MockFeishu::start().await;
+            mock.add_record(TABLE, json!({ "问题": "q" }));
+            let client = mock.client();
+            client.list_records(APP_TOKEN, TABLE, &mut 0).await.unwrap();
+
+            mock.revoke_tokens_with(code);
+            let mut retries = 0;
+            let records = client
+                .list_records(APP_TOKEN, TABLE, &mut retries)
+                .await
+                .unwrap();
+
+            assert_eq!(records.len(), 1);
+            assert_eq!(mock.token_requests(), 2);
+            assert_eq!(retries, 1);
+            let path = format!("GET /apps/{}/tables/{}/records", APP_TOKEN, TABLE);
+            assert_eq!(mock.request_count(&path), 3);
+        }
+    }
+
+    #[tokio::test]
+    async fn wrong_app_id_is_reported() {
+        let mock = MockFeishu::start().await;
+        let client = mock.client();
+        let credentials = FeishuCredentials {
+            app_id: "cli_wrong".to_string(),
+            app_secret: crate::mock_feishu::APP_SECRET.to_string(),
+        };
+
+        let err = client
+            .request_token(&mock.base_url, &credentials)
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.kind, ErrorKind::InvalidAppId);
+    }
+
+    #[tokio::test]
+    async fn wrong_app_secret_is_reported() {
+        let mock = MockFeishu::start().await;
+        let client = mock.client();
+        let credentials = FeishuCredentials {
+            app_id: crate::mock_feishu::APP_ID.to_string(),
+            app_secret: "wrong".to_string(),
+        };
+
+        let err = client
+            .request_token(&mock.base_url, &credentials)
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.kind, ErrorKind::InvalidAppSecret);
+    }
+
+    #[tokio::test]
+    async fn list_records_follows_pagination() {
+        let mock = MockFeishu::start().await;
+        mock.set_page_size(2);
+        for i in 0..5 {
+            mock.add_record(TABLE, json!({ "问题": format!("q{}", i) }));
+        }
+        let client = mock.client();
+
+        let records = client.list_records(APP_TOKEN, TABLE, &mut 0).await.unwrap();
+
+        let questions: Vec<_> = records.iter().map(|r| r.fields["问题"].clone()).collect();
+        assert_eq!(questions, vec!["q0", "q1", "q2", "q3", "q4"]);
+        assert!(records.iter().all(|r| r.last_modified_time.is_some()));
+        let path = format!("GET /apps/{}/tables/{}/records", APP_TOKEN, TABLE);
+        assert_eq!(mock.request_count(&path), 3);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_page_is_retried() {
+        let mock = MockFeishu::start().await;
+        mock.set_page_size(1);
+        mock.add_record(TABLE, json!({ "问题": "q0" }));
+        mock.add_record(TABLE, json!({ "问题": "q1" }));
+        let client = mock.client();
+        client.list_tables(APP_TOKEN, &mut 0).await.unwrap();
+
+        mock.push_failure(
+            StatusCode::TOO_MANY_REQUESTS,
+            r#"{"code":99991400,"msg":"request trigger frequency limit"}"#,
+        );
+        let mut retries = 0;
+        let records = client
+            .list_records(APP_TOKEN, TABLE, &mut retries)
+            .await
+            .unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(retries, 1);
+    }
+
+    #[tokio::test]
+    async fn server_errors_give_up_after_max_retries() {
+        let mock = MockFeishu::start().await;
+        let client = mock.client();
+        client.list_tables(APP_TOKEN, &mut 0).await.unwrap();
+
+        for _ in 0..3 {
+            mock.push_failure(StatusCode::BAD_GATEWAY, "bad gateway");
+        }
+        let err = client.list_tables(APP_TOKEN, &mut 0).await.unwrap_err();
+
+        assert_eq!(err.kind, ErrorKind::Http);
+        assert_eq!(err.status, Some(502));
+        assert_eq!(err.retries, 2);
+    }
+
+    #[tokio::test]
+    async fn create_is_not_retried_on_server_error() {
+        let mock = MockFeishu::start().await;
+        let client = mock.client();
+        client.list_tables(APP_TOKEN, &mut 0).await.unwrap();
+
+        mock.push_failure(StatusCode::INTERNAL_SERVER_ERROR, "oops");
+        let fields = HashMap::from([("问题".to_string(), json!("q"))]);
+        let mut retries = 0;
+        let err = client
+            .create_record(APP_TOKEN, TABLE, &fields, &mut retries)
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.status, Some(500));
+        assert_eq!(retries, 0);
+        assert_eq!(mock.record_count(TABLE), 0);
+    }
+
+    #[tokio::test]
+    async fn record_crud_round_trip() {
+        let mock = MockFeishu::start().await;
+        let client = mock.client();
+        let fields = HashMap::from([("问题".to_string(), json!("q"))]);
+
+        let created = client
+            .create_record(APP_TOKEN, TABLE, &fields, &mut 0)
+            .await
+            .unwrap();
+        let fetched = client
+            .get_record(APP_TOKEN, TABLE, &created.record_id, &mut 0)
+            .await
+            .unwrap();
+        assert_eq!(fetched.fields["问题"], "q");
+
+        client
+            .delete_record(APP_TOKEN, TABLE, &created.record_id, &mut 0)
+            .await
+            .unwrap();
+        let err = client
+            .get_record(APP_TOKEN, TABLE, &created.record_id, &mut 0)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Feishu);
+        assert_eq!(err.code, Some(CODE_RECORD_NOT_FOUND));
+    }
+
+    #[tokio::test]
+    async fn list_fields_returns_select_options() {
+        let mock = MockFeishu::start().await;
+        mock.add_field(TABLE, "问题", 1);
+        mock.add_field(TABLE, "状态", 3);
+        let client = mock.client();
+
+        let fields = client.list_fields(APP_TOKEN, TABLE, &mut 0).await.unwrap();
+
+        let names: Vec<_> = fields.iter().map(|f| f.field_name.as_str()).collect();
+        assert_eq!(names, vec!["问题", "状态"]);
+        assert_eq!(fields[1].field_type, 3);
+    }
+
+    #[tokio::test]
+    async fn batch_create_is_split_into_chunks() {
+        let mock = MockFeishu::start().await;
+        let client = mock.client();
+        let records: Vec<_> = (0..BATCH_LIMIT * 2 + 1)
+            .map(|i| HashMap::from([("问题".to_string(), json!(format!("q{}", i)))]))
+            .collect();
+
+        let results = client
+            .batch_create_records(APP_TOKEN, TABLE, &records, &mut 0)
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), records.len());
+        assert!(results.iter().all(|r| r.success));
+        assert!(results.iter().enumerate().all(|(i, r)| r.index == i));
+        assert_eq!(mock.record_count(TABLE), records.len());
+        let path = format!(
+            "POST /apps/{}/tables/{}/records/batch_create",
+            APP_TOKEN, TABLE
+        );
+        assert_eq!(mock.request_count(&path), 3);
+    }
+
+    #[tokio::test]
+    async fn batch_create_isolates_rejected_record() {
+        let mock = MockFeishu::start().await;
+        mock.add_field(TABLE, "问题", 1);
+        let client = mock.client();
+        let records = vec![
+            HashMap::from([("问题".to_string(), json!("q1"))]),
+            HashMap::from([("不存在的列".to_string(), json!("x"))]),
+            HashMap::from([("问题".to_string(), json!("q3"))]),
+        ];
+
+        let results = client
+            .batch_create_records(APP_TOKEN, TABLE, &records, &mut 0)
+            .await
+            .unwrap();
+
+        let success: Vec<_> = results.iter().map(|r| r.success).collect();
+        assert_eq!(success, vec![true, false, true]);
+        let err = results[1].error.as_ref().unwrap();
+        assert_eq!(err.code, Some(CODE_FIELD_NOT_FOUND));
+        assert_eq!(results[1].record_id, None);
+        assert_eq!(mock.record_count(TABLE), 2);
+    }
+
+    #[tokio::test]
+    async fn batch_update_reports_missing_record() {
+        let mock = MockFeishu::start().await;
+        let first = mock.add_record(TABLE, json!({ "问题": "q1" }));
+        let second = mock.add_record(TABLE, json!({ "问题": "q2" }));
+        let client = mock.client();
+        let records: Vec<_> = [&first, "recMissing", &second]
+            .iter()
+            .map(|id| BatchUpdateItem {
+                record_id: id.to_string(),
+                fields: HashMap::from([("状态".to_string(), json!("已审核"))]),
+            })
+            .collect();
+
+        let results = client
+            .batch_update_records(APP_TOKEN, TABLE, &records, &mut 0)
+            .await
+            .unwrap();
+
+        let success: Vec<_> = results.iter().map(|r| r.success).collect();
+        assert_eq!(success, vec![true, false, true]);
+        assert_eq!(results[1].record_id.as_deref(), Some("recMissing"));
+        let err = results[1].error.as_ref().unwrap();
+        assert_eq!(err.code, Some(CODE_RECORD_NOT_FOUND));
+        assert_eq!(
+            mock.record(TABLE, &first).unwrap()["fields"]["状态"],
+            "已审核"
+        );
+        assert_eq!(mock.record(TABLE, &second).unwrap()["fields"]["问题"], "q2");
+    }
+
+    #[tokio::test]
+    async fn batch_delete_removes_records() {
+        let mock = MockFeishu::start().await;
+        let ids: Vec<_> = (0..3)
+            .map(|i| mock.add_record(TABLE, json!({ "问题": i })))
+            .collect();
+        let client = mock.client();
+
+        let results = client
+            .batch_delete_records(APP_TOKEN, TABLE, &ids[..2], &mut 0)
+            .await
+            .unwrap();
+
+        assert!(results.iter().all(|r| r.success));
+        assert_eq!(mock.record_count(TABLE), 1);
+        assert!(mock.record(TABLE, &ids[2]).is_some());
+    }
+
+    #[tokio::test]
+    async fn batch_server_error_fails_whole_chunk() {
+        let mock = MockFeishu::start().await;
+        let ids: Vec<_> = (0..2)
+            .map(|i| mock.add_record(TABLE, json!({ "问题": i })))
+            .collect();
+        let client = mock.client();
+        client.access_token().await.unwrap();
+        for _ in 0..3 {
+            mock.push_failure(StatusCode::BAD_GATEWAY, "bad gateway");
+        }
+
+        let mut retries = 0;
+        let results = client
+            .batch_delete_records(APP_TOKEN, TABLE, &ids, &mut retries)
+            .await
+            .unwrap();
+
+        assert!(results.iter().all(|r| !r.success));
+        assert_eq!(results[0].error.as_ref().unwrap().status, Some(502));
+        assert_eq!(retries, 2);
+        assert_eq!(mock.record_count(TABLE), 2);
+    }
+
+    #[tokio::test]
+    async fn batch_without_credentials_is_an_error() {
+        let mock = MockFeishu::start().await;
+        let client = FeishuClient::new(None, &mock.endpoint()).unwrap();
+
+        let err = client
+            .batch_delete_records(APP_TOKEN, TABLE, &["rec".to_string()], &mut 0)
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.kind, ErrorKind::NotConfigured);
+    }
+
+    #[test]
+    fn custom_endpoint_is_normalized() {
+        let endpoint = FeishuEndpoint::Custom {
+            base_url: " https://open.example.com/ ".to_string(),
+        };
+        assert_eq!(
+            endpoint.normalize().unwrap().base_url(),
+            "https://open.example.com/open-apis"
+        );
+
+        let invalid = FeishuEndpoint::Custom {
+            base_url: "open.example.com".to_string(),
+        };
+        assert_eq!(
+            invalid.normalize().unwrap_err().kind,
+            ErrorKind::InvalidInput
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use rand::Rng;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::error::{AppError, AppResult, CODE_RATE_LIMITED, CODE_TOKEN_INVALID};
+
+// 重试策略：指数退避 + 随机抖动
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    pub max_retries: u32,   // 最多重试次数（不含首次请求）
+    pub base_delay_ms: u64, // 第一次重试前的等待时间
+    pub max_delay_ms: u64,  // 单次等待上限（限流响应给出的等待时间也受此限制）
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay_ms: 500,
+            max_delay_ms: 10_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    // 第 attempt 次重试（从 0 开始）的等待时间，在 [d/2, d] 之间随机
+    fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .base_delay_ms
+            .saturating_mul(1u64 << attempt.min(16))
+            .min(self.max_delay_ms);
+        let jittered = rand::thread_rng().gen_range(delay / 2..=delay);
+        Duration::from_millis(jittered)
+    }
+}
+
+// 一次请求的重试判断结果
+enum Outcome {
+    Done(serde_json::Value),
+    Retry(AppError, Option<Duration>), // 可重试的错误，以及服务端要求的等待时间
+    Fail(AppError),
+}
+
+// 发送请求并解析 JSON 响应，遇到可恢复的错误时按重试策略重试。
+// idempotent 为 false 的请求（如创建记录）只在被限流时重试，避免重复写入。
+// retries 累加实际发生的重试次数，由调用方汇总后返回给前端。
+pub async fn send_json<T: DeserializeOwned>(
+    request: RequestBuilder,
+    policy: RetryPolicy,
+    idempotent: bool,
+    retries: &mut u32,
+) -> AppResult<T> {
+    let mut attempt = 0;
+
+    loop {
+        let current = request
+            .try_clone()
+            .ok_or_else(|| AppError::internal("请求无法重试"))?;
+
+        let outcome = match current.send().await {
+            Ok(response) => classify(response, idempotent).await,
+            Err(e) => {
+                let err = AppError::network(e);
+                if idempotent && err.retryable {
+                    Outcome::Retry(err, None)
+                } else {
+                    Outcome::Fail(err)
+                }
+            }
+        };
+
+        match outcome {
+            Outcome::Done(value) => {
+                return serde_json::from_value(value)
+                    .map_err(|e| AppError::parse(format!("解析响应失败: {}", e)));
+            }
+            Outcome::Fail(err) => return Err(err.with_retries(*retries)),
+            Outcome::Retry(err, wait) => {
+                if attempt >= policy.max_retries {
+                    return Err(err.with_retries(*retries));
+                }
+                let delay = wait
+                    .map(|d| d.min(Duration::from_millis(policy.max_delay_ms)))
+                    .unwrap_or_else(|| policy.backoff(attempt));
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+                *retries += 1;
+            }
+        }
+    }
+}
+
+async fn classify(response: Response, idempotent: bool) -> Outcome {
+    let status = response.status();
+    let wait = rate_limit_wait(&response);
+    let text = match response.text().await {
+        Ok(text) => text,
+        Err(e) => {
+            let err = AppError::network(e);
+            return if idempotent {
+                Outcome::Retry(err, None)
+            } else {
+                Outcome::Fail(err)
+            };
+        }
+    };
+    let body: Option<serde_json::Value> = serde_json::from_str(&text).ok();
+    let code = body
+        .as_ref()
+        .and_then(|v| v.get("code"))
+        .and_then(|v| v.as_i64());
+
+    // 被限流的请求没有被执行，非幂等请求也可以安全重试
+    if status == StatusCode::TOO_MANY_REQUESTS || code == Some(CODE_RATE_LIMITED) {
+        let msg = body
+            .as_ref()
+            .and_then(|v| v.get("msg"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("请求过于频繁");
+        let err = match code {
+            Some(CODE_RATE_LIMITED) => AppError::feishu(CODE_RATE_LIMITED, msg, "请求被限流"),
+            _ => AppError::http(status, format!("请求被限流: {}", msg)),
+        };
+        return Outcome::Retry(err, wait);
+    }
+
+    if status.is_server_error() {
+        let err = AppError::http(status, format!("服务端错误 ({}): {}", status, text));
+        return if idempotent {
+            Outcome::Retry(err, wait)
+        } else {
+            Outcome::Fail(err)
+        };
+    }
+
+    // token 失效单独标记，由调用方刷新 token 后重放
+    if let Some(code) = code.filter(|c| CODE_TOKEN_INVALID.contains(c)) {
+        let msg = body
+            .as_ref()
+            .and_then(|v| v.get("msg"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("访问凭证无效");
+        return Outcome::Fail(AppError::feishu(code, msg, "访问凭证失效"));
+    }
+
+    match body {
+        // 飞书的业务错误也可能以 4xx 返回，交给调用方按 code 处理
+        Some(value) if status.is_success() || code.is_some() => Outcome::Done(value),
+        _ if !status.is_success() => Outcome::Fail(AppError::http(
+            status,
+            format!("请求失败 ({}): {}", status, text),
+        )),
+        _ => Outcome::Fail(AppError::parse(format!("解析响应失败: {}", text))),
+    }
+}
+
+// 限流响应中的等待时间：优先 Retry-After，其次飞书网关的 x-ogw-ratelimit-reset（秒）
+fn rate_limit_wait(response: &Response) -> Option<Duration> {
+    ["retry-after", "x-ogw-ratelimit-reset"]
+        .iter()
+        .filter_map(|name| response.headers().get(*name))
+        .filter_map(|value| value.to_str().ok())
+        .find_map(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
@@ -0,0 +1,352 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::error::{AppError, AppResult};
+
+const MAPPING_FILE: &str = "field_mappings.json";
+
+// Answer 逻辑字段到飞书列名的映射，每个逻辑字段可配置多个别名（按顺序匹配）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldMapping {
+    pub question: Vec<String>,
+    pub standard_answer: Vec<String>,
+    pub enable_status: Vec<String>,
+    pub scene: Vec<String>,
+    pub tone: Vec<String>,
+    pub product_name: Vec<String>,
+    pub product_id: Vec<String>,
+}
+
+impl Default for FieldMapping {
+    // 默认使用 Answers 表的中文列名
+    fn default() -> Self {
+        let names = |names: &[&str]| names.iter().map(|s| s.to_string()).collect();
+        Self {
+            question: names(&["问题"]),
+            standard_answer: names(&["标准回答"]),
+            enable_status: names(&["状态"]),
+            scene: names(&["使用场景"]),
+            tone: names(&["语气"]),
+            product_name: names(&["对应产品"]),
+            product_id: names(&["product_id"]),
+        }
+    }
+}
+
+// 缺少这些字段时 list_answers 会过滤掉所有记录，保存时必须能匹配到
+const REQUIRED_FIELDS: &[&str] = &["question", "standard_answer"];
+
+impl FieldMapping {
+    // (逻辑字段名, 别名列表)
+    fn entries(&self) -> [(&'static str, &Vec<String>); 7] {
+        [
+            ("question", &self.question),
+            ("standard_answer", &self.standard_answer),
+            ("enable_status", &self.enable_status),
+            ("scene", &self.scene),
+            ("tone", &self.tone),
+            ("product_name", &self.product_name),
+            ("product_id", &self.product_id),
+        ]
+    }
+
+    fn aliases(&self, logical: &str) -> Option<&Vec<String>> {
+        self.entries()
+            .into_iter()
+            .find(|(name, _)| *name == logical)
+            .map(|(_, aliases)| aliases)
+    }
+
+    // 用表格实际的列名校验映射。别名不能为空白；两个逻辑字段匹配到同一列时写回会互相覆盖，也视为无效
+    pub fn validate(&self, table_fields: &[String]) -> FieldMappingValidation {
+        let mut columns: HashMap<String, String> = HashMap::new();
+        let mut unmatched = Vec::new();
+        let mut missing_required = Vec::new();
+        let mut empty_aliases = Vec::new();
+        let mut duplicate_columns = Vec::new();
+
+        for (logical, aliases) in self.entries() {
+            if aliases.iter().any(|alias| alias.trim().is_empty()) {
+                empty_aliases.push(logical.to_string());
+            }
+            match aliases.iter().find(|alias| table_fields.contains(alias)) {
+                Some(column) => {
+                    if columns.values().any(|c| c == column) && !duplicate_columns.contains(column)
+                    {
+                        duplicate_columns.push(column.clone());
+                    }
+                    columns.insert(logical.to_string(), column.clone());
+                }
+                None => {
+                    unmatched.push(logical.to_string());
+                    if REQUIRED_FIELDS.contains(&logical) {
+                        missing_required.push(logical.to_string());
+                    }
+                }
+            }
+        }
+
+        FieldMappingValidation {
+            valid: missing_required.is_empty()
+                && empty_aliases.is_empty()
+                && duplicate_columns.is_empty(),
+            columns,
+            unmatched,
+            missing_required,
+            empty_aliases,
+            duplicate_columns,
+        }
+    }
+}
+
+// 映射校验结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldMappingValidation {
+    pub valid: bool,
+    pub columns: HashMap<String, String>, // 逻辑字段 -> 表格中实际匹配到的列名
+    pub unmatched: Vec<String>,           // 没有匹配到任何列的逻辑字段
+    pub missing_required: Vec<String>,    // 没有匹配到的必填字段
+    pub empty_aliases: Vec<String>,       // 含有空白别名的逻辑字段
+    pub duplicate_columns: Vec<String>,   // 被多个逻辑字段匹配到的列
+}
+
+impl FieldMappingValidation {
+    // 无效时给用户看的原因
+    pub fn error_message(&self) -> Option<String> {
+        let mut reasons = Vec::new();
+        if !self.missing_required.is_empty() {
+            reasons.push(format!(
+                "以下必填字段在表格中找不到对应列: {}",
+                self.missing_required.join(", ")
+            ));
+        }
+        if !self.empty_aliases.is_empty() {
+            reasons.push(format!(
+                "以下字段含有空的列名: {}",
+                self.empty_aliases.join(", ")
+            ));
+        }
+        if !self.duplicate_columns.is_empty() {
+            reasons.push(format!(
+                "以下列被多个字段同时使用: {}",
+                self.duplicate_columns.join(", ")
+            ));
+        }
+        (!reasons.is_empty()).then(|| format!("字段映射无效，{}", reasons.join("；")))
+    }
+}
+
+// 某张表保存的映射配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TableFieldMapping {
+    pub mapping: FieldMapping,
+    pub columns: HashMap<String, String>, // 保存时校验得到的实际列名，写回飞书时使用
+}
+
+impl TableFieldMapping {
+    // 写回飞书前把逻辑字段名（如 standard_answer）替换为实际列名，其他键原样保留
+    pub fn to_columns(
+        &self,
+        fields: HashMap<String, serde_json::Value>,
+    ) -> HashMap<String, serde_json::Value> {
+        fields
+            .into_iter()
+            .map(|(key, value)| {
+                let column = self.columns.get(&key).cloned().or_else(|| {
+                    self.mapping
+                        .aliases(&key)
+                        .and_then(|aliases| aliases.first().cloned())
+                });
+                (column.unwrap_or(key), value)
+            })
+            .collect()
+    }
+}
+
+// 按表持久化的字段映射（应用数据目录下的 JSON 文件）
+pub struct FieldMappingStore {
+    path: PathBuf,
+    mappings: Mutex<HashMap<String, TableFieldMapping>>,
+}
+
+impl FieldMappingStore {
+    pub fn load(data_dir: &Path) -> AppResult<Self> {
+        let path = data_dir.join(MAPPING_FILE);
+        let mappings = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .map_err(|e| AppError::storage(format!("读取字段映射失败: {}", e)))?;
+            serde_json::from_str(&content)
+                .map_err(|e| AppError::storage(format!("解析字段映射失败: {}", e)))?
+        } else {
+            HashMap::new()
+        };
+        Ok(Self {
+            path,
+            mappings: Mutex::new(mappings),
+        })
+    }
+
+    // 未配置的表使用默认映射
+    pub fn get(&self, app_token: &str, table_id: &str) -> TableFieldMapping {
+        self.mappings
+            .lock()
+            .unwrap()
+            .get(&table_key(app_token, table_id))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn set(
+        &self,
+        app_token: &str,
+        table_id: &str,
+        mapping: TableFieldMapping,
+    ) -> AppResult<()> {
+        let mut guard = self.mappings.lock().unwrap();
+        let mut next = guard.clone();
+        next.insert(table_key(app_token, table_id), mapping);
+        let content = serde_json::to_string_pretty(&next)
+            .map_err(|e| AppError::storage(format!("序列化字段映射失败: {}", e)))?;
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, content)
+            .map_err(|e| AppError::storage(format!("保存字段映射失败: {}", e)))?;
+        fs::rename(&tmp_path, &self.path)
+            .map_err(|e| AppError::storage(format!("保存字段映射失败: {}", e)))?;
+        *guard = next;
+        Ok(())
+    }
+}
+
+fn table_key(app_token: &str, table_id: &str) -> String {
+    format!("{}/{}", app_token, table_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn columns(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn aliases(names: &[&str]) -> Vec<String> {
+        columns(names)
+    }
+
+    #[test]
+    fn validate_matches_first_existing_alias() {
+        let mapping = FieldMapping {
+            question: aliases(&["Question", "问题"]),
+            standard_answer: aliases(&["Answer"]),
+            ..FieldMapping::default()
+        };
+
+        let validation = mapping.validate(&columns(&["问题", "Answer", "状态"]));
+
+        assert!(validation.valid);
+        assert_eq!(validation.columns["question"], "问题");
+        assert_eq!(validation.columns["standard_answer"], "Answer");
+        assert_eq!(validation.columns["enable_status"], "状态");
+        assert_eq!(
+            validation.unmatched,
+            vec!["scene", "tone", "product_name", "product_id"]
+        );
+        assert!(validation.error_message().is_none());
+    }
+
+    #[test]
+    fn validate_reports_missing_required_columns() {
+        let validation = FieldMapping::default().validate(&columns(&["问题", "状态"]));
+
+        assert!(!validation.valid);
+        assert_eq!(validation.missing_required, vec!["standard_answer"]);
+        assert!(validation
+            .error_message()
+            .unwrap()
+            .contains("standard_answer"));
+    }
+
+    #[test]
+    fn validate_rejects_empty_aliases() {
+        let mapping = FieldMapping {
+            tone: aliases(&["语气", " "]),
+            scene: Vec::new(),
+            ..FieldMapping::default()
+        };
+
+        let validation = mapping.validate(&columns(&["问题", "标准回答", "语气", " "]));
+
+        assert!(!validation.valid);
+        assert_eq!(validation.empty_aliases, vec!["tone"]);
+        // 没有配置任何别名只是匹配不到，不算无效
+        assert!(validation.unmatched.contains(&"scene".to_string()));
+        assert!(validation.error_message().unwrap().contains("tone"));
+    }
+
+    #[test]
+    fn validate_rejects_column_used_by_two_fields() {
+        let mapping = FieldMapping {
+            standard_answer: aliases(&["标准回答"]),
+            scene: aliases(&["场景", "标准回答"]),
+            ..FieldMapping::default()
+        };
+
+        let validation = mapping.validate(&columns(&["问题", "标准回答"]));
+
+        assert!(!validation.valid);
+        assert_eq!(validation.duplicate_columns, vec!["标准回答"]);
+        assert!(validation.missing_required.is_empty());
+    }
+
+    #[test]
+    fn to_columns_uses_saved_columns_then_first_alias() {
+        let table = TableFieldMapping {
+            mapping: FieldMapping {
+                tone: aliases(&["Tone", "语气"]),
+                ..FieldMapping::default()
+            },
+            columns: HashMap::from([("standard_answer".to_string(), "Answer".to_string())]),
+        };
+        let fields = HashMap::from([
+            ("standard_answer".to_string(), json!("新回答")),
+            ("tone".to_string(), json!("亲切")),
+            ("最新版本来源".to_string(), json!("AI优化")),
+        ]);
+
+        let written = table.to_columns(fields);
+
+        assert_eq!(written.len(), 3);
+        assert_eq!(written["Answer"], "新回答");
+        assert_eq!(written["Tone"], "亲切");
+        assert_eq!(written["最新版本来源"], "AI优化");
+    }
+
+    #[test]
+    fn store_persists_mappings_per_table() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FieldMappingStore::load(dir.path()).unwrap();
+        let saved = TableFieldMapping {
+            mapping: FieldMapping {
+                question: aliases(&["Question"]),
+                ..FieldMapping::default()
+            },
+            columns: HashMap::from([("question".to_string(), "Question".to_string())]),
+        };
+        store.set("bascnA", "tbl1", saved).unwrap();
+
+        let reloaded = FieldMappingStore::load(dir.path()).unwrap();
+        assert_eq!(
+            reloaded.get("bascnA", "tbl1").mapping.question,
+            vec!["Question"]
+        );
+        // 其他表仍使用默认映射
+        assert_eq!(
+            reloaded.get("bascnB", "tbl1").mapping.question,
+            vec!["问题"]
+        );
+    }
+}
@@ -0,0 +1,699 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+
+// 飞书多维表格字段类型
+pub const TYPE_TEXT: i32 = 1;
+pub const TYPE_NUMBER: i32 = 2;
+pub const TYPE_SINGLE_SELECT: i32 = 3;
+pub const TYPE_MULTI_SELECT: i32 = 4;
+pub const TYPE_DATE: i32 = 5;
+pub const TYPE_CHECKBOX: i32 = 7;
+pub const TYPE_PERSON: i32 = 11;
+pub const TYPE_PHONE: i32 = 13;
+pub const TYPE_URL: i32 = 15;
+pub const TYPE_ATTACHMENT: i32 = 17;
+pub const TYPE_SINGLE_LINK: i32 = 18;
+pub const TYPE_LOOKUP: i32 = 19;
+pub const TYPE_FORMULA: i32 = 20;
+pub const TYPE_DUPLEX_LINK: i32 = 21;
+pub const TYPE_CREATED_TIME: i32 = 1001;
+pub const TYPE_MODIFIED_TIME: i32 = 1002;
+pub const TYPE_CREATED_USER: i32 = 1003;
+pub const TYPE_MODIFIED_USER: i32 = 1004;
+pub const TYPE_AUTO_NUMBER: i32 = 1005;
+
+// 多行文本中的一段（普通文本、链接、@人 等）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TextSegment {
+    pub segment_type: String,
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Person {
+    pub id: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Attachment {
+    pub file_token: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+// 解码后的字段值
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum FieldValue {
+    Text(String),
+    RichText(Vec<TextSegment>),
+    Number(f64),
+    SingleSelect(String),
+    MultiSelect(Vec<String>),
+    Person(Vec<Person>),
+    Date(i64), // 毫秒时间戳
+    Checkbox(bool),
+    Url {
+        text: String,
+        link: String,
+    },
+    Attachment(Vec<Attachment>),
+    LinkedRecord {
+        record_ids: Vec<String>,
+        texts: Vec<String>,
+    },
+    Unknown(Value),
+}
+
+impl FieldValue {
+    // 按字段类型解码；没有表结构时（field_type 为 None）按值的形状推断。空值返回 None
+    pub fn decode(field_type: Option<i32>, value: &Value) -> Option<FieldValue> {
+        let decoded = match field_type {
+            Some(TYPE_TEXT) | Some(TYPE_PHONE) | Some(TYPE_AUTO_NUMBER) => decode_text(value),
+            Some(TYPE_NUMBER) => value.as_f64().map(FieldValue::Number),
+            Some(TYPE_SINGLE_SELECT) => match value {
+                Value::String(s) => Some(FieldValue::SingleSelect(s.clone())),
+                _ => select_names(value)
+                    .into_iter()
+                    .next()
+                    .map(FieldValue::SingleSelect),
+            },
+            Some(TYPE_MULTI_SELECT) => Some(FieldValue::MultiSelect(select_names(value))),
+            Some(TYPE_DATE) | Some(TYPE_CREATED_TIME) | Some(TYPE_MODIFIED_TIME) => {
+                value.as_i64().map(FieldValue::Date)
+            }
+            Some(TYPE_CHECKBOX) => value.as_bool().map(FieldValue::Checkbox),
+            Some(TYPE_PERSON) | Some(TYPE_CREATED_USER) | Some(TYPE_MODIFIED_USER) => {
+                Some(FieldValue::Person(decode_persons(value)))
+            }
+            Some(TYPE_URL) => decode_url(value),
+            Some(TYPE_ATTACHMENT) => Some(FieldValue::Attachment(decode_attachments(value))),
+            Some(TYPE_SINGLE_LINK) | Some(TYPE_DUPLEX_LINK) => decode_link(value),
+            // 查找引用 / 公式：{ "type": 实际类型, "value": [...] }
+            Some(TYPE_LOOKUP) | Some(TYPE_FORMULA) => decode_typed_wrapper(value),
+            _ => infer(value),
+        };
+        decoded.filter(|v| !v.is_empty())
+    }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            FieldValue::Text(s) | FieldValue::SingleSelect(s) => s.is_empty(),
+            FieldValue::RichText(segments) => segments.iter().all(|s| s.text.is_empty()),
+            FieldValue::MultiSelect(v) => v.is_empty(),
+            FieldValue::Person(v) => v.is_empty(),
+            FieldValue::Attachment(v) => v.is_empty(),
+            FieldValue::LinkedRecord { record_ids, .. } => record_ids.is_empty(),
+            FieldValue::Url { text, link } => text.is_empty() && link.is_empty(),
+            FieldValue::Unknown(v) => v.is_null(),
+            FieldValue::Number(_) | FieldValue::Date(_) | FieldValue::Checkbox(_) => false,
+        }
+    }
+
+    // 所有值的文本形式，多值字段保留每一项
+    pub fn texts(&self) -> Vec<String> {
+        match self {
+            FieldValue::Text(s) | FieldValue::SingleSelect(s) => vec![s.clone()],
+            FieldValue::RichText(segments) => {
+                vec![segments.iter().map(|s| s.text.as_str()).collect()]
+            }
+            FieldValue::Number(n) => vec![format_number(*n)],
+            FieldValue::MultiSelect(v) => v.clone(),
+            FieldValue::Person(v) => v.iter().map(|p| p.name.clone()).collect(),
+            FieldValue::Date(ms) => vec![ms.to_string()],
+            FieldValue::Checkbox(b) => vec![b.to_string()],
+            FieldValue::Url { text, link } => {
+                vec![if text.is_empty() {
+                    link.clone()
+                } else {
+                    text.clone()
+                }]
+            }
+            FieldValue::Attachment(v) => v.iter().map(|a| a.name.clone()).collect(),
+            FieldValue::LinkedRecord { record_ids, texts } => {
+                if texts.is_empty() {
+                    record_ids.clone()
+                } else {
+                    texts.clone()
+                }
+            }
+            FieldValue::Unknown(_) => Vec::new(),
+        }
+    }
+
+    // 单个文本，多值字段用"、"连接
+    pub fn text(&self) -> String {
+        self.texts().join("、")
+    }
+
+    // 转换为更新记录接口接受的写入格式。读取接口返回的富文本分段、人员和附件对象不能原样写回：
+    // 富文本写为纯文本，人员只保留 id，附件只保留 file_token，关联记录写为 record_id 列表
+    pub fn to_write_value(&self) -> Value {
+        match self {
+            FieldValue::Text(s) | FieldValue::SingleSelect(s) => json!(s),
+            FieldValue::RichText(segments) => {
+                json!(segments.iter().map(|s| s.text.as_str()).collect::<String>())
+            }
+            FieldValue::Number(n) => json!(n),
+            FieldValue::MultiSelect(v) => json!(v),
+            FieldValue::Person(v) => {
+                json!(v.iter().map(|p| json!({ "id": p.id })).collect::<Vec<_>>())
+            }
+            FieldValue::Date(ms) => json!(ms),
+            FieldValue::Checkbox(b) => json!(b),
+            FieldValue::Url { text, link } => json!({ "text": text, "link": link }),
+            FieldValue::Attachment(v) => json!(v
+                .iter()
+                .map(|a| json!({ "file_token": a.file_token }))
+                .collect::<Vec<_>>()),
+            FieldValue::LinkedRecord { record_ids, .. } => json!(record_ids),
+            FieldValue::Unknown(v) => v.clone(),
+        }
+    }
+}
+
+fn format_number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        format!("{}", n as i64)
+    } else {
+        n.to_string()
+    }
+}
+
+fn str_field(obj: &serde_json::Map<String, Value>, key: &str) -> Option<String> {
+    obj.get(key).and_then(|v| v.as_str()).map(|s| s.to_string())
+}
+
+fn decode_text(value: &Value) -> Option<FieldValue> {
+    match value {
+        Value::String(s) => Some(FieldValue::Text(s.clone())),
+        Value::Number(n) => Some(FieldValue::Text(n.to_string())),
+        Value::Array(items) => Some(FieldValue::RichText(
+            items
+                .iter()
+                .filter_map(|item| match item {
+                    Value::Object(obj) => Some(TextSegment {
+                        segment_type: str_field(obj, "type").unwrap_or_else(|| "text".to_string()),
+                        text: str_field(obj, "text").or_else(|| str_field(obj, "name"))?,
+                        link: str_field(obj, "link"),
+                    }),
+                    Value::String(s) => Some(TextSegment {
+                        segment_type: "text".to_string(),
+                        text: s.clone(),
+                        link: None,
+                    }),
+                    _ => None,
+                })
+                .collect(),
+        )),
+        _ => None,
+    }
+}
+
+// 选项值可能是字符串数组，也可能是带 text / name / option_name / label 的对象数组
+fn select_names(value: &Value) -> Vec<String> {
+    let items = match value {
+        Value::Array(items) => items.iter().collect::<Vec<_>>(),
+        other => vec![other],
+    };
+    items
+        .into_iter()
+        .filter_map(|item| match item {
+            Value::String(s) => Some(s.clone()),
+            Value::Object(obj) => ["text", "name", "option_name", "label"]
+                .iter()
+                .find_map(|key| str_field(obj, key)),
+            _ => None,
+        })
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+fn decode_persons(value: &Value) -> Vec<Person> {
+    let items = match value {
+        Value::Array(items) => items.iter().collect::<Vec<_>>(),
+        other => vec![other],
+    };
+    items
+        .into_iter()
+        .filter_map(|item| {
+            let obj = item.as_object()?;
+            Some(Person {
+                id: str_field(obj, "id").unwrap_or_default(),
+                name: str_field(obj, "name")
+                    .or_else(|| str_field(obj, "en_name"))
+                    .unwrap_or_default(),
+                email: str_field(obj, "email"),
+            })
+        })
+        .collect()
+}
+
+fn decode_url(value: &Value) -> Option<FieldValue> {
+    match value {
+        Value::Object(obj) => Some(FieldValue::Url {
+            text: str_field(obj, "text").unwrap_or_default(),
+            link: str_field(obj, "link").unwrap_or_default(),
+        }),
+        Value::String(s) => Some(FieldValue::Url {
+            text: s.clone(),
+            link: s.clone(),
+        }),
+        _ => None,
+    }
+}
+
+fn decode_attachments(value: &Value) -> Vec<Attachment> {
+    value
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| {
+                    let obj = item.as_object()?;
+                    Some(Attachment {
+                        file_token: str_field(obj, "file_token")?,
+                        name: str_field(obj, "name").unwrap_or_default(),
+                        url: str_field(obj, "url").or_else(|| str_field(obj, "tmp_url")),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// 关联字段：列表接口返回 { link_record_ids }，查询接口返回 [{ record_ids, text_arr }]
+fn decode_link(value: &Value) -> Option<FieldValue> {
+    let mut record_ids = Vec::new();
+    let mut texts = Vec::new();
+    let strings = |v: Option<&Value>| -> Vec<String> {
+        v.and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|s| s.as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    match value {
+        Value::Object(obj) => record_ids = strings(obj.get("link_record_ids")),
+        Value::Array(items) => {
+            for item in items {
+                match item {
+                    Value::Object(obj) => {
+                        record_ids.extend(strings(obj.get("record_ids")));
+                        texts.extend(strings(obj.get("text_arr")));
+                    }
+                    Value::String(s) => record_ids.push(s.clone()),
+                    _ => {}
+                }
+            }
+        }
+        _ => return None,
+    }
+    Some(FieldValue::LinkedRecord { record_ids, texts })
+}
+
+fn decode_typed_wrapper(value: &Value) -> Option<FieldValue> {
+    let obj = value.as_object()?;
+    let inner_type = obj.get("type").and_then(|t| t.as_i64()).map(|t| t as i32);
+    let inner = obj.get("value")?;
+    // 公式 / 引用的值总是数组，单值类型取第一项
+    match (inner_type, inner) {
+        (Some(t), Value::Array(items))
+            if items.len() == 1 && !matches!(t, TYPE_TEXT | TYPE_MULTI_SELECT) =>
+        {
+            FieldValue::decode(Some(t), &items[0])
+        }
+        _ => FieldValue::decode(inner_type, inner),
+    }
+}
+
+// 没有表结构时按值的形状推断类型
+fn infer(value: &Value) -> Option<FieldValue> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(FieldValue::Text(s.clone())),
+        Value::Number(n) => n.as_f64().map(FieldValue::Number),
+        Value::Bool(b) => Some(FieldValue::Checkbox(*b)),
+        Value::Object(obj) => {
+            if obj.contains_key("link_record_ids") {
+                decode_link(value)
+            } else if obj.contains_key("link") {
+                decode_url(value)
+            } else if obj.contains_key("type") && obj.contains_key("value") {
+                decode_typed_wrapper(value)
+            } else {
+                select_names(value)
+                    .into_iter()
+                    .next()
+                    .map(FieldValue::SingleSelect)
+                    .or_else(|| Some(FieldValue::Unknown(value.clone())))
+            }
+        }
+        Value::Array(items) => {
+            let first = items.iter().find_map(|item| item.as_object());
+            match first {
+                None => Some(FieldValue::MultiSelect(select_names(value))),
+                Some(obj) if obj.contains_key("file_token") => {
+                    Some(FieldValue::Attachment(decode_attachments(value)))
+                }
+                Some(obj) if obj.contains_key("record_ids") => decode_link(value),
+                Some(obj) if obj.contains_key("en_name") || obj.contains_key("email") => {
+                    Some(FieldValue::Person(decode_persons(value)))
+                }
+                Some(obj) if obj.contains_key("type") && obj.contains_key("text") => {
+                    decode_text(value)
+                }
+                Some(_) => Some(FieldValue::MultiSelect(select_names(value))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> FieldValue {
+        FieldValue::Text(s.to_string())
+    }
+
+    fn segment(segment_type: &str, text: &str, link: Option<&str>) -> TextSegment {
+        TextSegment {
+            segment_type: segment_type.to_string(),
+            text: text.to_string(),
+            link: link.map(|l| l.to_string()),
+        }
+    }
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn decodes_each_field_type() {
+        let cases: Vec<(i32, Value, FieldValue)> = vec![
+            (TYPE_TEXT, json!("你好"), text("你好")),
+            (TYPE_TEXT, json!(42), text("42")),
+            (
+                TYPE_TEXT,
+                json!([
+                    { "type": "text", "text": "详见" },
+                    { "type": "url", "text": "帮助", "link": "https://example.com" },
+                ]),
+                FieldValue::RichText(vec![
+                    segment("text", "详见", None),
+                    segment("url", "帮助", Some("https://example.com")),
+                ]),
+            ),
+            (TYPE_PHONE, json!("13800000000"), text("13800000000")),
+            (TYPE_AUTO_NUMBER, json!("001"), text("001")),
+            (TYPE_NUMBER, json!(3.5), FieldValue::Number(3.5)),
+            (
+                TYPE_SINGLE_SELECT,
+                json!("高"),
+                FieldValue::SingleSelect("高".to_string()),
+            ),
+            (
+                TYPE_SINGLE_SELECT,
+                json!([{ "text": "低" }]),
+                FieldValue::SingleSelect("低".to_string()),
+            ),
+            (
+                TYPE_MULTI_SELECT,
+                json!(["售后", { "name": "物流" }]),
+                FieldValue::MultiSelect(strings(&["售后", "物流"])),
+            ),
+            (
+                TYPE_DATE,
+                json!(1_700_000_000_000i64),
+                FieldValue::Date(1_700_000_000_000),
+            ),
+            (
+                TYPE_MODIFIED_TIME,
+                json!(1_700_000_000_000i64),
+                FieldValue::Date(1_700_000_000_000),
+            ),
+            (TYPE_CHECKBOX, json!(true), FieldValue::Checkbox(true)),
+            (
+                TYPE_PERSON,
+                json!([{ "id": "ou_1", "name": "张三", "email": "zs@example.com" }]),
+                FieldValue::Person(vec![Person {
+                    id: "ou_1".to_string(),
+                    name: "张三".to_string(),
+                    email: Some("zs@example.com".to_string()),
+                }]),
+            ),
+            (
+                TYPE_CREATED_USER,
+                json!({ "id": "ou_2", "en_name": "Li" }),
+                FieldValue::Person(vec![Person {
+                    id: "ou_2".to_string(),
+                    name: "Li".to_string(),
+                    email: None,
+                }]),
+            ),
+            (
+                TYPE_URL,
+                json!({ "text": "官网", "link": "https://example.com" }),
+                FieldValue::Url {
+                    text: "官网".to_string(),
+                    link: "https://example.com".to_string(),
+                },
+            ),
+            (
+                TYPE_URL,
+                json!("https://example.com"),
+                FieldValue::Url {
+                    text: "https://example.com".to_string(),
+                    link: "https://example.com".to_string(),
+                },
+            ),
+            (
+                TYPE_ATTACHMENT,
+                json!([{ "file_token": "box1", "name": "a.png", "tmp_url": "https://t" }]),
+                FieldValue::Attachment(vec![Attachment {
+                    file_token: "box1".to_string(),
+                    name: "a.png".to_string(),
+                    url: Some("https://t".to_string()),
+                }]),
+            ),
+            (
+                TYPE_SINGLE_LINK,
+                json!({ "link_record_ids": ["rec1", "rec2"] }),
+                FieldValue::LinkedRecord {
+                    record_ids: strings(&["rec1", "rec2"]),
+                    texts: Vec::new(),
+                },
+            ),
+            (
+                TYPE_DUPLEX_LINK,
+                json!([{ "record_ids": ["rec1"], "text_arr": ["问题一"] }]),
+                FieldValue::LinkedRecord {
+                    record_ids: strings(&["rec1"]),
+                    texts: strings(&["问题一"]),
+                },
+            ),
+            (
+                TYPE_FORMULA,
+                json!({ "type": 2, "value": [7] }),
+                FieldValue::Number(7.0),
+            ),
+            (
+                TYPE_LOOKUP,
+                json!({ "type": 1, "value": [{ "type": "text", "text": "引用" }] }),
+                FieldValue::RichText(vec![segment("text", "引用", None)]),
+            ),
+            (
+                TYPE_LOOKUP,
+                json!({ "type": 4, "value": ["A", "B"] }),
+                FieldValue::MultiSelect(strings(&["A", "B"])),
+            ),
+        ];
+        for (field_type, value, expected) in cases {
+            assert_eq!(
+                FieldValue::decode(Some(field_type), &value),
+                Some(expected),
+                "类型 {} 的值 {}",
+                field_type,
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn empty_and_malformed_values_decode_to_none() {
+        let cases: Vec<(i32, Value)> = vec![
+            (TYPE_TEXT, json!("")),
+            (TYPE_TEXT, Value::Null),
+            (TYPE_TEXT, json!(true)),
+            (TYPE_TEXT, json!({ "text": "对象" })),
+            (TYPE_NUMBER, json!("abc")),
+            (TYPE_SINGLE_SELECT, json!(5)),
+            (TYPE_MULTI_SELECT, json!([])),
+            (TYPE_MULTI_SELECT, json!([1, 2])),
+            (TYPE_DATE, json!("2024-01-01")),
+            (TYPE_CHECKBOX, json!("yes")),
+            (TYPE_PERSON, json!("张三")),
+            (TYPE_URL, json!(5)),
+            (TYPE_ATTACHMENT, json!({ "file_token": "box1" })),
+            (TYPE_ATTACHMENT, json!([{ "name": "缺少 file_token" }])),
+            (TYPE_SINGLE_LINK, json!(5)),
+            (TYPE_DUPLEX_LINK, json!({ "record_ids": ["rec1"] })),
+            (TYPE_FORMULA, json!("不是包装对象")),
+            (TYPE_LOOKUP, json!({ "type": 2 })),
+        ];
+        for (field_type, value) in cases {
+            assert_eq!(
+                FieldValue::decode(Some(field_type), &value),
+                None,
+                "类型 {} 的值 {}",
+                field_type,
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn infers_type_from_shape_without_schema() {
+        let cases: Vec<(Value, Option<FieldValue>)> = vec![
+            (Value::Null, None),
+            (json!("文本"), Some(text("文本"))),
+            (json!(2), Some(FieldValue::Number(2.0))),
+            (json!(false), Some(FieldValue::Checkbox(false))),
+            (
+                json!({ "link": "https://example.com", "text": "官网" }),
+                Some(FieldValue::Url {
+                    text: "官网".to_string(),
+                    link: "https://example.com".to_string(),
+                }),
+            ),
+            (
+                json!({ "link_record_ids": ["rec1"] }),
+                Some(FieldValue::LinkedRecord {
+                    record_ids: strings(&["rec1"]),
+                    texts: Vec::new(),
+                }),
+            ),
+            (
+                json!({ "type": 2, "value": [1.5] }),
+                Some(FieldValue::Number(1.5)),
+            ),
+            (
+                json!({ "text": "选项" }),
+                Some(FieldValue::SingleSelect("选项".to_string())),
+            ),
+            (
+                json!({ "foo": 1 }),
+                Some(FieldValue::Unknown(json!({ "foo": 1 }))),
+            ),
+            (
+                json!(["A", "B"]),
+                Some(FieldValue::MultiSelect(strings(&["A", "B"]))),
+            ),
+            (
+                json!([{ "type": "text", "text": "段落" }]),
+                Some(FieldValue::RichText(vec![segment("text", "段落", None)])),
+            ),
+            (
+                json!([{ "file_token": "box1", "name": "a.png" }]),
+                Some(FieldValue::Attachment(vec![Attachment {
+                    file_token: "box1".to_string(),
+                    name: "a.png".to_string(),
+                    url: None,
+                }])),
+            ),
+            (
+                json!([{ "record_ids": ["rec1"], "text_arr": ["问题一"] }]),
+                Some(FieldValue::LinkedRecord {
+                    record_ids: strings(&["rec1"]),
+                    texts: strings(&["问题一"]),
+                }),
+            ),
+            (
+                json!([{ "id": "ou_1", "name": "张三", "en_name": "Zhang" }]),
+                Some(FieldValue::Person(vec![Person {
+                    id: "ou_1".to_string(),
+                    name: "张三".to_string(),
+                    email: None,
+                }])),
+            ),
+            // 形状无法识别的数组不会 panic，解码为空
+            (json!([1, [2], null]), None),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(FieldValue::decode(None, &value), expected, "值 {}", value);
+        }
+    }
+
+    #[test]
+    fn wrong_shape_for_declared_type_does_not_fall_back_to_other_types() {
+        // 声明为数字但值是富文本分段：按声明类型解码失败即为空，不会被推断为文本
+        let segments = json!([{ "type": "text", "text": "12" }]);
+        assert_eq!(FieldValue::decode(Some(TYPE_NUMBER), &segments), None);
+        // 未知的字段类型按值的形状推断
+        assert_eq!(FieldValue::decode(Some(9999), &json!("x")), Some(text("x")));
+    }
+
+    #[test]
+    fn texts_keep_every_value_and_text_joins_them() {
+        let multi = FieldValue::MultiSelect(strings(&["A", "B"]));
+        assert_eq!(multi.texts(), strings(&["A", "B"]));
+        assert_eq!(multi.text(), "A、B");
+        assert_eq!(FieldValue::Number(3.0).text(), "3");
+        assert_eq!(FieldValue::Number(0.25).text(), "0.25");
+        let bare_link = FieldValue::Url {
+            text: String::new(),
+            link: "https://example.com".to_string(),
+        };
+        assert_eq!(bare_link.text(), "https://example.com");
+        let ids_only = FieldValue::LinkedRecord {
+            record_ids: strings(&["rec1"]),
+            texts: Vec::new(),
+        };
+        assert_eq!(ids_only.text(), "rec1");
+    }
+
+    #[test]
+    fn read_values_convert_to_write_format() {
+        let cases: Vec<(i32, Value, Value)> = vec![
+            (
+                TYPE_TEXT,
+                json!([{ "type": "text", "text": "详见" }, { "type": "url", "text": "帮助", "link": "l" }]),
+                json!("详见帮助"),
+            ),
+            (
+                TYPE_PERSON,
+                json!([{ "id": "ou_1", "name": "张三" }]),
+                json!([{ "id": "ou_1" }]),
+            ),
+            (
+                TYPE_URL,
+                json!({ "text": "官网", "link": "https://example.com" }),
+                json!({ "text": "官网", "link": "https://example.com" }),
+            ),
+            (
+                TYPE_ATTACHMENT,
+                json!([{ "file_token": "box1", "name": "a.png" }]),
+                json!([{ "file_token": "box1" }]),
+            ),
+            (
+                TYPE_DUPLEX_LINK,
+                json!([{ "record_ids": ["rec1"], "text_arr": ["问题一"] }]),
+                json!(["rec1"]),
+            ),
+            (TYPE_MULTI_SELECT, json!(["A", "B"]), json!(["A", "B"])),
+        ];
+        for (field_type, value, expected) in cases {
+            let decoded = FieldValue::decode(Some(field_type), &value).unwrap();
+            assert_eq!(decoded.to_write_value(), expected, "类型 {}", field_type);
+        }
+    }
+}
@@ -1,2 +1 @@
 // Learn more about Tauri commands at https://tauri.app/v1/guides/features/command
-
@@ -1,33 +1,115 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod ai_cache;
+mod ai_output;
+mod ai_provider;
+mod ai_requests;
+mod ai_stream;
+mod answer_cache;
 mod commands;
+mod error;
+mod feishu_client;
+mod feishu_http;
+mod field_mapping;
+mod field_value;
+#[cfg(test)]
+mod mock_ai;
+#[cfg(test)]
+mod mock_feishu;
+mod prompt_templates;
+mod review_batch;
+mod revision_store;
+mod schema_cache;
+mod secret_store;
+mod usage_store;
+
+use tauri::Manager;
 
 // use tauri::menu::{Menu, MenuItem, Submenu};
-// use tauri::{Manager, Emitter};
+// use tauri::Emitter;
 
 fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .invoke_handler(tauri::generate_handler![
             commands::set_feishu_credentials,
+            commands::has_feishu_credentials,
+            commands::get_secret_store_status,
             commands::get_feishu_access_token,
             commands::test_feishu_connection,
+            commands::get_feishu_endpoint,
+            commands::set_feishu_endpoint,
+            commands::get_retry_policy,
+            commands::set_retry_policy,
             commands::get_bitable_tables,
             commands::get_answers_data,
             commands::list_answers,
+            commands::sync_answers,
+            commands::get_table_schema,
+            commands::get_field_mapping,
+            commands::set_field_mapping,
             commands::optimize_answer_with_ai,
             commands::review_answer_with_ai,
             commands::check_answer_risk,
+            commands::cancel_ai_request,
+            commands::list_prompt_templates,
+            commands::get_prompt_template_history,
+            commands::save_prompt_template,
+            commands::reset_prompt_template,
+            commands::start_batch_review,
+            commands::resume_batch_review,
+            commands::pause_batch_review,
+            commands::list_batch_reviews,
+            commands::get_batch_review_results,
             commands::set_ai_config,
             commands::get_ai_config,
+            commands::migrate_legacy_secrets,
             commands::test_ai_connection,
+            commands::get_ai_usage,
+            commands::get_model_prices,
+            commands::set_model_price,
+            commands::clear_ai_cache,
             commands::update_answer_to_feishu,
+            commands::list_answer_revisions,
+            commands::restore_answer_revision,
             commands::create_answer_to_feishu,
+            commands::delete_answer_from_feishu,
+            commands::batch_create_answers_to_feishu,
+            commands::batch_update_answers_to_feishu,
+            commands::batch_delete_answers_from_feishu,
             commands::get_bitable_record,
             commands::open_external_url,
         ])
-        .setup(|_app| {
+        .setup(|app| {
+            // 加载本地加密存储的凭证和 AI 配置
+            let data_dir = app.path().app_data_dir()?;
+            let secrets = secret_store::SecretStore::load(&data_dir)?;
+            // 飞书客户端（共享 HTTP 连接、token 缓存和重试策略）
+            app.manage(feishu_client::FeishuClient::new(
+                secrets.feishu_credentials(),
+                &secrets.feishu_endpoint(),
+            )?);
+            app.manage(secrets);
+            // 本地 SQLite 缓存（离线读取 Answers 表）
+            app.manage(answer_cache::AnswerCache::open(&data_dir)?);
+            // 按表保存的字段映射
+            app.manage(field_mapping::FieldMappingStore::load(&data_dir)?);
+            app.manage(schema_cache::SchemaCache::default());
+            // 写回飞书前的本地修订记录
+            app.manage(revision_store::RevisionStore::open(&data_dir)?);
+            // 可编辑的 AI 提示词模板
+            app.manage(prompt_templates::PromptStore::open(&data_dir)?);
+            // 进行中的 AI 请求，供 cancel_ai_request 取消
+            app.manage(ai_requests::AiRequests::default());
+            // 批量审核任务和结果
+            app.manage(review_batch::BatchStore::open(&data_dir)?);
+            app.manage(review_batch::BatchRunner::default());
+            // AI 调用的 token 用量和模型价格
+            app.manage(usage_store::UsageStore::open(&data_dir)?);
+            // AI 回复的本地缓存
+            app.manage(ai_cache::AiCache::open(&data_dir)?);
+
             // TODO: 菜单功能暂时禁用，等 Tauri v2 菜单 API 稳定后再启用
             // 创建中文菜单
             // let menu = create_chinese_menu(app.handle())?;
@@ -0,0 +1,284 @@
+// 测试用的进程内 AI 接口模拟服务（OpenAI 兼容、Azure、Anthropic、Ollama）
+use axum::extract::{OriginalUri, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::ai_provider::AiProviderKind;
+use crate::commands::AiConfig;
+use crate::secret_store::SecretStore;
+
+pub const MODEL: &str = "mock-model";
+// 响应中返回的实际提供服务的模型，和真实接口一样与请求中的模型名（别名或部署名）不同
+pub const SERVED_MODEL: &str = "mock-model-2024-08-06";
+
+// 一次请求的响应
+#[derive(Clone)]
+enum Reply {
+    Text(String),        // 普通 JSON 响应
+    Stream(Vec<String>), // SSE 流式响应，每段一个 delta
+    Status(StatusCode, String),
+    Hang, // 一直不响应，用于测试取消
+}
+
+// 收到的一次请求
+#[derive(Clone)]
+pub struct ReceivedRequest {
+    pub uri: String, // 路径和查询参数
+    pub headers: HeaderMap,
+    pub body: Value,
+}
+
+#[derive(Default)]
+struct MockState {
+    replies: Mutex<VecDeque<Reply>>, // 依次返回，队列为空时回复"ok"
+    requests: Mutex<Vec<ReceivedRequest>>,
+}
+
+pub struct MockAi {
+    origin: String,
+    state: Arc<MockState>,
+}
+
+impl MockAi {
+    pub async fn start() -> Self {
+        let state = Arc::new(MockState::default());
+        let app = Router::new()
+            .route(
+                "/v1/chat/completions",
+                post(|s, u, h, b| respond(AiProviderKind::OpenAi, s, u, h, b)),
+            )
+            .route(
+                "/openai/deployments/:deployment/chat/completions",
+                post(|s, u, h, b| respond(AiProviderKind::Azure, s, u, h, b)),
+            )
+            .route(
+                "/v1/messages",
+                post(|s, u, h, b| respond(AiProviderKind::Anthropic, s, u, h, b)),
+            )
+            .route(
+                "/api/chat",
+                post(|s, u, h, b| respond(AiProviderKind::Ollama, s, u, h, b)),
+            )
+            .with_state(state.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        Self {
+            origin: format!("http://{}", addr),
+            state,
+        }
+    }
+
+    // 在 data_dir 中创建指向模拟服务的配置（OpenAI 兼容接口）
+    pub fn store(&self, data_dir: &Path) -> SecretStore {
+        self.store_for(data_dir, AiProviderKind::OpenAi)
+    }
+
+    pub fn store_for(&self, data_dir: &Path, provider: AiProviderKind) -> SecretStore {
+        let api_base = match provider {
+            AiProviderKind::OpenAi | AiProviderKind::Anthropic => format!("{}/v1", self.origin),
+            AiProviderKind::Azure | AiProviderKind::Ollama => self.origin.clone(),
+        };
+        let store = SecretStore::load(data_dir).unwrap();
+        store
+            .set_ai_config(AiConfig {
+                provider,
+                api_key: "sk-mock".to_string(),
+                api_base,
+                model: MODEL.to_string(),
+                api_version: None,
+            })
+            .unwrap();
+        store
+    }
+
+    pub fn reply_text(&self, text: &str) {
+        self.push(Reply::Text(text.to_string()));
+    }
+
+    // 请求带 stream: true 时按段推送，否则合并为一次 JSON 响应
+    pub fn reply_stream(&self, pieces: &[&str]) {
+        self.push(Reply::Stream(
+            pieces.iter().map(|p| p.to_string()).collect(),
+        ));
+    }
+
+    pub fn reply_status(&self, status: StatusCode, body: &str) {
+        self.push(Reply::Status(status, body.to_string()));
+    }
+
+    pub fn reply_hang(&self) {
+        self.push(Reply::Hang);
+    }
+
+    // 收到的请求体
+    pub fn requests(&self) -> Vec<Value> {
+        self.received().into_iter().map(|r| r.body).collect()
+    }
+
+    pub fn received(&self) -> Vec<ReceivedRequest> {
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    fn push(&self, reply: Reply) {
+        self.state.replies.lock().unwrap().push_back(reply);
+    }
+}
+
+// 模拟的用量：输入固定为 PROMPT_TOKENS，输出为回复的字符数
+pub const PROMPT_TOKENS: u64 = 10;
+
+fn completion_tokens(text: &str) -> u64 {
+    text.chars().count() as u64
+}
+
+fn completion(provider: AiProviderKind, text: &str) -> Response {
+    let output = completion_tokens(text);
+    let body = match provider {
+        AiProviderKind::OpenAi | AiProviderKind::Azure => json!({
+            "model": SERVED_MODEL,
+            "choices": [{
+                "message": { "role": "assistant", "content": text },
+                "finish_reason": "stop",
+            }],
+            "usage": {
+                "prompt_tokens": PROMPT_TOKENS,
+                "completion_tokens": output,
+                "total_tokens": PROMPT_TOKENS + output,
+            },
+        }),
+        AiProviderKind::Anthropic => json!({
+            "type": "message",
+            "model": SERVED_MODEL,
+            "role": "assistant",
+            "content": [{ "type": "text", "text": text }],
+            "stop_reason": "end_turn",
+            "usage": { "input_tokens": PROMPT_TOKENS, "output_tokens": output },
+        }),
+        AiProviderKind::Ollama => json!({
+            "model": SERVED_MODEL,
+            "message": { "role": "assistant", "content": text },
+            "done": true,
+            "prompt_eval_count": PROMPT_TOKENS,
+            "eval_count": output,
+        }),
+    };
+    Json(body).into_response()
+}
+
+// include_usage：OpenAI 兼容接口只在请求 stream_options.include_usage 时返回用量
+fn event_stream(provider: AiProviderKind, pieces: &[String], include_usage: bool) -> Response {
+    let sse = |body: &mut String, data: Value| body.push_str(&format!("data: {}\n\n", data));
+    let mut body = String::new();
+    let output = completion_tokens(&pieces.concat());
+    let content_type = match provider {
+        AiProviderKind::OpenAi | AiProviderKind::Azure => {
+            for piece in pieces {
+                sse(
+                    &mut body,
+                    json!({ "model": SERVED_MODEL, "choices": [{ "delta": { "content": piece } }] }),
+                );
+            }
+            if include_usage {
+                sse(
+                    &mut body,
+                    json!({
+                        "model": SERVED_MODEL,
+                        "choices": [],
+                        "usage": { "prompt_tokens": PROMPT_TOKENS, "completion_tokens": output },
+                    }),
+                );
+            }
+            body.push_str("data: [DONE]\n\n");
+            "text/event-stream"
+        }
+        AiProviderKind::Anthropic => {
+            sse(
+                &mut body,
+                json!({
+                    "type": "message_start",
+                    "message": {
+                        "model": SERVED_MODEL,
+                        "usage": { "input_tokens": PROMPT_TOKENS, "output_tokens": 1 },
+                    },
+                }),
+            );
+            for piece in pieces {
+                body.push_str("event: content_block_delta\n");
+                sse(
+                    &mut body,
+                    json!({
+                        "type": "content_block_delta",
+                        "index": 0,
+                        "delta": { "type": "text_delta", "text": piece },
+                    }),
+                );
+            }
+            sse(
+                &mut body,
+                json!({ "type": "message_delta", "usage": { "output_tokens": output } }),
+            );
+            sse(&mut body, json!({ "type": "message_stop" }));
+            "text/event-stream"
+        }
+        AiProviderKind::Ollama => {
+            for piece in pieces {
+                let line = json!({
+                    "model": SERVED_MODEL,
+                    "message": { "role": "assistant", "content": piece },
+                    "done": false,
+                });
+                body.push_str(&format!("{}\n", line));
+            }
+            body.push_str(&format!(
+                "{}\n",
+                json!({
+                    "model": SERVED_MODEL,
+                    "message": { "content": "" },
+                    "done": true,
+                    "prompt_eval_count": PROMPT_TOKENS,
+                    "eval_count": output,
+                })
+            ));
+            "application/x-ndjson"
+        }
+    };
+    ([(header::CONTENT_TYPE, content_type)], body).into_response()
+}
+
+async fn respond(
+    provider: AiProviderKind,
+    State(state): State<Arc<MockState>>,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    // Ollama 未指定 stream 时默认流式输出
+    let stream = match provider {
+        AiProviderKind::Ollama => body["stream"] != false,
+        _ => body["stream"] == true,
+    };
+    let include_usage = body["stream_options"]["include_usage"] == true;
+    state.requests.lock().unwrap().push(ReceivedRequest {
+        uri: uri.to_string(),
+        headers,
+        body,
+    });
+
+    let reply = state.replies.lock().unwrap().pop_front();
+    match reply.unwrap_or_else(|| Reply::Text("ok".to_string())) {
+        Reply::Text(text) => completion(provider, &text),
+        Reply::Stream(pieces) if stream => event_stream(provider, &pieces, include_usage),
+        Reply::Stream(pieces) => completion(provider, &pieces.concat()),
+        Reply::Status(status, body) => (status, body).into_response(),
+        Reply::Hang => std::future::pending().await,
+    }
+}
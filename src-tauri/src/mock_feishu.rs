@@ -0,0 +1,668 @@
+// 测试用的进程内飞书开放平台模拟服务，覆盖 auth、tables、records、fields 接口
+use axum::extract::{Path, Query, Request, State};
+use axum::http::StatusCode;
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::commands::FeishuCredentials;
+use crate::feishu_client::{FeishuClient, FeishuEndpoint, BATCH_LIMIT};
+use crate::feishu_http::RetryPolicy;
+use crate::field_value::{TYPE_MODIFIED_TIME, TYPE_TEXT};
+
+pub const APP_ID: &str = "cli_mock";
+pub const APP_SECRET: &str = "mock_secret";
+pub const APP_TOKEN: &str = "bascnMock";
+
+// 记录不存在时飞书返回的错误码
+pub const CODE_RECORD_NOT_FOUND: i64 = 1254043;
+// 写入了表中不存在的字段
+pub const CODE_FIELD_NOT_FOUND: i64 = 1254045;
+// 批量接口单次请求的记录数超过上限
+pub const CODE_BATCH_TOO_LARGE: i64 = 1254104;
+// 写入值不能转换为文本字段（如把读取接口返回的富文本分段原样写回）
+pub const CODE_TEXT_CONV_FAIL: i64 = 1254060;
+// 查询条件不合法（模拟服务只支持按"最后更新时间"过滤）
+pub const CODE_INVALID_FILTER: i64 = 1254018;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+type SharedState = Arc<MockState>;
+
+#[derive(Default)]
+struct MockState {
+    tables: Mutex<Vec<(String, String)>>,        // (table_id, name)
+    records: Mutex<HashMap<String, Vec<Value>>>, // table_id -> 记录
+    fields: Mutex<HashMap<String, Vec<Value>>>,  // table_id -> 字段
+    page_size: AtomicUsize,
+    tokens: Mutex<Vec<String>>,    // 当前有效的 token
+    invalid_token_code: AtomicI64, // 携带无效 token 时返回的错误码
+    token_requests: AtomicUsize,
+    next_id: AtomicUsize,
+    clock: AtomicI64, // 记录的 last_modified_time（毫秒），每次写入递增
+    failures: Mutex<VecDeque<(StatusCode, String)>>, // 接下来的 bitable 请求依次返回这些响应
+    requests: Mutex<Vec<String>>, // 收到的 bitable 请求，如 "GET /apps/x/tables/y/records"
+    searches: Mutex<Vec<Value>>, // 收到的 records/search 请求体
+}
+
+pub struct MockFeishu {
+    pub base_url: String,
+    state: SharedState,
+}
+
+impl MockFeishu {
+    pub async fn start() -> Self {
+        let state: SharedState = Arc::new(MockState {
+            page_size: AtomicUsize::new(20),
+            clock: AtomicI64::new(1_700_000_000_000),
+            invalid_token_code: AtomicI64::new(99991668),
+            ..Default::default()
+        });
+
+        let bitable = Router::new()
+            .route("/apps/:app_token/tables", get(list_tables))
+            .route(
+                "/apps/:app_token/tables/:table_id/records",
+                get(list_records).post(create_record),
+            )
+            .route(
+                "/apps/:app_token/tables/:table_id/records/search",
+                post(search_records),
+            )
+            .route(
+                "/apps/:app_token/tables/:table_id/records/batch_create",
+                post(batch_create_records),
+            )
+            .route(
+                "/apps/:app_token/tables/:table_id/records/batch_update",
+                post(batch_update_records),
+            )
+            .route(
+                "/apps/:app_token/tables/:table_id/records/batch_delete",
+                post(batch_delete_records),
+            )
+            .route(
+                "/apps/:app_token/tables/:table_id/records/:record_id",
+                get(get_record).put(update_record).delete(delete_record),
+            )
+            .route("/apps/:app_token/tables/:table_id/fields", get(list_fields))
+            .layer(middleware::from_fn_with_state(state.clone(), guard));
+
+        let app = Router::new()
+            .route(
+                "/open-apis/auth/v3/tenant_access_token/internal",
+                post(issue_token),
+            )
+            .nest("/open-apis/bitable/v1", bitable)
+            .with_state(state.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        Self {
+            base_url: format!("http://{}/open-apis", addr),
+            state,
+        }
+    }
+
+    pub fn endpoint(&self) -> FeishuEndpoint {
+        FeishuEndpoint::Custom {
+            base_url: self.base_url.clone(),
+        }
+    }
+
+    // 指向模拟服务的客户端，重试等待缩短到毫秒级
+    pub fn client(&self) -> FeishuClient {
+        let credentials = FeishuCredentials {
+            app_id: APP_ID.to_string(),
+            app_secret: APP_SECRET.to_string(),
+        };
+        let client = FeishuClient::new(Some(credentials), &self.endpoint()).unwrap();
+        client.set_retry_policy(RetryPolicy {
+            max_retries: 2,
+            base_delay_ms: 1,
+            max_delay_ms: 5,
+        });
+        client
+    }
+
+    pub fn add_table(&self, table_id: &str, name: &str) {
+        let mut tables = self.state.tables.lock().unwrap();
+        tables.push((table_id.to_string(), name.to_string()));
+    }
+
+    pub fn add_field(&self, table_id: &str, name: &str, field_type: i32) {
+        let mut fields = self.state.fields.lock().unwrap();
+        let list = fields.entry(table_id.to_string()).or_default();
+        let field_id = format!("fld{}", list.len() + 1);
+        list.push(json!({ "field_id": field_id, "field_name": name, "type": field_type }));
+    }
+
+    // 添加一条记录，返回 record_id
+    pub fn add_record(&self, table_id: &str, fields: Value) -> String {
+        self.state.insert_record(table_id, fields)["record_id"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    pub fn record(&self, table_id: &str, record_id: &str) -> Option<Value> {
+        let records = self.state.records.lock().unwrap();
+        records
+            .get(table_id)?
+            .iter()
+            .find(|r| r["record_id"] == record_id)
+            .cloned()
+    }
+
+    pub fn record_count(&self, table_id: &str) -> usize {
+        let records = self.state.records.lock().unwrap();
+        records.get(table_id).map(Vec::len).unwrap_or(0)
+    }
+
+    pub fn set_page_size(&self, page_size: usize) {
+        self.state.page_size.store(page_size, Ordering::SeqCst);
+    }
+
+    pub fn token_requests(&self) -> usize {
+        self.state.token_requests.load(Ordering::SeqCst)
+    }
+
+    // 让已签发的 token 全部失效（模拟过期或被吊销）
+    pub fn revoke_tokens(&self) {
+        self.state.tokens.lock().unwrap().clear();
+    }
+
+    // 让已签发的 token 全部失效，之后的请求返回指定的错误码（如 99991663、99991677）
+    pub fn revoke_tokens_with(&self, code: i64) {
+        self.state.invalid_token_code.store(code, Ordering::SeqCst);
+        self.revoke_tokens();
+    }
+
+    // 下一个 bitable 请求返回指定响应（可多次调用排队）
+    pub fn push_failure(&self, status: StatusCode, body: &str) {
+        let mut failures = self.state.failures.lock().unwrap();
+        failures.push_back((status, body.to_string()));
+    }
+
+    // 收到的 records/search 请求体，按收到的顺序
+    pub fn searches(&self) -> Vec<Value> {
+        self.state.searches.lock().unwrap().clone()
+    }
+
+    // 让之后写入的记录的 last_modified_time 向后推移
+    pub fn advance_clock(&self, ms: i64) {
+        self.state.clock.fetch_add(ms, Ordering::SeqCst);
+    }
+
+    // 收到的 bitable 请求中以 prefix 开头的数量，如 "GET /apps/x/tables/y/records"
+    pub fn request_count(&self, prefix: &str) -> usize {
+        let requests = self.state.requests.lock().unwrap();
+        requests.iter().filter(|r| r.starts_with(prefix)).count()
+    }
+}
+
+impl MockState {
+    fn tick(&self) -> i64 {
+        self.clock.fetch_add(1000, Ordering::SeqCst) + 1000
+    }
+
+    fn insert_record(&self, table_id: &str, fields: Value) -> Value {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let record = json!({
+            "record_id": format!("rec{:04}", id),
+            "fields": fields,
+            "last_modified_time": self.tick(),
+        });
+        let mut records = self.records.lock().unwrap();
+        records
+            .entry(table_id.to_string())
+            .or_default()
+            .push(record.clone());
+        record
+    }
+
+    // 只支持 {"field_name": <最后更新时间字段>, "operator": "isGreater", "value": ["ExactDate", 毫秒]}，
+    // 返回条件中的日期（UTC 天数）
+    fn modified_after(&self, table_id: &str, condition: &Value) -> Option<i64> {
+        let fields = self.fields.lock().unwrap();
+        let field = fields
+            .get(table_id)?
+            .iter()
+            .find(|f| f["field_name"] == condition["field_name"])?;
+        if field["type"] != TYPE_MODIFIED_TIME || condition["operator"] != "isGreater" {
+            return None;
+        }
+        match condition["value"].as_array()?.as_slice() {
+            [kind, value] if kind == "ExactDate" => {
+                Some(value.as_str()?.parse::<i64>().ok()? / DAY_MS)
+            }
+            _ => None,
+        }
+    }
+
+    // 登记过字段的表只接受已有字段，返回第一个不存在的字段名
+    fn unknown_field(&self, table_id: &str, fields: &Value) -> Option<String> {
+        let known = self.fields.lock().unwrap();
+        let known = known.get(table_id)?;
+        fields
+            .as_object()?
+            .keys()
+            .find(|name| !known.iter().any(|f| f["field_name"] == **name))
+            .cloned()
+    }
+
+    // 与飞书一致，文本字段只接受字符串（或 null 清空），返回第一个格式不对的字段名
+    fn unwritable_text(&self, table_id: &str, fields: &Value) -> Option<String> {
+        let known = self.fields.lock().unwrap();
+        let known = known.get(table_id)?;
+        fields
+            .as_object()?
+            .iter()
+            .find(|(name, value)| {
+                let is_text = known
+                    .iter()
+                    .any(|f| f["field_name"] == **name && f["type"] == TYPE_TEXT);
+                is_text && !(value.is_string() || value.is_null())
+            })
+            .map(|(name, _)| name.clone())
+    }
+
+    // 只覆盖传入的字段，其他字段保持不变；记录不存在时返回 None
+    fn patch_record(&self, table_id: &str, record_id: &str, fields: &Value) -> Option<Value> {
+        let modified = self.tick();
+        let mut records = self.records.lock().unwrap();
+        let record = records
+            .get_mut(table_id)?
+            .iter_mut()
+            .find(|r| r["record_id"] == record_id)?;
+        if let Some(fields) = fields.as_object() {
+            for (name, value) in fields {
+                record["fields"][name] = value.clone();
+            }
+        }
+        record["last_modified_time"] = json!(modified);
+        Some(record.clone())
+    }
+
+    fn has_record(&self, table_id: &str, record_id: &str) -> bool {
+        let records = self.records.lock().unwrap();
+        records
+            .get(table_id)
+            .is_some_and(|list| list.iter().any(|r| r["record_id"] == record_id))
+    }
+}
+
+fn ok(data: Value) -> Response {
+    Json(json!({ "code": 0, "msg": "success", "data": data })).into_response()
+}
+
+fn error(status: StatusCode, code: i64, msg: &str) -> Response {
+    (status, Json(json!({ "code": code, "msg": msg }))).into_response()
+}
+
+fn field_not_found(name: &str) -> Response {
+    error(
+        StatusCode::BAD_REQUEST,
+        CODE_FIELD_NOT_FOUND,
+        &format!("FieldNameNotFound: {}", name),
+    )
+}
+
+fn text_conv_fail(name: &str) -> Response {
+    error(
+        StatusCode::BAD_REQUEST,
+        CODE_TEXT_CONV_FAIL,
+        &format!("TextFieldConvFail: {}", name),
+    )
+}
+
+fn record_not_found() -> Response {
+    error(
+        StatusCode::BAD_REQUEST,
+        CODE_RECORD_NOT_FOUND,
+        "RecordIdNotFound",
+    )
+}
+
+// 按 page_token（起始下标）和 page_size 分页
+fn page(items: Vec<Value>, query: &HashMap<String, String>, default_size: usize) -> Value {
+    let start: usize = query
+        .get("page_token")
+        .and_then(|t| t.parse().ok())
+        .unwrap_or(0);
+    let size: usize = query
+        .get("page_size")
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(default_size)
+        .min(default_size);
+    let end = (start + size).min(items.len());
+    let has_more = end < items.len();
+    json!({
+        "items": items[start.min(end)..end],
+        "has_more": has_more,
+        "page_token": if has_more { Some(end.to_string()) } else { None },
+        "total": items.len(),
+    })
+}
+
+// 记录请求、注入失败响应、校验 token
+async fn guard(State(state): State<SharedState>, request: Request, next: Next) -> Response {
+    let line = format!("{} {}", request.method(), request.uri().path());
+    state.requests.lock().unwrap().push(line);
+
+    if let Some((status, body)) = state.failures.lock().unwrap().pop_front() {
+        return (status, body).into_response();
+    }
+
+    let token = request
+        .headers()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::to_string);
+    let valid = token.is_some_and(|t| state.tokens.lock().unwrap().contains(&t));
+    if !valid {
+        return error(
+            StatusCode::BAD_REQUEST,
+            state.invalid_token_code.load(Ordering::SeqCst),
+            "Invalid access token for authorization",
+        );
+    }
+
+    next.run(request).await
+}
+
+async fn issue_token(State(state): State<SharedState>, Json(body): Json<Value>) -> Response {
+    let n = state.token_requests.fetch_add(1, Ordering::SeqCst) + 1;
+    if body["app_id"] != APP_ID {
+        return error(StatusCode::OK, 99991663, "app id not exist");
+    }
+    if body["app_secret"] != APP_SECRET {
+        return error(StatusCode::OK, 99991664, "app secret invalid");
+    }
+    let token = format!("t-mock-{}", n);
+    state.tokens.lock().unwrap().push(token.clone());
+    Json(json!({
+        "code": 0,
+        "msg": "ok",
+        "tenant_access_token": token,
+        "expire": 7200,
+    }))
+    .into_response()
+}
+
+async fn list_tables(State(state): State<SharedState>) -> Response {
+    let tables = state.tables.lock().unwrap();
+    let items: Vec<Value> = tables
+        .iter()
+        .map(|(table_id, name)| json!({ "table_id": table_id, "name": name }))
+        .collect();
+    ok(json!({ "items": items, "has_more": false }))
+}
+
+async fn list_records(
+    State(state): State<SharedState>,
+    Path((_, table_id)): Path<(String, String)>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    let mut items = state
+        .records
+        .lock()
+        .unwrap()
+        .get(&table_id)
+        .cloned()
+        .unwrap_or_default();
+    // last_modified_time 只有带 automatic_fields=true 时才返回
+    if query.get("automatic_fields").map(String::as_str) != Some("true") {
+        for item in &mut items {
+            item.as_object_mut().unwrap().remove("last_modified_time");
+        }
+    }
+    ok(page(items, &query, state.page_size.load(Ordering::SeqCst)))
+}
+
+// 实现分页、field_names 投影，以及"最后更新时间"字段上的 isGreater + ExactDate 过滤（按 UTC 日期比较）
+async fn search_records(
+    State(state): State<SharedState>,
+    Path((_, table_id)): Path<(String, String)>,
+    Query(query): Query<HashMap<String, String>>,
+    Json(body): Json<Value>,
+) -> Response {
+    state.searches.lock().unwrap().push(body.clone());
+    let conditions = body["filter"]["conditions"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    let filter_fields: serde_json::Map<String, Value> = conditions
+        .iter()
+        .filter_map(|c| c["field_name"].as_str())
+        .map(|name| (name.to_string(), Value::Null))
+        .collect();
+    if let Some(name) = state.unknown_field(&table_id, &Value::Object(filter_fields)) {
+        return field_not_found(&name);
+    }
+    let mut since_days = Vec::new();
+    for condition in &conditions {
+        match state.modified_after(&table_id, condition) {
+            Some(day) => since_days.push(day),
+            None => {
+                return error(
+                    StatusCode::BAD_REQUEST,
+                    CODE_INVALID_FILTER,
+                    "InvalidFilter",
+                )
+            }
+        }
+    }
+    let mut items: Vec<Value> = state
+        .records
+        .lock()
+        .unwrap()
+        .get(&table_id)
+        .cloned()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|r| {
+            let day = r["last_modified_time"].as_i64().unwrap() / DAY_MS;
+            since_days.iter().all(|since| day > *since)
+        })
+        .collect();
+    if body["automatic_fields"] != true {
+        for item in &mut items {
+            item.as_object_mut().unwrap().remove("last_modified_time");
+        }
+    }
+    if let Some(names) = body["field_names"].as_array() {
+        for item in &mut items {
+            let fields = item["fields"].as_object().cloned().unwrap_or_default();
+            item["fields"] = fields
+                .into_iter()
+                .filter(|(name, _)| names.iter().any(|n| n == name))
+                .collect();
+        }
+    }
+    ok(page(items, &query, state.page_size.load(Ordering::SeqCst)))
+}
+
+async fn get_record(
+    State(state): State<SharedState>,
+    Path((_, table_id, record_id)): Path<(String, String, String)>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    let records = state.records.lock().unwrap();
+    let Some(record) = records
+        .get(&table_id)
+        .and_then(|list| list.iter().find(|r| r["record_id"] == record_id))
+    else {
+        return record_not_found();
+    };
+    let mut record = record.clone();
+    if query.get("automatic_fields").map(String::as_str) != Some("true") {
+        record.as_object_mut().unwrap().remove("last_modified_time");
+    }
+    ok(json!({ "record": record }))
+}
+
+async fn create_record(
+    State(state): State<SharedState>,
+    Path((_, table_id)): Path<(String, String)>,
+    Json(body): Json<Value>,
+) -> Response {
+    if let Some(name) = state.unknown_field(&table_id, &body["fields"]) {
+        return field_not_found(&name);
+    }
+    let record = state.insert_record(&table_id, body["fields"].clone());
+    ok(json!({ "record": record }))
+}
+
+async fn update_record(
+    State(state): State<SharedState>,
+    Path((_, table_id, record_id)): Path<(String, String, String)>,
+    Json(body): Json<Value>,
+) -> Response {
+    if let Some(name) = state.unknown_field(&table_id, &body["fields"]) {
+        return field_not_found(&name);
+    }
+    if let Some(name) = state.unwritable_text(&table_id, &body["fields"]) {
+        return text_conv_fail(&name);
+    }
+    // 与飞书一致，响应中只包含本次写入的字段
+    match state.patch_record(&table_id, &record_id, &body["fields"]) {
+        Some(record) => ok(json!({
+            "record": {
+                "record_id": record_id,
+                "fields": body["fields"],
+                "last_modified_time": record["last_modified_time"],
+            }
+        })),
+        None => record_not_found(),
+    }
+}
+
+async fn delete_record(
+    State(state): State<SharedState>,
+    Path((_, table_id, record_id)): Path<(String, String, String)>,
+) -> Response {
+    let mut records = state.records.lock().unwrap();
+    let list = records.entry(table_id).or_default();
+    let before = list.len();
+    list.retain(|r| r["record_id"] != record_id);
+    if list.len() == before {
+        return record_not_found();
+    }
+    ok(json!({ "deleted": true, "record_id": record_id }))
+}
+
+fn batch_too_large(len: usize) -> Option<Response> {
+    (len > BATCH_LIMIT).then(|| {
+        error(
+            StatusCode::BAD_REQUEST,
+            CODE_BATCH_TOO_LARGE,
+            "RecordAddOnceExceedLimit",
+        )
+    })
+}
+
+// 与飞书一致：整批校验，任意一条出错整批都不写入
+async fn batch_create_records(
+    State(state): State<SharedState>,
+    Path((_, table_id)): Path<(String, String)>,
+    Json(body): Json<Value>,
+) -> Response {
+    let items = body["records"].as_array().cloned().unwrap_or_default();
+    if let Some(response) = batch_too_large(items.len()) {
+        return response;
+    }
+    if let Some(name) = items
+        .iter()
+        .find_map(|item| state.unknown_field(&table_id, &item["fields"]))
+    {
+        return field_not_found(&name);
+    }
+    let records: Vec<Value> = items
+        .iter()
+        .map(|item| state.insert_record(&table_id, item["fields"].clone()))
+        .collect();
+    ok(json!({ "records": records }))
+}
+
+async fn batch_update_records(
+    State(state): State<SharedState>,
+    Path((_, table_id)): Path<(String, String)>,
+    Json(body): Json<Value>,
+) -> Response {
+    let items = body["records"].as_array().cloned().unwrap_or_default();
+    if let Some(response) = batch_too_large(items.len()) {
+        return response;
+    }
+    for item in &items {
+        if !state.has_record(&table_id, item["record_id"].as_str().unwrap_or_default()) {
+            return record_not_found();
+        }
+        if let Some(name) = state.unknown_field(&table_id, &item["fields"]) {
+            return field_not_found(&name);
+        }
+        if let Some(name) = state.unwritable_text(&table_id, &item["fields"]) {
+            return text_conv_fail(&name);
+        }
+    }
+    let records: Vec<Value> = items
+        .iter()
+        .filter_map(|item| {
+            let record_id = item["record_id"].as_str().unwrap_or_default();
+            state.patch_record(&table_id, record_id, &item["fields"])
+        })
+        .collect();
+    ok(json!({ "records": records }))
+}
+
+async fn batch_delete_records(
+    State(state): State<SharedState>,
+    Path((_, table_id)): Path<(String, String)>,
+    Json(body): Json<Value>,
+) -> Response {
+    let ids: Vec<String> = body["records"]
+        .as_array()
+        .map(|ids| {
+            ids.iter()
+                .filter_map(|id| id.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    if let Some(response) = batch_too_large(ids.len()) {
+        return response;
+    }
+    if ids.iter().any(|id| !state.has_record(&table_id, id)) {
+        return record_not_found();
+    }
+    let mut records = state.records.lock().unwrap();
+    let list = records.entry(table_id).or_default();
+    list.retain(|r| !ids.iter().any(|id| r["record_id"] == *id));
+    let deleted: Vec<Value> = ids
+        .iter()
+        .map(|id| json!({ "deleted": true, "record_id": id }))
+        .collect();
+    ok(json!({ "records": deleted }))
+}
+
+async fn list_fields(
+    State(state): State<SharedState>,
+    Path((_, table_id)): Path<(String, String)>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    let items = state
+        .fields
+        .lock()
+        .unwrap()
+        .get(&table_id)
+        .cloned()
+        .unwrap_or_default();
+    ok(page(items, &query, state.page_size.load(Ordering::SeqCst)))
+}
@@ -0,0 +1,408 @@
+use rusqlite::{params, Connection, Row};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::answer_cache::unix_now;
+use crate::error::{AppError, AppResult};
+
+const PROMPTS_FILE: &str = "prompts.db";
+
+// AI 功能对应的提示词模板
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptKind {
+    Optimize,
+    Review,
+    Risk,
+    Compress, // 优化结果超出字数上限时要求压缩
+}
+
+impl PromptKind {
+    pub const ALL: [PromptKind; 4] = [
+        PromptKind::Optimize,
+        PromptKind::Review,
+        PromptKind::Risk,
+        PromptKind::Compress,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PromptKind::Optimize => "optimize",
+            PromptKind::Review => "review",
+            PromptKind::Risk => "risk",
+            PromptKind::Compress => "compress",
+        }
+    }
+
+    // 内置默认模板
+    pub fn default_body(self) -> &'static str {
+        match self {
+            PromptKind::Optimize => include_str!("prompts/optimize.txt"),
+            PromptKind::Review => include_str!("prompts/review.txt"),
+            PromptKind::Risk => include_str!("prompts/risk.txt"),
+            PromptKind::Compress => include_str!("prompts/compress.txt"),
+        }
+        .trim_end()
+    }
+
+    // 模板中可以使用的占位符
+    pub fn placeholders(self) -> &'static [&'static str] {
+        match self {
+            PromptKind::Optimize => &["original_length", "max_length", "context", "answer"],
+            PromptKind::Review => &["context", "answer"],
+            PromptKind::Risk => &["answer"],
+            PromptKind::Compress => &["reply_length", "max_length", "context", "reply"],
+        }
+    }
+
+    // 模板中必须出现的占位符
+    pub fn required_placeholders(self) -> &'static [&'static str] {
+        match self {
+            PromptKind::Optimize => &["max_length", "answer"],
+            PromptKind::Review | PromptKind::Risk => &["answer"],
+            PromptKind::Compress => &["max_length", "reply"],
+        }
+    }
+
+    // 从未修改过时使用的内置模板
+    pub fn builtin(self) -> PromptTemplate {
+        PromptTemplate {
+            kind: self,
+            version: 0,
+            body: self.default_body().to_string(),
+            is_default: true,
+            author: None,
+            created_at: None,
+        }
+    }
+}
+
+impl std::str::FromStr for PromptKind {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> AppResult<Self> {
+        PromptKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| AppError::storage(format!("未知的模板类型: {}", s)))
+    }
+}
+
+// 提示词模板的一个版本。占位符写作 {name}，字面量花括号写作 {{ 和 }}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    pub kind: PromptKind,
+    pub version: i64, // 从 1 开始递增，0 表示从未修改过的内置模板
+    pub body: String,
+    pub is_default: bool, // 内容与内置默认模板相同
+    pub author: Option<String>,
+    pub created_at: Option<i64>, // 秒级时间戳，内置模板为 None
+}
+
+impl PromptTemplate {
+    pub fn reference(&self) -> TemplateRef {
+        TemplateRef {
+            kind: self.kind,
+            version: self.version,
+        }
+    }
+
+    // 用占位符的值生成提示词，values 需覆盖模板中出现的所有占位符
+    pub fn render(&self, values: &[(&str, &str)]) -> AppResult<String> {
+        let mut prompt = String::with_capacity(self.body.len());
+        for segment in parse(&self.body)? {
+            match segment {
+                Segment::Text(text) => prompt.push_str(text),
+                Segment::Brace(brace) => prompt.push(brace),
+                Segment::Placeholder(name) => {
+                    let value = values
+                        .iter()
+                        .find(|(key, _)| *key == name)
+                        .map(|(_, value)| *value)
+                        .ok_or_else(|| {
+                            AppError::internal(format!("缺少占位符 {{{}}} 的值", name))
+                        })?;
+                    prompt.push_str(value);
+                }
+            }
+        }
+        Ok(prompt)
+    }
+}
+
+// AI 结果使用的模板版本
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateRef {
+    pub kind: PromptKind,
+    pub version: i64,
+}
+
+enum Segment<'a> {
+    Text(&'a str),
+    Brace(char), // 转义的 { 或 }
+    Placeholder(&'a str),
+}
+
+fn parse(body: &str) -> AppResult<Vec<Segment<'_>>> {
+    let mut segments = Vec::new();
+    let mut rest = body;
+    while let Some(pos) = rest.find(['{', '}']) {
+        if pos > 0 {
+            segments.push(Segment::Text(&rest[..pos]));
+        }
+        let brace = rest[pos..].chars().next().unwrap();
+        let after = &rest[pos + 1..];
+        if after.starts_with(brace) {
+            segments.push(Segment::Brace(brace));
+            rest = &after[1..];
+        } else if brace == '}' {
+            return Err(AppError::invalid_input(
+                "模板中有多余的 }，字面量花括号请写作 }}",
+            ));
+        } else {
+            let end = after.find('}').ok_or_else(|| {
+                AppError::invalid_input("模板中有未闭合的 {，字面量花括号请写作 {{")
+            })?;
+            segments.push(Segment::Placeholder(after[..end].trim()));
+            rest = &after[end + 1..];
+        }
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest));
+    }
+    Ok(segments)
+}
+
+// 校验模板：占位符必须是该类型支持的，且必需的占位符都要出现
+pub fn validate(kind: PromptKind, body: &str) -> AppResult<()> {
+    if body.trim().is_empty() {
+        return Err(AppError::invalid_input("模板内容不能为空"));
+    }
+    let used: Vec<&str> = parse(body)?
+        .into_iter()
+        .filter_map(|segment| match segment {
+            Segment::Placeholder(name) => Some(name),
+            _ => None,
+        })
+        .collect();
+
+    let unknown: Vec<String> = used
+        .iter()
+        .filter(|name| !kind.placeholders().contains(name))
+        .map(|name| format!("{{{}}}", name))
+        .collect();
+    if !unknown.is_empty() {
+        return Err(AppError::invalid_input(format!(
+            "模板中有不支持的占位符: {}（可用: {}）",
+            unknown.join("、"),
+            braced(kind.placeholders())
+        )));
+    }
+
+    let missing: Vec<&str> = kind
+        .required_placeholders()
+        .iter()
+        .copied()
+        .filter(|name| !used.contains(name))
+        .collect();
+    if !missing.is_empty() {
+        return Err(AppError::invalid_input(format!(
+            "模板缺少必需的占位符: {}",
+            braced(&missing)
+        )));
+    }
+    Ok(())
+}
+
+fn braced(names: &[&str]) -> String {
+    names
+        .iter()
+        .map(|name| format!("{{{}}}", name))
+        .collect::<Vec<_>>()
+        .join("、")
+}
+
+// 本地保存的提示词模板，每次修改生成一个新版本，最新版本生效
+pub struct PromptStore {
+    conn: Mutex<Connection>,
+}
+
+impl PromptStore {
+    pub fn open(data_dir: &Path) -> AppResult<Self> {
+        let conn = Connection::open(data_dir.join(PROMPTS_FILE))
+            .map_err(|e| AppError::storage(format!("打开提示词模板失败: {}", e)))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS prompt_templates (
+                kind TEXT NOT NULL,
+                version INTEGER NOT NULL,
+                body TEXT NOT NULL,
+                author TEXT,
+                created_at INTEGER NOT NULL,
+                PRIMARY KEY (kind, version)
+            );",
+        )
+        .map_err(|e| AppError::storage(format!("初始化提示词模板失败: {}", e)))?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    // 当前生效的模板，从未修改过时为内置模板
+    pub fn active(&self, kind: PromptKind) -> AppResult<PromptTemplate> {
+        Ok(self
+            .history(kind)?
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| kind.builtin()))
+    }
+
+    // 所有保存过的版本，最新的在前
+    pub fn history(&self, kind: PromptKind) -> AppResult<Vec<PromptTemplate>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT kind, version, body, author, created_at FROM prompt_templates
+                 WHERE kind = ?1 ORDER BY version DESC",
+            )
+            .map_err(|e| AppError::storage(format!("读取提示词模板失败: {}", e)))?;
+        let rows = stmt
+            .query_map(params![kind.as_str()], read_row)
+            .map_err(|e| AppError::storage(format!("读取提示词模板失败: {}", e)))?;
+
+        let mut templates = Vec::new();
+        for row in rows {
+            let template =
+                row.map_err(|e| AppError::storage(format!("读取提示词模板失败: {}", e)))?;
+            templates.push(template?);
+        }
+        Ok(templates)
+    }
+
+    // 校验后保存为新版本
+    pub fn save(
+        &self,
+        kind: PromptKind,
+        body: &str,
+        author: Option<&str>,
+    ) -> AppResult<PromptTemplate> {
+        validate(kind, body)?;
+        let conn = self.conn.lock().unwrap();
+        let version: i64 = conn
+            .query_row(
+                "SELECT COALESCE(MAX(version), 0) + 1 FROM prompt_templates WHERE kind = ?1",
+                params![kind.as_str()],
+                |row| row.get(0),
+            )
+            .map_err(|e| AppError::storage(format!("保存提示词模板失败: {}", e)))?;
+        let created_at = unix_now();
+        conn.execute(
+            "INSERT INTO prompt_templates (kind, version, body, author, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![kind.as_str(), version, body, author, created_at],
+        )
+        .map_err(|e| AppError::storage(format!("保存提示词模板失败: {}", e)))?;
+
+        Ok(PromptTemplate {
+            kind,
+            version,
+            body: body.to_string(),
+            is_default: body == kind.default_body(),
+            author: author.map(str::to_string),
+            created_at: Some(created_at),
+        })
+    }
+
+    // 恢复内置模板：保存为新版本以保留修改历史，从未修改过时不产生新版本
+    pub fn reset(&self, kind: PromptKind, author: Option<&str>) -> AppResult<PromptTemplate> {
+        let active = self.active(kind)?;
+        if active.is_default {
+            return Ok(active);
+        }
+        self.save(kind, kind.default_body(), author)
+    }
+}
+
+fn read_row(row: &Row) -> rusqlite::Result<AppResult<PromptTemplate>> {
+    let kind: String = row.get(0)?;
+    let body: String = row.get(2)?;
+    let kind = match kind.parse::<PromptKind>() {
+        Ok(kind) => kind,
+        Err(e) => return Ok(Err(e)),
+    };
+    Ok(Ok(PromptTemplate {
+        kind,
+        version: row.get(1)?,
+        is_default: body == kind.default_body(),
+        body,
+        author: row.get(3)?,
+        created_at: row.get(4)?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn builtin_templates_are_valid() {
+        for kind in PromptKind::ALL {
+            validate(kind, kind.default_body()).unwrap();
+        }
+    }
+
+    #[test]
+    fn render_fills_placeholders_and_unescapes_braces() {
+        let mut template = PromptKind::Risk.builtin();
+        template.body = "检测 {answer}，输出 {{\"risk\": true}}".to_string();
+
+        let prompt = template.render(&[("answer", "回复")]).unwrap();
+
+        assert_eq!(prompt, "检测 回复，输出 {\"risk\": true}");
+    }
+
+    #[test]
+    fn validation_reports_unknown_and_missing_placeholders() {
+        let unknown = validate(PromptKind::Review, "{answer} {answr}").unwrap_err();
+        let missing = validate(PromptKind::Optimize, "优化 {answer}").unwrap_err();
+        let unclosed = validate(PromptKind::Risk, "{answer").unwrap_err();
+
+        assert_eq!(unknown.kind, ErrorKind::InvalidInput);
+        assert!(unknown.message.contains("{answr}"));
+        assert!(missing.message.contains("{max_length}"));
+        assert_eq!(unclosed.kind, ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn saved_versions_and_reset() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PromptStore::open(dir.path()).unwrap();
+        assert_eq!(store.active(PromptKind::Risk).unwrap().version, 0);
+        // 从未修改过时恢复默认不产生新版本
+        assert_eq!(store.reset(PromptKind::Risk, None).unwrap().version, 0);
+
+        store
+            .save(PromptKind::Risk, "v1 {answer}", Some("alice"))
+            .unwrap();
+        store.save(PromptKind::Risk, "v2 {answer}", None).unwrap();
+        store.save(PromptKind::Review, "{answer}", None).unwrap();
+        let active = store.active(PromptKind::Risk).unwrap();
+        assert_eq!((active.version, active.body.as_str()), (2, "v2 {answer}"));
+        assert!(!active.is_default);
+
+        let reset = store.reset(PromptKind::Risk, Some("bob")).unwrap();
+        assert_eq!(reset.version, 3);
+        assert!(reset.is_default);
+        let history = store.history(PromptKind::Risk).unwrap();
+        let versions: Vec<i64> = history.iter().map(|t| t.version).collect();
+        assert_eq!(versions, vec![3, 2, 1]);
+        assert_eq!(history[2].author.as_deref(), Some("alice"));
+
+        // 重新打开后仍生效
+        drop(store);
+        let store = PromptStore::open(dir.path()).unwrap();
+        assert_eq!(store.active(PromptKind::Risk).unwrap().version, 3);
+        assert_eq!(store.active(PromptKind::Optimize).unwrap().version, 0);
+    }
+}
@@ -0,0 +1,647 @@
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
+
+use crate::ai_output::{ReviewReport, RiskReport};
+use crate::answer_cache::unix_now;
+use crate::error::{AppError, AppResult};
+
+const BATCH_FILE: &str = "batch_reviews.db";
+
+// 批量审核进度事件，payload 为 BatchReviewEvent
+pub const BATCH_REVIEW_EVENT: &str = "batch-review-progress";
+
+// 同时进行的审核数
+pub const DEFAULT_CONCURRENCY: usize = 3;
+const MAX_CONCURRENCY: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    Running,
+    Paused, // 用户暂停，或应用退出时任务未完成
+    Completed,
+}
+
+impl BatchStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            BatchStatus::Running => "running",
+            BatchStatus::Paused => "paused",
+            BatchStatus::Completed => "completed",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "running" => BatchStatus::Running,
+            "completed" => BatchStatus::Completed,
+            _ => BatchStatus::Paused,
+        }
+    }
+}
+
+// 批量审核任务及其进度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchJob {
+    pub id: i64,
+    pub app_token: String,
+    pub table_id: String,
+    pub status: BatchStatus,
+    pub total: usize,
+    pub done: usize,    // 审核完成的条数
+    pub failed: usize,  // 审核出错的条数，继续任务时会重试
+    pub flagged: usize, // 结论为需修改或存在风险的条数
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+// 批量审核进度事件的内容：任务当前进度，任务因错误停止时附带原因
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchReviewEvent {
+    #[serde(flatten)]
+    pub job: BatchJob,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<AppError>,
+}
+
+// 任务中待审核的一条答案，创建任务时保存，继续任务时不必重新拉取表格
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchItem {
+    pub record_id: String,
+    pub question: String,
+    pub answer: String,
+    pub context: String,
+}
+
+// 一条答案的审核结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchItemResult {
+    pub record_id: String,
+    pub question: String,
+    pub answer: String,
+    pub review: Option<ReviewReport>,
+    pub risk: Option<RiskReport>,
+    pub error: Option<String>,
+    pub reviewed_at: Option<i64>,
+}
+
+// 批量审核任务和结果，保存在本地 SQLite 中
+pub struct BatchStore {
+    conn: Mutex<Connection>,
+}
+
+impl BatchStore {
+    pub fn open(data_dir: &Path) -> AppResult<Self> {
+        let conn = Connection::open(data_dir.join(BATCH_FILE))
+            .map_err(|e| AppError::storage(format!("打开批量审核记录失败: {}", e)))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS jobs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                app_token TEXT NOT NULL,
+                table_id TEXT NOT NULL,
+                status TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS items (
+                job_id INTEGER NOT NULL,
+                position INTEGER NOT NULL,
+                record_id TEXT NOT NULL,
+                question TEXT NOT NULL,
+                answer TEXT NOT NULL,
+                context TEXT NOT NULL,
+                review TEXT,
+                risk TEXT,
+                verdict TEXT,
+                has_risk INTEGER,
+                error TEXT,
+                reviewed_at INTEGER,
+                PRIMARY KEY (job_id, record_id)
+            );
+            -- 上次退出时仍在运行的任务改为暂停，由用户继续
+            UPDATE jobs SET status = 'paused' WHERE status = 'running';",
+        )
+        .map_err(|e| AppError::storage(format!("初始化批量审核记录失败: {}", e)))?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    pub fn create_job(
+        &self,
+        app_token: &str,
+        table_id: &str,
+        items: &[BatchItem],
+    ) -> AppResult<BatchJob> {
+        let job_id = {
+            let mut conn = self.conn.lock().unwrap();
+            let tx = conn.transaction().map_err(storage_error)?;
+            let now = unix_now();
+            tx.execute(
+                "INSERT INTO jobs (app_token, table_id, status, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?4)",
+                params![app_token, table_id, BatchStatus::Running.as_str(), now],
+            )
+            .map_err(storage_error)?;
+            let job_id = tx.last_insert_rowid();
+            for (position, item) in items.iter().enumerate() {
+                tx.execute(
+                    "INSERT OR IGNORE INTO items (job_id, position, record_id, question, answer, context)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        job_id,
+                        position as i64,
+                        item.record_id,
+                        item.question,
+                        item.answer,
+                        item.context
+                    ],
+                )
+                .map_err(storage_error)?;
+            }
+            tx.commit().map_err(storage_error)?;
+            job_id
+        };
+        self.job(job_id)
+    }
+
+    pub fn job(&self, job_id: i64) -> AppResult<BatchJob> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT id, app_token, table_id, status, created_at, updated_at,
+                    (SELECT COUNT(*) FROM items WHERE job_id = jobs.id),
+                    (SELECT COUNT(*) FROM items WHERE job_id = jobs.id
+                        AND reviewed_at IS NOT NULL AND error IS NULL),
+                    (SELECT COUNT(*) FROM items WHERE job_id = jobs.id AND error IS NOT NULL),
+                    (SELECT COUNT(*) FROM items WHERE job_id = jobs.id
+                        AND (verdict = 'needs_revision' OR has_risk = 1))
+             FROM jobs WHERE id = ?1",
+            params![job_id],
+            read_job,
+        )
+        .optional()
+        .map_err(storage_error)?
+        .ok_or_else(|| AppError::invalid_input(format!("批量审核任务不存在: {}", job_id)))
+    }
+
+    // 某张表的所有任务，最新的在前
+    pub fn jobs(&self, app_token: &str, table_id: &str) -> AppResult<Vec<BatchJob>> {
+        let ids: Vec<i64> = {
+            let conn = self.conn.lock().unwrap();
+            let mut stmt = conn
+                .prepare(
+                    "SELECT id FROM jobs WHERE app_token = ?1 AND table_id = ?2
+                     ORDER BY id DESC",
+                )
+                .map_err(storage_error)?;
+            let rows = stmt
+                .query_map(params![app_token, table_id], |row| row.get(0))
+                .map_err(storage_error)?;
+            rows.collect::<rusqlite::Result<_>>()
+                .map_err(storage_error)?
+        };
+        ids.into_iter().map(|id| self.job(id)).collect()
+    }
+
+    fn set_status(&self, job_id: i64, status: BatchStatus) -> AppResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE jobs SET status = ?2, updated_at = ?3 WHERE id = ?1",
+            params![job_id, status.as_str(), unix_now()],
+        )
+        .map_err(storage_error)?;
+        Ok(())
+    }
+
+    // 尚未审核或审核出错的条目，按创建任务时的顺序
+    pub fn pending_items(&self, job_id: i64) -> AppResult<Vec<BatchItem>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT record_id, question, answer, context FROM items
+                 WHERE job_id = ?1 AND (reviewed_at IS NULL OR error IS NOT NULL)
+                 ORDER BY position",
+            )
+            .map_err(storage_error)?;
+        let rows = stmt
+            .query_map(params![job_id], |row| {
+                Ok(BatchItem {
+                    record_id: row.get(0)?,
+                    question: row.get(1)?,
+                    answer: row.get(2)?,
+                    context: row.get(3)?,
+                })
+            })
+            .map_err(storage_error)?;
+        rows.collect::<rusqlite::Result<_>>().map_err(storage_error)
+    }
+
+    fn save_result(
+        &self,
+        job_id: i64,
+        record_id: &str,
+        outcome: &AppResult<(ReviewReport, RiskReport)>,
+    ) -> AppResult<()> {
+        let (review, risk, verdict, has_risk, error) = match outcome {
+            Ok((review, risk)) => (
+                Some(to_json(review)?),
+                Some(to_json(risk)?),
+                Some(to_json(&review.verdict)?.trim_matches('"').to_string()),
+                Some(risk.has_risk),
+                None,
+            ),
+            Err(e) => (None, None, None, None, Some(e.message.clone())),
+        };
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE items SET review = ?3, risk = ?4, verdict = ?5, has_risk = ?6,
+                              error = ?7, reviewed_at = ?8
+             WHERE job_id = ?1 AND record_id = ?2",
+            params![
+                job_id,
+                record_id,
+                review,
+                risk,
+                verdict,
+                has_risk,
+                error,
+                unix_now()
+            ],
+        )
+        .map_err(storage_error)?;
+        conn.execute(
+            "UPDATE jobs SET updated_at = ?2 WHERE id = ?1",
+            params![job_id, unix_now()],
+        )
+        .map_err(storage_error)?;
+        Ok(())
+    }
+
+    // 审核结果。flagged_only 时只返回结论为需修改或存在风险的条目
+    pub fn results(&self, job_id: i64, flagged_only: bool) -> AppResult<Vec<BatchItemResult>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT record_id, question, answer, review, risk, error, reviewed_at FROM items
+                 WHERE job_id = ?1 AND (?2 = 0 OR verdict = 'needs_revision' OR has_risk = 1)
+                 ORDER BY position",
+            )
+            .map_err(storage_error)?;
+        let rows = stmt
+            .query_map(params![job_id, flagged_only], read_result)
+            .map_err(storage_error)?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row.map_err(storage_error)??);
+        }
+        Ok(results)
+    }
+}
+
+fn storage_error(e: rusqlite::Error) -> AppError {
+    AppError::storage(format!("读写批量审核记录失败: {}", e))
+}
+
+fn to_json<T: Serialize>(value: &T) -> AppResult<String> {
+    serde_json::to_string(value)
+        .map_err(|e| AppError::storage(format!("序列化审核结果失败: {}", e)))
+}
+
+fn read_job(row: &Row) -> rusqlite::Result<BatchJob> {
+    let status: String = row.get(3)?;
+    let count = |index: usize| row.get::<_, i64>(index).map(|n| n as usize);
+    Ok(BatchJob {
+        id: row.get(0)?,
+        app_token: row.get(1)?,
+        table_id: row.get(2)?,
+        status: BatchStatus::parse(&status),
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+        total: count(6)?,
+        done: count(7)?,
+        failed: count(8)?,
+        flagged: count(9)?,
+    })
+}
+
+// 读取一行审核结果，JSON 解析失败时返回存储错误
+fn read_result(row: &Row) -> rusqlite::Result<AppResult<BatchItemResult>> {
+    fn parse<T: serde::de::DeserializeOwned>(text: Option<String>) -> AppResult<Option<T>> {
+        text.map(|text| {
+            serde_json::from_str(&text)
+                .map_err(|e| AppError::storage(format!("解析审核结果失败: {}", e)))
+        })
+        .transpose()
+    }
+    let (review, risk) = match (parse(row.get(3)?), parse(row.get(4)?)) {
+        (Ok(review), Ok(risk)) => (review, risk),
+        (Err(e), _) | (_, Err(e)) => return Ok(Err(e)),
+    };
+    Ok(Ok(BatchItemResult {
+        record_id: row.get(0)?,
+        question: row.get(1)?,
+        answer: row.get(2)?,
+        review,
+        risk,
+        error: row.get(5)?,
+        reviewed_at: row.get(6)?,
+    }))
+}
+
+// 本进程中正在运行的任务及其暂停标记
+#[derive(Default)]
+pub struct BatchRunner {
+    running: Arc<Mutex<HashMap<i64, Arc<AtomicBool>>>>,
+}
+
+// 任务运行期间的登记，drop 时移除。可移入后台任务
+pub struct RunningJob {
+    running: Arc<Mutex<HashMap<i64, Arc<AtomicBool>>>>,
+    job_id: i64,
+    paused: Arc<AtomicBool>,
+}
+
+impl Drop for RunningJob {
+    fn drop(&mut self) {
+        self.running.lock().unwrap().remove(&self.job_id);
+    }
+}
+
+impl BatchRunner {
+    // 登记要运行的任务，任务已在运行（包括暂停后尚未停下）时返回错误
+    pub fn register(&self, job_id: i64) -> AppResult<RunningJob> {
+        let mut running = self.running.lock().unwrap();
+        if running.contains_key(&job_id) {
+            return Err(AppError::invalid_input(
+                "任务正在运行或正在暂停，请等待进行中的审核完成后再继续",
+            ));
+        }
+        let paused = Arc::new(AtomicBool::new(false));
+        running.insert(job_id, paused.clone());
+        Ok(RunningJob {
+            running: self.running.clone(),
+            job_id,
+            paused,
+        })
+    }
+
+    // 请求暂停：进行中的审核完成后不再开始新的条目。任务不在运行时返回 false
+    pub fn pause(&self, job_id: i64) -> bool {
+        match self.running.lock().unwrap().get(&job_id) {
+            Some(paused) => {
+                paused.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// 以最多 concurrency 个并发审核任务中未完成的条目，每完成一条调用一次 progress。
+// review 对一条答案执行审核和风险检测；单条失败只记录错误，不中断任务
+pub async fn run_job<F, Fut>(
+    store: &BatchStore,
+    job: &RunningJob,
+    concurrency: usize,
+    review: F,
+    progress: impl Fn(&BatchJob),
+) -> AppResult<BatchJob>
+where
+    F: Fn(BatchItem) -> Fut,
+    Fut: Future<Output = AppResult<(ReviewReport, RiskReport)>>,
+{
+    let job_id = job.job_id;
+    let queue = Mutex::new(VecDeque::from(store.pending_items(job_id)?));
+    store.set_status(job_id, BatchStatus::Running)?;
+    progress(&store.job(job_id)?);
+
+    let worker = || async {
+        loop {
+            if job.paused.load(Ordering::SeqCst) {
+                return Ok::<(), AppError>(());
+            }
+            let Some(item) = queue.lock().unwrap().pop_front() else {
+                return Ok(());
+            };
+            let record_id = item.record_id.clone();
+            let outcome = review(item).await;
+            store.save_result(job_id, &record_id, &outcome)?;
+            progress(&store.job(job_id)?);
+        }
+    };
+    let workers = (0..concurrency.clamp(1, MAX_CONCURRENCY)).map(|_| worker());
+    let result = join_all(workers)
+        .await
+        .into_iter()
+        .collect::<AppResult<Vec<()>>>();
+
+    // 所有条目都已审核（出错的条目可继续任务重试）即为完成，保存结果失败时暂停
+    let current = store.job(job_id)?;
+    let status = if result.is_ok() && current.done + current.failed == current.total {
+        BatchStatus::Completed
+    } else {
+        BatchStatus::Paused
+    };
+    store.set_status(job_id, status)?;
+    let finished = store.job(job_id)?;
+    progress(&finished);
+    result.map(|_| finished)
+}
+
+// 在当前任务中并发运行多个 future，全部完成后按顺序返回结果
+async fn join_all<F: Future>(futures: impl IntoIterator<Item = F>) -> Vec<F::Output> {
+    let mut futures: Vec<Pin<Box<F>>> = futures.into_iter().map(Box::pin).collect();
+    let mut outputs: Vec<Option<F::Output>> = futures.iter().map(|_| None).collect();
+    std::future::poll_fn(|cx| {
+        let mut pending = false;
+        for (future, output) in futures.iter_mut().zip(outputs.iter_mut()) {
+            if output.is_some() {
+                continue;
+            }
+            match future.as_mut().poll(cx) {
+                Poll::Ready(value) => *output = Some(value),
+                Poll::Pending => pending = true,
+            }
+        }
+        if pending {
+            Poll::Pending
+        } else {
+            Poll::Ready(())
+        }
+    })
+    .await;
+    outputs.into_iter().map(Option::unwrap).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai_output::ReviewVerdict;
+
+    fn item(record_id: &str) -> BatchItem {
+        BatchItem {
+            record_id: record_id.to_string(),
+            question: format!("{} 的问题", record_id),
+            answer: format!("{} 的回答", record_id),
+            context: String::new(),
+        }
+    }
+
+    fn report(verdict: ReviewVerdict, has_risk: bool) -> (ReviewReport, RiskReport) {
+        (
+            ReviewReport {
+                verdict,
+                explanation: String::new(),
+                risks: Vec::new(),
+                suggestion: None,
+                revision_reason: None,
+                suggested_reply: None,
+                principles: Vec::new(),
+            },
+            RiskReport {
+                has_risk,
+                reason: String::new(),
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn runs_all_items_and_reports_flagged() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = BatchStore::open(dir.path()).unwrap();
+        let runner = BatchRunner::default();
+        let job = store
+            .create_job(
+                "app",
+                "tbl",
+                &[item("rec1"), item("rec2"), item("rec3"), item("rec4")],
+            )
+            .unwrap();
+        let events = Mutex::new(Vec::new());
+
+        let finished = run_job(
+            &store,
+            &runner.register(job.id).unwrap(),
+            2,
+            |item| async move {
+                match item.record_id.as_str() {
+                    "rec1" => Ok(report(ReviewVerdict::NeedsRevision, false)),
+                    "rec2" => Ok(report(ReviewVerdict::Reasonable, true)),
+                    "rec3" => Err(AppError::ai("接口错误")),
+                    _ => Ok(report(ReviewVerdict::Reasonable, false)),
+                }
+            },
+            |job| {
+                events
+                    .lock()
+                    .unwrap()
+                    .push((job.status, job.done + job.failed))
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(finished.status, BatchStatus::Completed);
+        assert_eq!(
+            (
+                finished.total,
+                finished.done,
+                finished.failed,
+                finished.flagged
+            ),
+            (4, 3, 1, 2)
+        );
+        let events = events.into_inner().unwrap();
+        assert_eq!(events.first(), Some(&(BatchStatus::Running, 0)));
+        assert_eq!(events.last(), Some(&(BatchStatus::Completed, 4)));
+
+        let flagged = store.results(job.id, true).unwrap();
+        let ids: Vec<_> = flagged.iter().map(|r| r.record_id.as_str()).collect();
+        assert_eq!(ids, vec!["rec1", "rec2"]);
+        assert_eq!(
+            flagged[0].review.as_ref().unwrap().verdict,
+            ReviewVerdict::NeedsRevision
+        );
+        assert_eq!(
+            store.results(job.id, false).unwrap()[2].error.as_deref(),
+            Some("接口错误")
+        );
+        // 出错的条目在继续任务时重试
+        assert_eq!(store.pending_items(job.id).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn paused_job_resumes_where_it_stopped() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = BatchStore::open(dir.path()).unwrap();
+        let runner = BatchRunner::default();
+        let job = store
+            .create_job("app", "tbl", &[item("rec1"), item("rec2"), item("rec3")])
+            .unwrap();
+
+        let running = runner.register(job.id).unwrap();
+        assert!(runner.register(job.id).is_err());
+        let (runner_ref, job_id) = (&runner, job.id);
+        let paused = run_job(
+            &store,
+            &running,
+            1,
+            move |_| async move {
+                // 第一条审核期间用户点了暂停
+                assert!(runner_ref.pause(job_id));
+                Ok(report(ReviewVerdict::Reasonable, false))
+            },
+            |_| {},
+        )
+        .await
+        .unwrap();
+        drop(running);
+
+        assert_eq!(paused.status, BatchStatus::Paused);
+        assert_eq!(paused.done, 1);
+        assert!(!runner.pause(job.id));
+
+        let reviewed = Mutex::new(Vec::new());
+        let finished = run_job(
+            &store,
+            &runner.register(job.id).unwrap(),
+            1,
+            |item| {
+                reviewed.lock().unwrap().push(item.record_id);
+                async { Ok(report(ReviewVerdict::Reasonable, false)) }
+            },
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(finished.status, BatchStatus::Completed);
+        assert_eq!(finished.done, 3);
+        assert_eq!(reviewed.into_inner().unwrap(), vec!["rec2", "rec3"]);
+    }
+
+    #[test]
+    fn reopening_marks_interrupted_jobs_paused() {
+        let dir = tempfile::tempdir().unwrap();
+        let job_id = {
+            let store = BatchStore::open(dir.path()).unwrap();
+            let job = store.create_job("app", "tbl", &[item("rec1")]).unwrap();
+            store.set_status(job.id, BatchStatus::Running).unwrap();
+            job.id
+        };
+
+        let store = BatchStore::open(dir.path()).unwrap();
+
+        assert_eq!(store.job(job_id).unwrap().status, BatchStatus::Paused);
+        assert_eq!(store.jobs("app", "tbl").unwrap().len(), 1);
+        assert!(store.jobs("app", "other").unwrap().is_empty());
+    }
+}
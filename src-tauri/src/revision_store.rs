@@ -0,0 +1,312 @@
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::answer_cache::unix_now;
+use crate::error::{AppError, AppResult};
+
+const REVISIONS_FILE: &str = "revisions.db";
+
+// 写回飞书前保存的本地修订记录，用于查看历史和恢复
+pub struct RevisionStore {
+    conn: Mutex<Connection>,
+}
+
+// 一次写回的修订：写入前后的字段值（表格实际列名），old_fields 中为 null 表示写入前为空
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Revision {
+    pub id: i64,
+    pub app_token: String,
+    pub table_id: String,
+    pub record_id: String,
+    pub old_fields: HashMap<String, serde_json::Value>,
+    pub new_fields: HashMap<String, serde_json::Value>,
+    pub author: Option<String>,
+    pub ai_action: Option<String>,  // 如"AI优化"、"AI审核"、"人工调整"
+    pub restored_from: Option<i64>, // 由恢复操作产生时，被恢复的修订 id
+    pub created_at: i64,            // 秒级时间戳
+}
+
+// 调用方随写回一起传入的修订信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RevisionInfo {
+    pub author: Option<String>,
+    pub ai_action: Option<String>,
+}
+
+pub struct NewRevision<'a> {
+    pub app_token: &'a str,
+    pub table_id: &'a str,
+    pub record_id: &'a str,
+    pub old_fields: &'a HashMap<String, serde_json::Value>,
+    pub new_fields: &'a HashMap<String, serde_json::Value>,
+    pub info: &'a RevisionInfo,
+    pub restored_from: Option<i64>,
+}
+
+impl RevisionStore {
+    pub fn open(data_dir: &Path) -> AppResult<Self> {
+        let conn = Connection::open(data_dir.join(REVISIONS_FILE))
+            .map_err(|e| AppError::storage(format!("打开修订记录失败: {}", e)))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS revisions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                app_token TEXT NOT NULL,
+                table_id TEXT NOT NULL,
+                record_id TEXT NOT NULL,
+                old_fields TEXT NOT NULL,
+                new_fields TEXT NOT NULL,
+                author TEXT,
+                ai_action TEXT,
+                restored_from INTEGER,
+                created_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS revisions_by_record
+                ON revisions (app_token, table_id, record_id);",
+        )
+        .map_err(|e| AppError::storage(format!("初始化修订记录失败: {}", e)))?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    // 保存一条修订，返回修订 id
+    pub fn insert(&self, revision: NewRevision) -> AppResult<i64> {
+        let old_fields = serde_json::to_string(revision.old_fields)
+            .map_err(|e| AppError::storage(format!("序列化修订失败: {}", e)))?;
+        let new_fields = serde_json::to_string(revision.new_fields)
+            .map_err(|e| AppError::storage(format!("序列化修订失败: {}", e)))?;
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO revisions (app_token, table_id, record_id, old_fields, new_fields,
+                                    author, ai_action, restored_from, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                revision.app_token,
+                revision.table_id,
+                revision.record_id,
+                old_fields,
+                new_fields,
+                revision.info.author,
+                revision.info.ai_action,
+                revision.restored_from,
+                unix_now()
+            ],
+        )
+        .map_err(|e| AppError::storage(format!("保存修订失败: {}", e)))?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    // 写回失败时撤销事先保存的修订
+    pub fn remove(&self, id: i64) -> AppResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM revisions WHERE id = ?1", params![id])
+            .map_err(|e| AppError::storage(format!("删除修订失败: {}", e)))?;
+        Ok(())
+    }
+
+    pub fn get(&self, id: i64) -> AppResult<Option<Revision>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT id, app_token, table_id, record_id, old_fields, new_fields,
+                    author, ai_action, restored_from, created_at
+             FROM revisions WHERE id = ?1",
+            params![id],
+            read_row,
+        )
+        .optional()
+        .map_err(|e| AppError::storage(format!("读取修订失败: {}", e)))?
+        .transpose()
+    }
+
+    // 某条记录的所有修订，最新的在前
+    pub fn list(
+        &self,
+        app_token: &str,
+        table_id: &str,
+        record_id: &str,
+    ) -> AppResult<Vec<Revision>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, app_token, table_id, record_id, old_fields, new_fields,
+                        author, ai_action, restored_from, created_at
+                 FROM revisions WHERE app_token = ?1 AND table_id = ?2 AND record_id = ?3
+                 ORDER BY id DESC",
+            )
+            .map_err(|e| AppError::storage(format!("读取修订失败: {}", e)))?;
+        let rows = stmt
+            .query_map(params![app_token, table_id, record_id], read_row)
+            .map_err(|e| AppError::storage(format!("读取修订失败: {}", e)))?;
+
+        let mut revisions = Vec::new();
+        for row in rows {
+            let revision = row.map_err(|e| AppError::storage(format!("读取修订失败: {}", e)))?;
+            revisions.push(revision?);
+        }
+        Ok(revisions)
+    }
+}
+
+// 读取一行修订，字段 JSON 解析失败时返回存储错误
+fn read_row(row: &Row) -> rusqlite::Result<AppResult<Revision>> {
+    let parse = |index: usize| -> rusqlite::Result<AppResult<HashMap<String, serde_json::Value>>> {
+        let text: String = row.get(index)?;
+        Ok(serde_json::from_str(&text)
+            .map_err(|e| AppError::storage(format!("解析修订失败: {}", e))))
+    };
+    let (old_fields, new_fields) = match (parse(4)?, parse(5)?) {
+        (Ok(old_fields), Ok(new_fields)) => (old_fields, new_fields),
+        (Err(e), _) | (_, Err(e)) => return Ok(Err(e)),
+    };
+    Ok(Ok(Revision {
+        id: row.get(0)?,
+        app_token: row.get(1)?,
+        table_id: row.get(2)?,
+        record_id: row.get(3)?,
+        old_fields,
+        new_fields,
+        author: row.get(6)?,
+        ai_action: row.get(7)?,
+        restored_from: row.get(8)?,
+        created_at: row.get(9)?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fields(pairs: &[(&str, serde_json::Value)]) -> HashMap<String, serde_json::Value> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect()
+    }
+
+    fn insert(store: &RevisionStore, record_id: &str, answer: &str) -> i64 {
+        store
+            .insert(NewRevision {
+                app_token: "bascnA",
+                table_id: "tbl1",
+                record_id,
+                old_fields: &fields(&[("标准回答", json!("旧"))]),
+                new_fields: &fields(&[("标准回答", json!(answer))]),
+                info: &RevisionInfo::default(),
+                restored_from: None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn insert_and_get_round_trip_field_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = RevisionStore::open(dir.path()).unwrap();
+        let old_fields = fields(&[
+            ("标准回答", json!([{ "type": "text", "text": "旧" }])),
+            ("负责人", json!([{ "id": "ou_1", "name": "张三" }])),
+            ("最新版本来源", serde_json::Value::Null),
+        ]);
+        let new_fields = fields(&[
+            ("标准回答", json!("新")),
+            ("负责人", json!([{ "id": "ou_2" }])),
+        ]);
+        let info = RevisionInfo {
+            author: Some("alice".to_string()),
+            ai_action: Some("AI优化".to_string()),
+        };
+
+        let id = store
+            .insert(NewRevision {
+                app_token: "bascnA",
+                table_id: "tbl1",
+                record_id: "rec1",
+                old_fields: &old_fields,
+                new_fields: &new_fields,
+                info: &info,
+                restored_from: Some(7),
+            })
+            .unwrap();
+        let revision = store.get(id).unwrap().unwrap();
+
+        assert_eq!(revision.id, id);
+        assert_eq!(revision.app_token, "bascnA");
+        assert_eq!(revision.table_id, "tbl1");
+        assert_eq!(revision.record_id, "rec1");
+        assert_eq!(revision.old_fields, old_fields);
+        assert_eq!(revision.new_fields, new_fields);
+        assert_eq!(revision.author.as_deref(), Some("alice"));
+        assert_eq!(revision.ai_action.as_deref(), Some("AI优化"));
+        assert_eq!(revision.restored_from, Some(7));
+        assert!(revision.created_at > 0);
+        assert!(store.get(id + 1).unwrap().is_none());
+    }
+
+    #[test]
+    fn list_returns_newest_first_for_one_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = RevisionStore::open(dir.path()).unwrap();
+        let first = insert(&store, "rec1", "第一次");
+        insert(&store, "rec2", "其他记录");
+        let second = insert(&store, "rec1", "第二次");
+
+        let revisions = store.list("bascnA", "tbl1", "rec1").unwrap();
+        let ids: Vec<_> = revisions.iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![second, first]);
+        assert_eq!(revisions[0].new_fields["标准回答"], "第二次");
+        assert!(store.list("bascnB", "tbl1", "rec1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn remove_deletes_only_that_revision() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = RevisionStore::open(dir.path()).unwrap();
+        let first = insert(&store, "rec1", "第一次");
+        let second = insert(&store, "rec1", "第二次");
+
+        store.remove(second).unwrap();
+
+        assert!(store.get(second).unwrap().is_none());
+        let ids: Vec<_> = store
+            .list("bascnA", "tbl1", "rec1")
+            .unwrap()
+            .iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(ids, vec![first]);
+    }
+
+    #[test]
+    fn revisions_survive_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+        let id = insert(&RevisionStore::open(dir.path()).unwrap(), "rec1", "新");
+
+        let store = RevisionStore::open(dir.path()).unwrap();
+        assert_eq!(store.get(id).unwrap().unwrap().new_fields["标准回答"], "新");
+    }
+
+    #[test]
+    fn corrupted_fields_are_reported_as_storage_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = RevisionStore::open(dir.path()).unwrap();
+        let id = insert(&store, "rec1", "新");
+        store
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE revisions SET old_fields = 'not json' WHERE id = ?1",
+                params![id],
+            )
+            .unwrap();
+
+        assert_eq!(
+            store.get(id).unwrap_err().kind,
+            crate::error::ErrorKind::Storage
+        );
+        assert!(store.list("bascnA", "tbl1", "rec1").is_err());
+    }
+}
@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::commands::TableField;
+
+// 按 (app_token, table_id) 缓存的表结构（字段列表），避免每次打开下拉框都请求字段接口。
+// 不同多维表格中的 table_id 可能相同，只按 table_id 缓存会串表
+#[derive(Default)]
+pub struct SchemaCache {
+    tables: Mutex<HashMap<(String, String), Vec<TableField>>>,
+}
+
+impl SchemaCache {
+    pub fn get(&self, app_token: &str, table_id: &str) -> Option<Vec<TableField>> {
+        let key = (app_token.to_string(), table_id.to_string());
+        self.tables.lock().unwrap().get(&key).cloned()
+    }
+
+    pub fn insert(&self, app_token: &str, table_id: &str, fields: Vec<TableField>) {
+        let key = (app_token.to_string(), table_id.to_string());
+        self.tables.lock().unwrap().insert(key, fields);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str) -> TableField {
+        TableField {
+            field_id: "fld1".to_string(),
+            field_name: name.to_string(),
+            field_type: 1,
+            options: None,
+        }
+    }
+
+    #[test]
+    fn same_table_id_in_different_bases_is_cached_separately() {
+        let cache = SchemaCache::default();
+        cache.insert("bascnA", "tbl1", vec![field("问题")]);
+        cache.insert("bascnB", "tbl1", vec![field("Question")]);
+
+        assert_eq!(cache.get("bascnA", "tbl1").unwrap()[0].field_name, "问题");
+        assert_eq!(
+            cache.get("bascnB", "tbl1").unwrap()[0].field_name,
+            "Question"
+        );
+        assert!(cache.get("bascnC", "tbl1").is_none());
+    }
+}
@@ -0,0 +1,283 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::answer_cache::unix_now;
+use crate::commands::{AiConfig, FeishuCredentials};
+use crate::error::{AppError, AppResult};
+use crate::feishu_client::FeishuEndpoint;
+
+// 加密文件格式：MAGIC(4) + nonce(12) + 密文
+const MAGIC: &[u8; 4] = b"A3S1";
+const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 32;
+
+const STORE_FILE: &str = "secrets.bin";
+const SALT_FILE: &str = "secrets.salt";
+
+// 持久化的敏感配置
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Secrets {
+    pub feishu: Option<FeishuCredentials>,
+    pub ai: Option<AiConfig>,
+    #[serde(default)]
+    pub feishu_endpoint: FeishuEndpoint, // 开放平台地址，旧版本的文件中没有时使用飞书
+}
+
+// 启动时已有的加密存储无法读取：旧文件已移到 backup，需要重新填写凭证和 AI 配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnreadableSecrets {
+    pub reason: String,
+    pub backup: String, // 旧加密文件的新路径
+}
+
+// 本地加密存储（保存在应用数据目录，启动时加载）
+pub struct SecretStore {
+    path: PathBuf,
+    key: [u8; 32],
+    secrets: Mutex<Secrets>,
+    unreadable: Mutex<Option<UnreadableSecrets>>, // 重新保存任一配置后清除
+}
+
+impl SecretStore {
+    // 从应用数据目录加载，不存在时创建空存储。
+    // 已有的加密文件无法解密（如机器标识变化、数据目录被移动或文件损坏）时，
+    // 把加密文件和密钥盐移到一旁保留，以空存储启动，由 unreadable() 提示重新填写
+    pub fn load(data_dir: &Path) -> AppResult<Self> {
+        fs::create_dir_all(data_dir)
+            .map_err(|e| AppError::storage(format!("创建数据目录失败: {}", e)))?;
+
+        let path = data_dir.join(STORE_FILE);
+        match Self::open(data_dir, &path) {
+            Err(e) if path.exists() => {
+                let backup = set_aside(data_dir, &path)?;
+                let store = Self::open(data_dir, &path)?;
+                *store.unreadable.lock().unwrap() = Some(UnreadableSecrets {
+                    reason: e.message,
+                    backup: backup.display().to_string(),
+                });
+                Ok(store)
+            }
+            result => result,
+        }
+    }
+
+    fn open(data_dir: &Path, path: &Path) -> AppResult<Self> {
+        let path = path.to_path_buf();
+        let key = derive_key(data_dir, path.exists())?;
+
+        let secrets = if path.exists() {
+            let bytes = fs::read(&path)
+                .map_err(|e| AppError::storage(format!("读取加密存储失败: {}", e)))?;
+            decrypt(&key, &bytes).map_err(|e| {
+                AppError::storage(format!(
+                    "加密存储无法解密（{}）: {}",
+                    e.message,
+                    path.display()
+                ))
+            })?
+        } else {
+            Secrets::default()
+        };
+
+        Ok(Self {
+            path,
+            key,
+            secrets: Mutex::new(secrets),
+            unreadable: Mutex::new(None),
+        })
+    }
+
+    pub fn unreadable(&self) -> Option<UnreadableSecrets> {
+        self.unreadable.lock().unwrap().clone()
+    }
+
+    pub fn feishu_credentials(&self) -> Option<FeishuCredentials> {
+        self.secrets.lock().unwrap().feishu.clone()
+    }
+
+    pub fn feishu_endpoint(&self) -> FeishuEndpoint {
+        self.secrets.lock().unwrap().feishu_endpoint.clone()
+    }
+
+    pub fn ai_config(&self) -> Option<AiConfig> {
+        self.secrets.lock().unwrap().ai.clone()
+    }
+
+    pub fn set_feishu_credentials(&self, creds: FeishuCredentials) -> AppResult<()> {
+        self.update(|secrets| secrets.feishu = Some(creds))
+    }
+
+    pub fn set_feishu_endpoint(&self, endpoint: FeishuEndpoint) -> AppResult<()> {
+        self.update(|secrets| secrets.feishu_endpoint = endpoint)
+    }
+
+    pub fn set_ai_config(&self, config: AiConfig) -> AppResult<()> {
+        self.update(|secrets| secrets.ai = Some(config))
+    }
+
+    // 修改后立即落盘，写入失败时回滚内存中的数据
+    fn update(&self, apply: impl FnOnce(&mut Secrets)) -> AppResult<()> {
+        let mut guard = self.secrets.lock().unwrap();
+        let mut next = guard.clone();
+        apply(&mut next);
+        self.persist(&next)?;
+        *guard = next;
+        *self.unreadable.lock().unwrap() = None;
+        Ok(())
+    }
+
+    fn persist(&self, secrets: &Secrets) -> AppResult<()> {
+        let bytes = encrypt(&self.key, secrets)?;
+        // 先写临时文件再重命名，避免写入中断导致文件损坏
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, bytes)
+            .map_err(|e| AppError::storage(format!("写入加密存储失败: {}", e)))?;
+        fs::rename(&tmp_path, &self.path)
+            .map_err(|e| AppError::storage(format!("写入加密存储失败: {}", e)))?;
+        Ok(())
+    }
+}
+
+// 把无法读取的加密文件和密钥盐重命名保留（之后恢复原机器标识时仍可解密），返回加密文件的新路径
+fn set_aside(data_dir: &Path, path: &Path) -> AppResult<PathBuf> {
+    let suffix = format!("unreadable-{}", unix_now());
+    let backup = path.with_extension(format!("bin.{}", suffix));
+    fs::rename(path, &backup)
+        .map_err(|e| AppError::storage(format!("移走无法读取的加密存储失败: {}", e)))?;
+    let salt_path = data_dir.join(SALT_FILE);
+    if salt_path.exists() {
+        fs::rename(
+            &salt_path,
+            salt_path.with_extension(format!("salt.{}", suffix)),
+        )
+        .map_err(|e| AppError::storage(format!("移走密钥盐失败: {}", e)))?;
+    }
+    Ok(backup)
+}
+
+// 本地派生密钥：随机盐 + 数据目录路径 + 机器标识。
+// 已有加密文件时盐必须可用，重新生成会导致原文件再也无法解密
+fn derive_key(data_dir: &Path, store_exists: bool) -> AppResult<[u8; 32]> {
+    let salt_path = data_dir.join(SALT_FILE);
+    let salt = match fs::read(&salt_path) {
+        Ok(salt) if salt.len() == SALT_LEN => salt,
+        Ok(_) if store_exists => return Err(AppError::storage("密钥盐文件已损坏")),
+        Err(e) if store_exists => return Err(AppError::storage(format!("读取密钥盐失败: {}", e))),
+        _ => {
+            let salt = Aes256Gcm::generate_key(&mut OsRng).to_vec();
+            fs::write(&salt_path, &salt)
+                .map_err(|e| AppError::storage(format!("写入密钥盐失败: {}", e)))?;
+            salt
+        }
+    };
+
+    let mut hasher = Sha256::new();
+    hasher.update(b"a3.secret-store.v1");
+    hasher.update(&salt);
+    hasher.update(data_dir.to_string_lossy().as_bytes());
+    if let Ok(machine_id) = fs::read_to_string("/etc/machine-id") {
+        hasher.update(machine_id.trim().as_bytes());
+    }
+    Ok(hasher.finalize().into())
+}
+
+fn encrypt(key: &[u8; 32], secrets: &Secrets) -> AppResult<Vec<u8>> {
+    let plaintext = serde_json::to_vec(secrets)
+        .map_err(|e| AppError::storage(format!("序列化配置失败: {}", e)))?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_ref())
+        .map_err(|_| AppError::storage("加密配置失败"))?;
+
+    let mut out = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+fn decrypt(key: &[u8; 32], bytes: &[u8]) -> AppResult<Secrets> {
+    if bytes.len() < MAGIC.len() + NONCE_LEN || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(AppError::storage("文件格式无效"));
+    }
+    let (nonce, ciphertext) = bytes[MAGIC.len()..].split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| AppError::storage("密钥不匹配或文件已损坏"))?;
+    serde_json::from_slice(&plaintext)
+        .map_err(|e| AppError::storage(format!("解析配置失败: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credentials() -> FeishuCredentials {
+        FeishuCredentials {
+            app_id: "cli_a".to_string(),
+            app_secret: "secret".to_string(),
+        }
+    }
+
+    #[test]
+    fn saved_secrets_survive_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SecretStore::load(dir.path()).unwrap();
+        store.set_feishu_credentials(credentials()).unwrap();
+
+        let reloaded = SecretStore::load(dir.path()).unwrap();
+        assert_eq!(reloaded.feishu_credentials().unwrap().app_id, "cli_a");
+    }
+
+    #[test]
+    fn undecryptable_store_is_set_aside_and_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        SecretStore::load(dir.path())
+            .unwrap()
+            .set_feishu_credentials(credentials())
+            .unwrap();
+        let original = fs::read(dir.path().join(STORE_FILE)).unwrap();
+
+        // 盐被替换后无法解密
+        fs::write(dir.path().join(SALT_FILE), [7u8; SALT_LEN]).unwrap();
+        let store = SecretStore::load(dir.path()).unwrap();
+
+        assert!(store.feishu_credentials().is_none());
+        let unreadable = store.unreadable().unwrap();
+        assert_eq!(fs::read(&unreadable.backup).unwrap(), original);
+        assert!(!dir.path().join(STORE_FILE).exists());
+
+        // 重新填写后正常保存，提示清除
+        store.set_feishu_credentials(credentials()).unwrap();
+        assert!(store.unreadable().is_none());
+        let reloaded = SecretStore::load(dir.path()).unwrap();
+        assert_eq!(reloaded.feishu_credentials().unwrap().app_id, "cli_a");
+        assert!(reloaded.unreadable().is_none());
+    }
+
+    #[test]
+    fn missing_salt_is_not_regenerated_over_existing_store() {
+        let dir = tempfile::tempdir().unwrap();
+        SecretStore::load(dir.path())
+            .unwrap()
+            .set_feishu_credentials(credentials())
+            .unwrap();
+        let original = fs::read(dir.path().join(STORE_FILE)).unwrap();
+
+        fs::remove_file(dir.path().join(SALT_FILE)).unwrap();
+        let store = SecretStore::load(dir.path()).unwrap();
+
+        // 原文件保留在备份路径中，没有被新密钥覆盖
+        assert_eq!(
+            fs::read(store.unreadable().unwrap().backup).unwrap(),
+            original
+        );
+    }
+}
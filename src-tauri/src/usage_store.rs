@@ -0,0 +1,277 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::ai_provider::TokenUsage;
+use crate::answer_cache::unix_now;
+use crate::error::{AppError, AppResult};
+
+const USAGE_FILE: &str = "usage.db";
+
+// 每次 AI 调用的 token 用量，以及按模型配置的价格
+pub struct UsageStore {
+    conn: Mutex<Connection>,
+}
+
+// 一次 AI 调用的用量记录
+pub struct NewUsage<'a> {
+    pub command: &'a str, // 如 optimize_answer_with_ai、batch_review
+    pub model: &'a str,
+    pub record_id: Option<&'a str>,
+    pub user: Option<&'a str>,
+    pub usage: TokenUsage,
+}
+
+// 模型价格，按每百万 token 计，币种由使用者自行约定
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub model: String,
+    pub prompt_price: f64,
+    pub completion_price: f64,
+}
+
+// 按日期、用户和模型汇总的用量
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageSummary {
+    pub day: String, // 本地日期，YYYY-MM-DD
+    pub user: Option<String>,
+    pub model: String,
+    pub calls: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: Option<f64>, // 未配置该模型价格时为 None
+}
+
+impl UsageStore {
+    pub fn open(data_dir: &Path) -> AppResult<Self> {
+        let conn = Connection::open(data_dir.join(USAGE_FILE))
+            .map_err(|e| AppError::storage(format!("打开用量记录失败: {}", e)))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS usage (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                command TEXT NOT NULL,
+                model TEXT NOT NULL,
+                record_id TEXT,
+                user TEXT,
+                prompt_tokens INTEGER NOT NULL,
+                completion_tokens INTEGER NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS usage_by_time ON usage (created_at);
+            CREATE TABLE IF NOT EXISTS prices (
+                model TEXT PRIMARY KEY,
+                prompt_price REAL NOT NULL,
+                completion_price REAL NOT NULL
+            );",
+        )
+        .map_err(|e| AppError::storage(format!("初始化用量记录失败: {}", e)))?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    pub fn record(&self, usage: NewUsage) -> AppResult<()> {
+        self.record_at(usage, unix_now())
+    }
+
+    fn record_at(&self, usage: NewUsage, created_at: i64) -> AppResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO usage (command, model, record_id, user, prompt_tokens,
+                                completion_tokens, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                usage.command,
+                usage.model,
+                usage.record_id,
+                usage.user,
+                usage.usage.prompt_tokens as i64,
+                usage.usage.completion_tokens as i64,
+                created_at
+            ],
+        )
+        .map_err(|e| AppError::storage(format!("保存用量记录失败: {}", e)))?;
+        Ok(())
+    }
+
+    // 汇总 [since, until) 时间段（秒级时间戳，为空表示不限）内的用量，最近的日期在前
+    pub fn summary(&self, since: Option<i64>, until: Option<i64>) -> AppResult<Vec<UsageSummary>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT date(u.created_at, 'unixepoch', 'localtime') AS day, u.user, u.model,
+                        COUNT(*), SUM(u.prompt_tokens), SUM(u.completion_tokens),
+                        p.prompt_price, p.completion_price
+                 FROM usage u LEFT JOIN prices p ON p.model = u.model
+                 WHERE (?1 IS NULL OR u.created_at >= ?1) AND (?2 IS NULL OR u.created_at < ?2)
+                 GROUP BY day, u.user, u.model
+                 ORDER BY day DESC, u.user, u.model",
+            )
+            .map_err(|e| AppError::storage(format!("读取用量记录失败: {}", e)))?;
+        let rows = stmt
+            .query_map(params![since, until], |row| {
+                let prompt_tokens = row.get::<_, i64>(4)? as u64;
+                let completion_tokens = row.get::<_, i64>(5)? as u64;
+                let prices: (Option<f64>, Option<f64>) = (row.get(6)?, row.get(7)?);
+                Ok(UsageSummary {
+                    day: row.get(0)?,
+                    user: row.get(1)?,
+                    model: row.get(2)?,
+                    calls: row.get::<_, i64>(3)? as u64,
+                    prompt_tokens,
+                    completion_tokens,
+                    cost: match prices {
+                        (Some(prompt_price), Some(completion_price)) => Some(
+                            (prompt_tokens as f64 * prompt_price
+                                + completion_tokens as f64 * completion_price)
+                                / 1_000_000.0,
+                        ),
+                        _ => None,
+                    },
+                })
+            })
+            .map_err(|e| AppError::storage(format!("读取用量记录失败: {}", e)))?;
+        rows.collect::<rusqlite::Result<_>>()
+            .map_err(|e| AppError::storage(format!("读取用量记录失败: {}", e)))
+    }
+
+    pub fn prices(&self) -> AppResult<Vec<ModelPrice>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT model, prompt_price, completion_price FROM prices ORDER BY model")
+            .map_err(|e| AppError::storage(format!("读取模型价格失败: {}", e)))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(ModelPrice {
+                    model: row.get(0)?,
+                    prompt_price: row.get(1)?,
+                    completion_price: row.get(2)?,
+                })
+            })
+            .map_err(|e| AppError::storage(format!("读取模型价格失败: {}", e)))?;
+        rows.collect::<rusqlite::Result<_>>()
+            .map_err(|e| AppError::storage(format!("读取模型价格失败: {}", e)))
+    }
+
+    pub fn set_price(&self, price: &ModelPrice) -> AppResult<()> {
+        if price.model.trim().is_empty() {
+            return Err(AppError::invalid_input("模型名称不能为空"));
+        }
+        let valid = |p: f64| p.is_finite() && p >= 0.0;
+        if !valid(price.prompt_price) || !valid(price.completion_price) {
+            return Err(AppError::invalid_input("价格必须是非负数"));
+        }
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO prices (model, prompt_price, completion_price) VALUES (?1, ?2, ?3)
+             ON CONFLICT(model) DO UPDATE SET prompt_price = ?2, completion_price = ?3",
+            params![
+                price.model.trim(),
+                price.prompt_price,
+                price.completion_price
+            ],
+        )
+        .map_err(|e| AppError::storage(format!("保存模型价格失败: {}", e)))?;
+        Ok(())
+    }
+
+    pub fn remove_price(&self, model: &str) -> AppResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM prices WHERE model = ?1", params![model])
+            .map_err(|e| AppError::storage(format!("删除模型价格失败: {}", e)))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage<'a>(
+        model: &'a str,
+        user: Option<&'a str>,
+        prompt: u64,
+        completion: u64,
+    ) -> NewUsage<'a> {
+        NewUsage {
+            command: "review_answer_with_ai",
+            model,
+            record_id: Some("rec1"),
+            user,
+            usage: TokenUsage {
+                prompt_tokens: prompt,
+                completion_tokens: completion,
+            },
+        }
+    }
+
+    #[test]
+    fn summary_groups_by_day_user_and_model_with_cost() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = UsageStore::open(dir.path()).unwrap();
+        // 相隔两天，避免时区影响分组
+        let day1 = 1_700_000_000;
+        let day2 = day1 + 2 * 24 * 60 * 60;
+        store
+            .record_at(usage("gpt", Some("alice"), 1000, 500), day1)
+            .unwrap();
+        store
+            .record_at(usage("gpt", Some("alice"), 3000, 1500), day1)
+            .unwrap();
+        store
+            .record_at(usage("gpt", Some("bob"), 100, 50), day1)
+            .unwrap();
+        store
+            .record_at(usage("local", None, 200, 100), day2)
+            .unwrap();
+        store
+            .set_price(&ModelPrice {
+                model: "gpt".to_string(),
+                prompt_price: 2.0,
+                completion_price: 8.0,
+            })
+            .unwrap();
+
+        let summary = store.summary(None, None).unwrap();
+
+        assert_eq!(summary.len(), 3);
+        assert_eq!(summary[0].model, "local");
+        assert_eq!(summary[0].cost, None);
+        let alice = &summary[1];
+        assert_eq!(alice.user.as_deref(), Some("alice"));
+        assert_eq!(
+            (alice.calls, alice.prompt_tokens, alice.completion_tokens),
+            (2, 4000, 2000)
+        );
+        // 4000 * 2 / 1M + 2000 * 8 / 1M
+        assert!((alice.cost.unwrap() - 0.024).abs() < 1e-9);
+        assert_eq!(summary[2].user.as_deref(), Some("bob"));
+        assert_ne!(summary[0].day, summary[1].day);
+
+        let since_day2 = store.summary(Some(day2), None).unwrap();
+        assert_eq!(since_day2.len(), 1);
+        assert_eq!(store.summary(None, Some(day1)).unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn prices_can_be_updated_and_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = UsageStore::open(dir.path()).unwrap();
+        let mut price = ModelPrice {
+            model: "gpt".to_string(),
+            prompt_price: 1.0,
+            completion_price: 2.0,
+        };
+        store.set_price(&price).unwrap();
+        price.completion_price = 3.0;
+        store.set_price(&price).unwrap();
+
+        assert_eq!(store.prices().unwrap(), vec![price.clone()]);
+
+        price.prompt_price = -1.0;
+        assert!(store.set_price(&price).is_err());
+        store.remove_price("gpt").unwrap();
+        assert!(store.prices().unwrap().is_empty());
+    }
+}
@@ -7,6 +7,7 @@ import SettingsPage from "./pages/SettingsPage";
 import AnswerList from "./components/AnswerList";
 import HelpPage from "./pages/HelpPage";
 import SplashScreen from "./components/SplashScreen";
+import { migrateLegacySecrets, getSecretStoreStatus, UnreadableSecrets } from "./lib/api";
 
 // 设置页面需要登录保护
 function SettingsProtectedRoute({ children }: { children: React.ReactNode }) {
@@ -52,6 +53,16 @@ function AppRoutes() {
 
 function App() {
   const [showSplash, setShowSplash] = useState(true);
+  const [unreadableSecrets, setUnreadableSecrets] = useState<UnreadableSecrets | null>(null);
+
+  useEffect(() => {
+    // 把旧版本 localStorage 中的明文凭证迁移到后端加密存储
+    migrateLegacySecrets();
+    // 已保存的凭证无法读取时提示重新填写
+    getSecretStoreStatus()
+      .then(setUnreadableSecrets)
+      .catch((error) => console.error("读取凭证状态失败:", error));
+  }, []);
 
   useEffect(() => {
     // 检查是否已经显示过启动动画（使用 sessionStorage）
@@ -75,6 +86,17 @@ function App() {
           v7_relativeSplatPath: true,
         }}
       >
+        {unreadableSecrets && (
+          <div className="flex items-start justify-between gap-4 p-3 text-sm bg-amber-50 text-amber-900 border-b border-amber-200">
+            <span>
+              已保存的飞书凭证和 AI 配置无法读取（{unreadableSecrets.reason}），请在设置中重新填写。
+              原文件已保留在 {unreadableSecrets.backup}
+            </span>
+            <button className="shrink-0 text-amber-700 hover:underline" onClick={() => setUnreadableSecrets(null)}>
+              知道了
+            </button>
+          </div>
+        )}
         <AppRoutes />
       </BrowserRouter>
     </AuthProvider>
@@ -1,8 +1,9 @@
 import { useState, useEffect } from "react";
 import { useNavigate, useLocation } from "react-router-dom";
 import { useAuth } from "../contexts/AuthContext";
-import { listAnswers, loadFeishuConfig, getBitableTables, Answer, optimizeAnswerWithAI, reviewAnswerWithAI, checkAnswerRisk, updateAnswerToFeishu, createAnswerToFeishu, saveAnswersCache, loadAnswersCache, getBitableRecord, AnswerRecord, getAnswersData, openExternalUrl, canSyncToday, saveLastSyncTimeForUser } from "../lib/api";
-import { extractOptimizedAnswer, extractReviewResult, ReviewResult, getFeishuRecordId, calculateAnswerMatchScore } from "../lib/utils";
+import { listAnswersWithMeta, syncAnswers, loadFeishuConfig, getBitableTables, Answer, optimizeAnswerWithAI, reviewAnswerWithAI, checkAnswerRisk, updateAnswerToFeishu, createAnswerToFeishu, saveAnswersCache, loadAnswersCache, getBitableRecord, AnswerRecord, getAnswersData, openExternalUrl, canSyncToday, saveLastSyncTimeForUser, errorMessage as describeError, FieldDiff, newAiRequestId, cancelAiRequest, isLocalMode } from "../lib/api";
+import BatchReviewPanel from "./BatchReviewPanel";
+import { toOptimizedAnswer, toReviewResult, ReviewResult, getFeishuRecordId, calculateAnswerMatchScore } from "../lib/utils";
 import { Button } from "./ui/button";
 import { Input } from "./ui/input";
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "./ui/card";
@@ -24,8 +25,10 @@ export default function AnswerList() {
   const [selectedTableId, setSelectedTableId] = useState<string>(""); // 选中的表格ID
   const [optimizing, setOptimizing] = useState(false); // AI 优化中
   const [reviewing, setReviewing] = useState(false); // AI 审核中
+  const [streamingText, setStreamingText] = useState(""); // AI 流式输出中已生成的文本
+  const [aiRequestId, setAiRequestId] = useState<string | null>(null); // 进行中的 AI 请求，用于取消
   const [checkingRisk, setCheckingRisk] = useState(false); // 风险检测中
-  const [optimizedResult, setOptimizedResult] = useState<{ answerText: string; explanationText?: string } | null>(null);
+  const [optimizedResult, setOptimizedResult] = useState<{ answerText: string; explanationText?: string; cached?: boolean } | null>(null);
   const [reviewResult, setReviewResult] = useState<ReviewResult | null>(null);
   const [riskResult, setRiskResult] = useState<{ hasRisk: boolean; reason: string } | null>(null);
   const [submitting, setSubmitting] = useState(false); // 提交中
@@ -165,10 +168,10 @@ export default function AnswerList() {
         return;
       }
 
-      // 检查是否为本地模式（没有 appId 和 appSecret）
-      const isLocalMode = !config.appId || !config.appSecret;
+      // 检查是否为本地模式（后端没有飞书凭证）
+      const localMode = isLocalMode(config);
       
-      if (isLocalMode) {
+      if (localMode) {
         // 本地模式：只能使用配置中的表格ID，无法获取表格列表
         if (config.tableId) {
           setSelectedTableId(config.tableId);
@@ -259,10 +262,10 @@ export default function AnswerList() {
         return;
       }
 
-      // 检查是否为本地模式（没有 appId 和 appSecret）
-      const isLocalMode = !config.appId || !config.appSecret;
+      // 检查是否为本地模式（后端没有飞书凭证）
+      const localMode = isLocalMode(config);
       
-      if (isLocalMode) {
+      if (localMode) {
         // 本地模式：只能使用缓存数据
         const cache = loadAnswersCache(tableId);
         if (cache && cache.data.length > 0) {
@@ -303,8 +306,9 @@ export default function AnswerList() {
         return;
       }
 
-      // 调用后端 list_answers 命令
-      const data = await listAnswers(config.appToken, tableId);
+      // 增量同步到后端本地缓存（首次为全量），再从缓存读取结构化数据
+      await syncAnswers(config.appToken, tableId);
+      const data = (await listAnswersWithMeta(config.appToken, tableId, true)).answers;
       setAnswers(data);
       setLoadingState("success");
       
@@ -559,7 +563,7 @@ export default function AnswerList() {
       setTimeout(() => setCopySuccess(null), 2000);
       setTimeout(() => setSubmitMessage(""), 3000);
     } catch (error: any) {
-      setSubmitMessage(`复制失败: ${error.message || error.toString()}`);
+      setSubmitMessage(`复制失败: ${describeError(error)}`);
     }
   };
 
@@ -577,42 +581,77 @@ export default function AnswerList() {
     setProductInfoExpanded(false);
   };
 
-  const handleOptimize = async () => {
+  // bypassCache 为 true 时重新调用 AI，不使用缓存的结果
+  const handleOptimize = async (bypassCache = false) => {
     if (!selectedAnswer) return;
     setOptimizing(true);
     setOptimizedResult(null);
+    setStreamingText("");
+    const requestId = newAiRequestId();
+    setAiRequestId(requestId);
     try {
       const context = `问题：${selectedAnswer.question}\n产品：${selectedAnswer.product_name}\n场景：${selectedAnswer.scene}\n语气：${selectedAnswer.tone}`;
-      const result = await optimizeAnswerWithAI(selectedAnswer.standard_answer, context);
-      const extracted = extractOptimizedAnswer(result);
-      setOptimizedResult(extracted);
+      const result = await optimizeAnswerWithAI(
+        selectedAnswer.standard_answer,
+        context,
+        (text) => setStreamingText((prev) => prev + text),
+        requestId,
+        { recordId: selectedAnswer.record_id, user: currentUser?.username, bypassCache }
+      );
+      setOptimizedResult(toOptimizedAnswer(result));
     } catch (error: any) {
-      setOptimizedResult({
-        answerText: error?.toString() || "优化失败",
-      });
+      // 用户主动取消时不显示错误
+      if (error?.kind !== "cancelled") {
+        setOptimizedResult({
+          answerText: describeError(error) || "优化失败",
+        });
+      }
     } finally {
       setOptimizing(false);
+      setStreamingText("");
+      setAiRequestId(null);
     }
   };
 
-  const handleReview = async () => {
+  const handleReview = async (bypassCache = false) => {
     if (!selectedAnswer) return;
     setReviewing(true);
     setReviewResult(null);
+    setStreamingText("");
+    const requestId = newAiRequestId();
+    setAiRequestId(requestId);
     try {
       const context = `问题：${selectedAnswer.question}\n产品：${selectedAnswer.product_name}\n场景：${selectedAnswer.scene}\n语气：${selectedAnswer.tone}`;
-      const result = await reviewAnswerWithAI(selectedAnswer.standard_answer, context);
-      const extracted = extractReviewResult(result);
-      setReviewResult(extracted);
+      const result = await reviewAnswerWithAI(
+        selectedAnswer.standard_answer,
+        context,
+        (text) => setStreamingText((prev) => prev + text),
+        requestId,
+        { recordId: selectedAnswer.record_id, user: currentUser?.username, bypassCache }
+      );
+      setReviewResult(toReviewResult(result));
     } catch (error: any) {
-      setReviewResult({
-        conclusion: "",
-        judgmentExplanation: error?.toString() || "审核失败",
-        riskPoints: "",
-        rawText: error?.toString() || "审核失败",
-      });
+      if (error?.kind !== "cancelled") {
+        setReviewResult({
+          conclusion: "",
+          judgmentExplanation: describeError(error) || "审核失败",
+          riskPoints: "",
+          rawText: describeError(error) || "审核失败",
+        });
+      }
     } finally {
       setReviewing(false);
+      setStreamingText("");
+      setAiRequestId(null);
+    }
+  };
+
+  const handleCancelAi = async () => {
+    if (!aiRequestId) return;
+    try {
+      await cancelAiRequest(aiRequestId);
+    } catch (error) {
+      console.error("取消 AI 请求失败:", error);
     }
   };
 
@@ -621,12 +660,15 @@ export default function AnswerList() {
     setCheckingRisk(true);
     setRiskResult(null);
     try {
-      const result = await checkAnswerRisk(selectedAnswer.standard_answer);
+      const result = await checkAnswerRisk(selectedAnswer.standard_answer, {
+        recordId: selectedAnswer.record_id,
+        user: currentUser?.username,
+      });
       setRiskResult(result);
     } catch (error: any) {
       setRiskResult({
         hasRisk: false,
-        reason: error?.toString() || "检测失败",
+        reason: describeError(error) || "检测失败",
       });
     } finally {
       setCheckingRisk(false);
@@ -685,7 +727,7 @@ export default function AnswerList() {
       setSubmitMessage("已复制到剪贴板，可直接粘贴使用");
       setTimeout(() => setSubmitMessage(""), 3000);
     } catch (error: any) {
-      setSubmitMessage(`复制失败: ${error.message || error.toString()}`);
+      setSubmitMessage(`复制失败: ${describeError(error)}`);
     }
   };
 
@@ -709,8 +751,8 @@ export default function AnswerList() {
     }
 
     // 检查是否为本地模式
-    const isLocalMode = !config.appId || !config.appSecret;
-    if (isLocalMode) {
+    const localMode = isLocalMode(config);
+    if (localMode) {
       setSubmitMessage("本地模式下无法创建问题，请配置完整的飞书凭证（App ID 和 App Secret）");
       return;
     }
@@ -728,13 +770,12 @@ export default function AnswerList() {
         "状态": "待审核",
       };
 
-      await createAnswerToFeishu(config.appToken, tableId, fields);
+      const created = await createAnswerToFeishu(config.appToken, tableId, fields);
       setSubmitMessage("问题已成功添加到飞书！");
       setShowAddQuestionDialog(false);
       setNewQuestion("");
-      
-      // 可选：自动同步一次以刷新列表（但受频率限制）
-      // await loadAnswersData();
+      // 直接插入返回的新记录，无需重新同步
+      setAnswers((prev) => [created, ...prev]);
     } catch (error: any) {
       setSubmitMessage(`创建失败: ${error.message || error}`);
     } finally {
@@ -753,8 +794,8 @@ export default function AnswerList() {
 
     // 检查是否为本地模式
     const config = loadFeishuConfig();
-    const isLocalMode = !config || !config.appId || !config.appSecret;
-    if (isLocalMode) {
+    const localMode = isLocalMode(config);
+    if (localMode) {
       setSubmitMessage("本地模式下无法写回飞书，请配置完整的飞书凭证（App ID 和 App Secret）");
       return;
     }
@@ -860,11 +901,20 @@ export default function AnswerList() {
       }
 
       // 使用 feishu_record_id 写回
-      await updateAnswerToFeishu(
+      const updated = await updateAnswerToFeishu(
         config.appToken,
         tableId,
         feishuRecordId,
-        fields
+        fields,
+        selectedAnswer.last_modified_time,
+        { author: currentUser?.username, ai_action: sourceValue }
+      );
+      // 用返回的最新记录替换列表中的旧数据
+      setAnswers((prev) =>
+        prev.map((a) => (a.record_id === updated.record_id ? updated : a))
+      );
+      setSelectedAnswer((prev) =>
+        prev && prev.record_id === updated.record_id ? updated : prev
       );
 
       setSubmitMessage("提交成功！已写回飞书（状态：已通过）");
@@ -872,7 +922,14 @@ export default function AnswerList() {
       setDraftContent("");
       setShowDraftEditor(false);
     } catch (error: any) {
-      setSubmitMessage(`提交失败: ${error.message || error.toString()}`);
+      if (error?.kind === "conflict" && error.conflict) {
+        const changed = error.conflict.fields.map((d: FieldDiff) => d.field).join("、");
+        setSubmitMessage(
+          `提交失败: ${describeError(error)}${changed ? `（有差异的字段：${changed}）` : ""}`
+        );
+        return;
+      }
+      setSubmitMessage(`提交失败: ${describeError(error)}`);
     } finally {
       setSubmitting(false);
     }
@@ -942,11 +999,11 @@ export default function AnswerList() {
               )}
               {(() => {
                 const config = loadFeishuConfig();
-                const isLocalMode = !config || !config.appId || !config.appSecret;
+                const localMode = isLocalMode(config);
                 // 检查普通用户今天是否已经同步过
                 const userId = currentUser?.id || "default";
                 const canSync = canSyncToday(userId, role || null);
-                const isDisabled = loadingState === "loading" || isLocalMode || (!canSync && role === "user");
+                const isDisabled = loadingState === "loading" || localMode || (!canSync && role === "user");
                 
                 return (
                   <Button
@@ -955,7 +1012,7 @@ export default function AnswerList() {
                     variant="outline"
                     size="sm"
                     title={
-                      isLocalMode 
+                      localMode 
                         ? "本地模式下无法同步数据，请配置完整的飞书凭证（App ID 和 App Secret）"
                         : !canSync && role === "user"
                         ? "普通用户一天只能同步一次，今天已同步过，请明天再试"
@@ -970,7 +1027,7 @@ export default function AnswerList() {
                     ) : (
                       <>
                         <RefreshCw className="w-4 h-4 mr-2" />
-                        {isLocalMode ? "本地模式" : !canSync && role === "user" ? "今日已同步" : "同步数据"}
+                        {localMode ? "本地模式" : !canSync && role === "user" ? "今日已同步" : "同步数据"}
                       </>
                     )}
                   </Button>
@@ -1008,6 +1065,13 @@ export default function AnswerList() {
       
       <div className="p-8">
         <div className="max-w-7xl mx-auto">
+        {role === "admin" && (() => {
+          const config = loadFeishuConfig();
+          const tableId = selectedTableId || config?.tableId;
+          return config?.appToken && tableId ? (
+            <BatchReviewPanel appToken={config.appToken} tableId={tableId} />
+          ) : null;
+        })()}
         <Card>
           <CardHeader>
             <div className="flex justify-between items-start">
@@ -1016,8 +1080,8 @@ export default function AnswerList() {
                 <CardDescription>
                   {loadingState === "idle" && (() => {
                     const config = loadFeishuConfig();
-                    const isLocalMode = !config || !config.appId || !config.appSecret;
-                    if (isLocalMode) {
+                    const localMode = isLocalMode(config);
+                    if (localMode) {
                       return <>本地模式：仅显示缓存数据，无法同步最新数据</>;
                     }
                     return (
@@ -1040,12 +1104,12 @@ export default function AnswerList() {
             {/* 初始态：提示用户同步数据 */}
             {loadingState === "idle" && (() => {
               const config = loadFeishuConfig();
-              const isLocalMode = !config || !config.appId || !config.appSecret;
+              const localMode = isLocalMode(config);
               return (
                 <div className="flex flex-col items-center justify-center py-12">
                   <RefreshCw className="w-12 h-12 text-gray-400 mb-4" />
                   <p className="text-gray-600 font-medium mb-2">尚未同步数据</p>
-                  {isLocalMode ? (
+                  {localMode ? (
                     <>
                       <p className="text-gray-500 text-sm text-center max-w-md mb-4">
                         当前为本地模式，只能使用缓存数据。如需同步最新数据，请前往设置页面配置完整的飞书凭证（App ID 和 App Secret）
@@ -1558,7 +1622,7 @@ export default function AnswerList() {
                     <Button
                       variant="outline"
                       size="lg"
-                      onClick={handleOptimize}
+                      onClick={() => handleOptimize()}
                       disabled={optimizing}
                       className="flex-1 min-w-[140px] border-2 hover:border-blue-400 hover:bg-blue-50/50 transition-all duration-200"
                     >
@@ -1577,7 +1641,7 @@ export default function AnswerList() {
                     <Button
                       variant="outline"
                       size="lg"
-                      onClick={handleReview}
+                      onClick={() => handleReview()}
                       disabled={reviewing}
                       className="flex-1 min-w-[140px] border-2 hover:border-indigo-400 hover:bg-indigo-50/50 transition-all duration-200"
                     >
@@ -1614,6 +1678,22 @@ export default function AnswerList() {
                     </Button>
                   </div>
 
+                  {/* AI 生成中的实时输出 */}
+                  {(optimizing || reviewing) && (
+                    <Card className="mb-6 border-2 border-gray-200/50 bg-white/60">
+                      <CardContent className="pt-4">
+                        <div className="flex items-start justify-between gap-3">
+                          <p className="text-sm text-gray-700 whitespace-pre-wrap">
+                            {streamingText || (optimizing ? "正在优化..." : "正在审核...")}
+                          </p>
+                          <Button variant="outline" size="sm" onClick={handleCancelAi} disabled={!aiRequestId}>
+                            取消
+                          </Button>
+                        </div>
+                      </CardContent>
+                    </Card>
+                  )}
+
                   {/* AI 优化结果 */}
                   {optimizedResult && (
                     <Card className="mb-6 border-2 border-blue-200/50 bg-gradient-to-br from-blue-50/50 to-indigo-50/50 shadow-lg">
@@ -1622,6 +1702,16 @@ export default function AnswerList() {
                           <div className="flex items-center gap-3">
                             <div className="w-1 h-8 bg-gradient-to-b from-blue-600 to-indigo-600 rounded-full"></div>
                             <CardTitle className="text-base font-bold text-gray-900">AI 优化结果</CardTitle>
+                            {optimizedResult.cached && (
+                              <button
+                                onClick={() => handleOptimize(true)}
+                                disabled={optimizing}
+                                className="flex items-center gap-1 text-xs text-gray-500 hover:text-blue-600"
+                                title="该结果来自本地缓存，点击重新调用 AI"
+                              >
+                                缓存结果 · <RefreshCw className="w-3 h-3" /> 重新生成
+                              </button>
+                            )}
                           </div>
                           <div className="flex items-center gap-1">
                             <button
@@ -1756,6 +1846,16 @@ export default function AnswerList() {
                               "bg-gradient-to-b from-gray-600 to-slate-600"
                             }`}></div>
                             <CardTitle className="text-base font-bold text-gray-900">AI 审核结果</CardTitle>
+                            {reviewResult.cached && (
+                              <button
+                                onClick={() => handleReview(true)}
+                                disabled={reviewing}
+                                className="flex items-center gap-1 text-xs text-gray-500 hover:text-indigo-600"
+                                title="该结果来自本地缓存，点击重新调用 AI"
+                              >
+                                缓存结果 · <RefreshCw className="w-3 h-3" /> 重新生成
+                              </button>
+                            )}
                           </div>
                           <div className="flex items-center gap-1">
                             <button
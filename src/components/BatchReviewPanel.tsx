@@ -0,0 +1,166 @@
+import { useState, useEffect } from "react";
+import {
+  startBatchReview,
+  resumeBatchReview,
+  pauseBatchReview,
+  listBatchReviews,
+  getBatchReviewResults,
+  onBatchReviewProgress,
+  errorMessage,
+  BatchJob,
+  BatchItemResult,
+} from "../lib/api";
+import { useAuth } from "../contexts/AuthContext";
+import { Button } from "./ui/button";
+import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "./ui/card";
+import { ClipboardCheck, Loader2, Pause, Play } from "lucide-react";
+
+const VERDICT_LABELS: Record<string, string> = {
+  reasonable: "合理",
+  mostly_reasonable: "基本合理",
+  needs_revision: "需修改",
+};
+
+interface BatchReviewPanelProps {
+  appToken: string;
+  tableId: string;
+}
+
+// 批量审核：对表中所有启用的答案执行 AI 审核和风险检测，并列出需修改或有风险的答案
+export default function BatchReviewPanel({ appToken, tableId }: BatchReviewPanelProps) {
+  const [job, setJob] = useState<BatchJob | null>(null);
+  const [results, setResults] = useState<BatchItemResult[]>([]);
+  const [busy, setBusy] = useState(false);
+  const [error, setError] = useState("");
+  const { currentUser } = useAuth();
+
+  // 加载该表最近一次任务
+  useEffect(() => {
+    if (!appToken || !tableId) return;
+    listBatchReviews(appToken, tableId)
+      .then((jobs) => setJob(jobs[0] ?? null))
+      .catch((err) => console.error("加载批量审核任务失败:", err));
+  }, [appToken, tableId]);
+
+  useEffect(() => {
+    const unlisten = onBatchReviewProgress(({ error: jobError, ...progress }) => {
+      setJob((current) => (current && current.id !== progress.id ? current : progress));
+      // 后台任务出错停止时显示原因
+      if (jobError) {
+        setError(`批量审核任务 #${progress.id} 已暂停：${errorMessage(jobError)}`);
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // 任务停止后刷新报告
+  useEffect(() => {
+    if (!job || job.status === "running") return;
+    getBatchReviewResults(job.id)
+      .then(setResults)
+      .catch((err) => console.error("加载批量审核结果失败:", err));
+  }, [job?.id, job?.status]);
+
+  const run = async (action: () => Promise<unknown>) => {
+    setBusy(true);
+    setError("");
+    try {
+      await action();
+    } catch (err) {
+      setError(errorMessage(err) || "操作失败");
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const handleStart = () =>
+    run(async () => {
+      setResults([]);
+      setJob(await startBatchReview(appToken, tableId, currentUser?.username));
+    });
+  const handlePause = () => job && run(() => pauseBatchReview(job.id));
+  const handleResume = () => job && run(async () => setJob(await resumeBatchReview(job.id, currentUser?.username)));
+
+  const processed = job ? job.done + job.failed : 0;
+  const percent = job && job.total > 0 ? Math.round((processed / job.total) * 100) : 0;
+
+  return (
+    <Card className="mb-6">
+      <CardHeader>
+        <CardTitle className="flex items-center gap-2">
+          <ClipboardCheck className="w-5 h-5" />
+          批量审核
+        </CardTitle>
+        <CardDescription>对所有启用的答案执行 AI 审核和风险检测，可随时暂停，结果保存在本地</CardDescription>
+      </CardHeader>
+      <CardContent className="space-y-4">
+        {job && (
+          <div className="space-y-2">
+            <div className="flex justify-between text-sm text-gray-600">
+              <span>
+                {job.status === "running" ? "审核中" : job.status === "paused" ? "已暂停" : "已完成"}：
+                {processed}/{job.total}
+                {job.failed > 0 && `（${job.failed} 条出错，继续任务时重试）`}
+              </span>
+              <span>需关注 {job.flagged} 条</span>
+            </div>
+            <div className="h-2 bg-gray-100 rounded-full overflow-hidden">
+              <div className="h-full bg-blue-500 transition-all" style={{ width: `${percent}%` }} />
+            </div>
+          </div>
+        )}
+
+        {error && <div className="p-3 rounded-md text-sm bg-red-50 text-red-800">{error}</div>}
+
+        <div className="flex gap-3">
+          {job?.status === "running" ? (
+            <Button variant="outline" onClick={handlePause} disabled={busy}>
+              <Pause className="w-4 h-4 mr-2" />
+              暂停
+            </Button>
+          ) : (
+            job &&
+            (job.status === "paused" || job.failed > 0) && (
+              <Button variant="outline" onClick={handleResume} disabled={busy}>
+                <Play className="w-4 h-4 mr-2" />
+                继续
+              </Button>
+            )
+          )}
+          <Button onClick={handleStart} disabled={busy || job?.status === "running"}>
+            {busy ? <Loader2 className="w-4 h-4 mr-2 animate-spin" /> : <ClipboardCheck className="w-4 h-4 mr-2" />}
+            开始新的批量审核
+          </Button>
+        </div>
+
+        {results.length > 0 && (
+          <div className="divide-y border rounded-md">
+            {results.map((result) => (
+              <div key={result.record_id} className="p-3 text-sm space-y-1">
+                <div className="font-medium text-gray-900">{result.question}</div>
+                <div className="flex gap-2 text-xs">
+                  {result.review?.verdict === "needs_revision" && (
+                    <span className="px-2 py-0.5 rounded bg-amber-100 text-amber-800">
+                      {VERDICT_LABELS[result.review.verdict]}
+                    </span>
+                  )}
+                  {result.risk?.has_risk && (
+                    <span className="px-2 py-0.5 rounded bg-red-100 text-red-800">存在风险</span>
+                  )}
+                </div>
+                {result.review?.revision_reason && (
+                  <div className="text-gray-600">修改原因：{result.review.revision_reason}</div>
+                )}
+                {result.risk?.has_risk && result.risk.reason && (
+                  <div className="text-gray-600">风险：{result.risk.reason}</div>
+                )}
+              </div>
+            ))}
+          </div>
+        )}
+      </CardContent>
+    </Card>
+  );
+}
@@ -1,6 +1,6 @@
 import { useState, useEffect } from "react";
 import { useNavigate } from "react-router-dom";
-import { saveFeishuConfig, loadFeishuConfig, testConnection, TableConfig, setAiConfig, loadAiConfig, testAiConnection } from "../lib/api";
+import { saveFeishuConfig, loadFeishuConfig, setFeishuCredentials, testConnection, TableConfig, setAiConfig, loadAiConfig, testAiConnection } from "../lib/api";
 import { extractBitableInfo } from "../lib/utils";
 import { Button } from "./ui/button";
 import { Input } from "./ui/input";
@@ -71,7 +71,6 @@ export default function Config() {
     const savedConfig = loadFeishuConfig();
     if (savedConfig) {
       setAppId(savedConfig.appId || "");
-      setAppSecret(savedConfig.appSecret || "");
       
       // 加载 BITABLE_APP_TOKEN（应用级别）
       if (savedConfig.appToken) {
@@ -217,7 +216,9 @@ export default function Config() {
 
   // 处理保存配置
   const handleSave = async () => {
-    if (!appId || !appSecret) {
+    // 后端已保存同一 App ID 的凭证时 App Secret 可留空
+    const saved = loadFeishuConfig();
+    if (!appId || (!appSecret && !(saved?.configured && saved.appId === appId))) {
       setMessage("请填写完整的飞书凭证信息");
       setTestResult(null);
       return;
@@ -263,9 +264,12 @@ export default function Config() {
       // 使用第一个表格作为主表格（兼容旧版本）
       const firstTable = tables[0];
 
+      // App Secret 留空时保留后端已保存的凭证
+      if (appSecret) {
+        await setFeishuCredentials(appId, appSecret);
+      }
       await saveFeishuConfig({
         appId,
-        appSecret,
         appToken: appToken,
         tableId: firstTable.tableId,
         tables,
@@ -391,7 +395,7 @@ export default function Config() {
               </label>
               <Input
                 type="password"
-                placeholder="请输入飞书应用 App Secret"
+                placeholder="请输入飞书应用 App Secret（已保存时可留空）"
                 value={appSecret}
                 onChange={(e) => setAppSecret(e.target.value)}
               />
@@ -1,11 +1,42 @@
 import { useState, useEffect } from "react";
-import { setAiConfig, loadAiConfig, testAiConnection } from "../../lib/api";
+import { setAiConfig, loadAiConfig, testAiConnection, clearAiCache, errorMessage, AiProvider } from "../../lib/api";
 import { Button } from "../ui/button";
 import { Input } from "../ui/input";
+import { Select } from "../ui/select";
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "../ui/card";
-import { TestTube2, CheckCircle2, XCircle, Loader2, Sparkles } from "lucide-react";
+import { TestTube2, CheckCircle2, XCircle, Loader2, Sparkles, Trash2 } from "lucide-react";
+
+// 各接口类型的默认地址和说明
+const PROVIDERS: Record<AiProvider, { label: string; baseUrl: string; modelLabel: string; modelPlaceholder: string }> = {
+  openai: {
+    label: "OpenAI 兼容接口（火山方舟等）",
+    baseUrl: "https://ark.cn-beijing.volces.com/api/v3",
+    modelLabel: "模型 ID",
+    modelPlaceholder: "例如：doubao-pro",
+  },
+  anthropic: {
+    label: "Anthropic",
+    baseUrl: "https://api.anthropic.com/v1",
+    modelLabel: "模型 ID",
+    modelPlaceholder: "例如：claude-sonnet-4-5",
+  },
+  azure: {
+    label: "Azure OpenAI",
+    baseUrl: "https://<资源名>.openai.azure.com",
+    modelLabel: "部署名称",
+    modelPlaceholder: "例如：gpt-4o",
+  },
+  ollama: {
+    label: "Ollama（本地）",
+    baseUrl: "http://localhost:11434",
+    modelLabel: "模型名称",
+    modelPlaceholder: "例如：qwen2.5:7b",
+  },
+};
 
 export default function AISettings() {
+  const [aiProvider, setAiProvider] = useState<AiProvider>("openai");
+  const [aiApiVersion, setAiApiVersion] = useState("");
   const [aiApiKey, setAiApiKey] = useState("");
   const [aiBaseUrl, setAiBaseUrl] = useState("https://ark.cn-beijing.volces.com/api/v3");
   const [aiModelId, setAiModelId] = useState("doubao-pro");
@@ -21,6 +52,8 @@ export default function AISettings() {
   useEffect(() => {
     loadAiConfig().then((config) => {
       if (config) {
+        setAiProvider(config.provider || "openai");
+        setAiApiVersion(config.api_version || "");
         setAiApiKey(config.api_key);
         setAiBaseUrl(config.base_url);
         setAiModelId(config.model_id);
@@ -32,7 +65,7 @@ export default function AISettings() {
   }, []);
 
   const handleTestAiConnection = async () => {
-    if (!aiApiKey || !aiBaseUrl || !aiModelId) {
+    if ((!aiApiKey && aiProvider !== "ollama") || !aiBaseUrl || !aiModelId) {
       setAiTestResult({
         success: false,
         message: "请先填写完整的 AI 配置信息",
@@ -46,9 +79,11 @@ export default function AISettings() {
 
     try {
       await setAiConfig({
+        provider: aiProvider,
         api_key: aiApiKey,
         base_url: aiBaseUrl,
         model_id: aiModelId,
+        api_version: aiProvider === "azure" ? aiApiVersion : undefined,
         request_timeout: aiRequestTimeout,
       });
 
@@ -65,8 +100,19 @@ export default function AISettings() {
     }
   };
 
+  // 清空 AI 响应缓存（优化、审核和风险检测会复用 24 小时内相同请求的结果）
+  const handleClearCache = async () => {
+    setAiTestResult(null);
+    try {
+      const removed = await clearAiCache();
+      setAiMessage(`清除缓存成功，共 ${removed} 条`);
+    } catch (error) {
+      setAiMessage(errorMessage(error) || "清除缓存失败");
+    }
+  };
+
   const handleSaveAiConfig = async () => {
-    if (!aiApiKey || !aiBaseUrl || !aiModelId) {
+    if ((!aiApiKey && aiProvider !== "ollama") || !aiBaseUrl || !aiModelId) {
       setAiMessage("请填写完整的 AI 配置信息");
       setAiTestResult(null);
       return;
@@ -84,9 +130,11 @@ export default function AISettings() {
 
     try {
       await setAiConfig({
+        provider: aiProvider,
         api_key: aiApiKey,
         base_url: aiBaseUrl,
         model_id: aiModelId,
+        api_version: aiProvider === "azure" ? aiApiVersion : undefined,
         request_timeout: aiRequestTimeout,
       });
       
@@ -106,7 +154,7 @@ export default function AISettings() {
           AI 设置
         </CardTitle>
         <CardDescription>
-          配置火山方舟 Ark AI 参数
+          配置 AI 接口参数
           <br />
           <span className="text-xs text-amber-600 mt-1 block">
             注意：当前使用 localStorage 存储配置，Phase 2 将迁移至 Tauri secure storage
@@ -116,11 +164,31 @@ export default function AISettings() {
       <CardContent className="space-y-6">
         <div>
           <label className="text-sm font-medium mb-2 block">
-            ARK_API_KEY <span className="text-red-500">*</span>
+            接口类型 <span className="text-red-500">*</span>
+          </label>
+          <Select
+            value={aiProvider}
+            onChange={(e) => {
+              const provider = e.target.value as AiProvider;
+              setAiProvider(provider);
+              setAiBaseUrl(PROVIDERS[provider].baseUrl);
+            }}
+          >
+            {(Object.keys(PROVIDERS) as AiProvider[]).map((provider) => (
+              <option key={provider} value={provider}>
+                {PROVIDERS[provider].label}
+              </option>
+            ))}
+          </Select>
+        </div>
+
+        <div>
+          <label className="text-sm font-medium mb-2 block">
+            API Key {aiProvider !== "ollama" && <span className="text-red-500">*</span>}
           </label>
           <Input
             type="password"
-            placeholder="请输入火山方舟 Ark API Key"
+            placeholder={aiProvider === "ollama" ? "本地 Ollama 无需填写" : "请输入 API Key"}
             value={aiApiKey}
             onChange={(e) => setAiApiKey(e.target.value)}
           />
@@ -131,11 +199,11 @@ export default function AISettings() {
 
         <div>
           <label className="text-sm font-medium mb-2 block">
-            ARK_BASE_URL <span className="text-red-500">*</span>
+            API 地址 <span className="text-red-500">*</span>
           </label>
           <Input
             type="text"
-            placeholder="例如：https://ark.cn-beijing.volces.com/api/v3"
+            placeholder={`例如：${PROVIDERS[aiProvider].baseUrl}`}
             value={aiBaseUrl}
             onChange={(e) => setAiBaseUrl(e.target.value)}
           />
@@ -143,16 +211,28 @@ export default function AISettings() {
 
         <div>
           <label className="text-sm font-medium mb-2 block">
-            ARK_MODEL_ID <span className="text-red-500">*</span>
+            {PROVIDERS[aiProvider].modelLabel} <span className="text-red-500">*</span>
           </label>
           <Input
             type="text"
-            placeholder="例如：doubao-pro"
+            placeholder={PROVIDERS[aiProvider].modelPlaceholder}
             value={aiModelId}
             onChange={(e) => setAiModelId(e.target.value)}
           />
         </div>
 
+        {aiProvider === "azure" && (
+          <div>
+            <label className="text-sm font-medium mb-2 block">API 版本</label>
+            <Input
+              type="text"
+              placeholder="默认：2024-06-01"
+              value={aiApiVersion}
+              onChange={(e) => setAiApiVersion(e.target.value)}
+            />
+          </div>
+        )}
+
         <div>
           <label className="text-sm font-medium mb-2 block">
             AI_REQUEST_TIMEOUT（秒）
@@ -233,6 +313,15 @@ export default function AISettings() {
               </>
             )}
           </Button>
+          <Button
+            onClick={handleClearCache}
+            disabled={aiTesting || aiLoading}
+            variant="outline"
+            title="优化、审核和风险检测会复用 24 小时内相同请求的结果"
+          >
+            <Trash2 className="w-4 h-4 mr-2" />
+            清除响应缓存
+          </Button>
           <Button
             onClick={handleSaveAiConfig}
             disabled={aiLoading || aiTesting}
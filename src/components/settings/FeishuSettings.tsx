@@ -1,8 +1,9 @@
 import { useState, useEffect } from "react";
-import { saveFeishuConfig, loadFeishuConfig, testConnection, TableConfig } from "../../lib/api";
+import { saveFeishuConfig, loadFeishuConfig, setFeishuCredentials, testConnection, TableConfig, FeishuEndpoint, getFeishuEndpoint, setFeishuEndpoint } from "../../lib/api";
 import { extractBitableInfo } from "../../lib/utils";
 import { Button } from "../ui/button";
 import { Input } from "../ui/input";
+import { Select } from "../ui/select";
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "../ui/card";
 import { TestTube2, CheckCircle2, XCircle, Loader2, Link2, Cloud } from "lucide-react";
 
@@ -25,6 +26,9 @@ interface TableLinkState {
 export default function FeishuSettings() {
   const [appId, setAppId] = useState("");
   const [appSecret, setAppSecret] = useState("");
+  const [credentialsSaved, setCredentialsSaved] = useState(false);
+  const [endpointPreset, setEndpointPreset] = useState<FeishuEndpoint["preset"]>("feishu");
+  const [customBaseUrl, setCustomBaseUrl] = useState("");
   const [appToken, setAppToken] = useState("");
   const [appTokenLink, setAppTokenLink] = useState("");
   const [tableLinks, setTableLinks] = useState<Record<string, TableLinkState>>(() => {
@@ -47,12 +51,29 @@ export default function FeishuSettings() {
     message: string;
   } | null>(null);
 
+  // 开放平台地址保存在后端
+  useEffect(() => {
+    getFeishuEndpoint()
+      .then((endpoint) => {
+        setEndpointPreset(endpoint.preset);
+        if (endpoint.preset === "custom") {
+          setCustomBaseUrl(endpoint.base_url);
+        }
+      })
+      .catch((error) => console.warn("加载开放平台地址失败（本地模式下可忽略）:", error));
+  }, []);
+
+  const currentEndpoint = (): FeishuEndpoint =>
+    endpointPreset === "custom"
+      ? { preset: "custom", base_url: customBaseUrl }
+      : { preset: endpointPreset };
+
   // 从本地存储加载已保存的配置
   useEffect(() => {
     const savedConfig = loadFeishuConfig();
     if (savedConfig) {
       setAppId(savedConfig.appId || "");
-      setAppSecret(savedConfig.appSecret || "");
+      setCredentialsSaved(!!savedConfig.configured);
       
       if (savedConfig.appToken) {
         setAppToken(savedConfig.appToken);
@@ -158,7 +179,7 @@ export default function FeishuSettings() {
     setMessage("");
 
     try {
-      const result = await testConnection(appId, appSecret);
+      const result = await testConnection(appId, appSecret, currentEndpoint());
       setTestResult(result);
       if (result.success) {
         setMessage("连接测试成功！");
@@ -177,8 +198,9 @@ export default function FeishuSettings() {
   };
 
   const handleSave = async () => {
-    // 检查是否为本地模式（只有 appToken 和 tableId，没有 appId 和 appSecret）
-    const isLocalMode = !appId || !appSecret;
+    // 检查是否为本地模式（只有 appToken 和 tableId，没有飞书凭证）。
+    // 后端已保存凭证时 App Secret 可留空，表示保持不变
+    const isLocalMode = !appId || (!appSecret && !credentialsSaved);
 
     if (isLocalMode) {
       // 本地模式：只需要 appToken 和至少一个 tableId
@@ -225,7 +247,6 @@ export default function FeishuSettings() {
 
         await saveFeishuConfig({
           appId: "", // 本地模式下为空
-          appSecret: "", // 本地模式下为空
           appToken: appToken,
           tableId: firstTable.tableId,
           tables,
@@ -240,7 +261,13 @@ export default function FeishuSettings() {
       return;
     }
 
-    // 完整模式：需要所有配置
+    // 完整模式：需要所有配置。更换 App ID 时必须重新填写 App Secret
+    if (!appSecret && appId !== loadFeishuConfig()?.appId) {
+      setMessage("更换 App ID 时请重新填写 App Secret");
+      setTestResult(null);
+      return;
+    }
+
     if (!appToken) {
       setMessage("请配置 BITABLE_APP_TOKEN（应用级别，所有表格共享）");
       setTestResult(null);
@@ -282,13 +309,22 @@ export default function FeishuSettings() {
 
       const firstTable = tables[0];
 
+      if (appSecret) {
+        await setFeishuCredentials(appId, appSecret);
+        setCredentialsSaved(true);
+        setAppSecret("");
+      }
       await saveFeishuConfig({
         appId,
-        appSecret,
         appToken: appToken,
         tableId: firstTable.tableId,
         tables,
       });
+
+      const savedEndpoint = await setFeishuEndpoint(currentEndpoint());
+      if (savedEndpoint.preset === "custom") {
+        setCustomBaseUrl(savedEndpoint.base_url);
+      }
       
       setMessage("配置保存成功！");
     } catch (error: any) {
@@ -318,6 +354,30 @@ export default function FeishuSettings() {
         </CardDescription>
       </CardHeader>
       <CardContent className="space-y-6">
+        {/* 开放平台地址 */}
+        <div>
+          <label className="text-sm font-medium mb-2 block">
+            开放平台地址
+          </label>
+          <Select
+            value={endpointPreset}
+            onChange={(e) => setEndpointPreset(e.target.value as FeishuEndpoint["preset"])}
+          >
+            <option value="feishu">飞书（open.feishu.cn）</option>
+            <option value="lark">Lark 国际版（open.larksuite.com）</option>
+            <option value="custom">私有化部署（自定义地址）</option>
+          </Select>
+          {endpointPreset === "custom" && (
+            <Input
+              className="mt-2"
+              type="text"
+              placeholder="例如：https://open.example.com/open-apis"
+              value={customBaseUrl}
+              onChange={(e) => setCustomBaseUrl(e.target.value)}
+            />
+          )}
+        </div>
+
         {/* FEISHU_APP_ID */}
         <div>
           <label className="text-sm font-medium mb-2 block">
@@ -343,11 +403,15 @@ export default function FeishuSettings() {
           </label>
           <Input
             type="password"
-            placeholder="请输入飞书应用 App Secret（留空则使用本地模式）"
+            placeholder={
+              credentialsSaved
+                ? "已保存在本地加密存储中（留空则保持不变）"
+                : "请输入飞书应用 App Secret（留空则使用本地模式）"
+            }
             value={appSecret}
             onChange={(e) => setAppSecret(e.target.value)}
           />
-          {!appSecret && (
+          {!appSecret && !credentialsSaved && (
             <p className="text-xs text-blue-600 mt-1">
               本地模式：不填写 App ID 和 App Secret 时，只能使用缓存数据，无法同步最新数据
             </p>
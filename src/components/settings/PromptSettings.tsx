@@ -0,0 +1,160 @@
+import { useState, useEffect } from "react";
+import { useAuth } from "../../contexts/AuthContext";
+import {
+  listPromptTemplates,
+  savePromptTemplate,
+  resetPromptTemplate,
+  errorMessage,
+  PromptKind,
+  PromptTemplate,
+} from "../../lib/api";
+import { Button } from "../ui/button";
+import { Textarea } from "../ui/textarea";
+import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "../ui/card";
+import { Tabs, TabsList, TabsTrigger, TabsContent } from "../ui/tabs";
+import { FileText, Loader2, RotateCcw } from "lucide-react";
+
+// 各模板的名称和可用占位符（必需的占位符标 *）
+const KINDS: { kind: PromptKind; label: string; placeholders: string }[] = [
+  { kind: "optimize", label: "AI 优化", placeholders: "{original_length}、{max_length}*、{context}、{answer}*" },
+  { kind: "review", label: "AI 审核", placeholders: "{context}、{answer}*" },
+  { kind: "risk", label: "风险检测", placeholders: "{answer}*" },
+  { kind: "compress", label: "字数压缩", placeholders: "{reply_length}、{max_length}*、{context}、{reply}*" },
+];
+
+export default function PromptSettings() {
+  const { currentUser } = useAuth();
+  const [templates, setTemplates] = useState<Partial<Record<PromptKind, PromptTemplate>>>({});
+  const [drafts, setDrafts] = useState<Partial<Record<PromptKind, string>>>({});
+  const [activeKind, setActiveKind] = useState<PromptKind>("optimize");
+  const [saving, setSaving] = useState(false);
+  const [message, setMessage] = useState<{ success: boolean; text: string } | null>(null);
+
+  const applyTemplate = (template: PromptTemplate) => {
+    setTemplates((prev) => ({ ...prev, [template.kind]: template }));
+    setDrafts((prev) => ({ ...prev, [template.kind]: template.body }));
+  };
+
+  useEffect(() => {
+    listPromptTemplates()
+      .then((list) => list.forEach(applyTemplate))
+      .catch((error) => {
+        console.error("加载提示词模板失败:", error);
+      });
+  }, []);
+
+  const handleSave = async (kind: PromptKind) => {
+    setSaving(true);
+    setMessage(null);
+    try {
+      const template = await savePromptTemplate(kind, drafts[kind] ?? "", currentUser?.username);
+      applyTemplate(template);
+      setMessage({ success: true, text: `已保存为版本 ${template.version}` });
+    } catch (error) {
+      setMessage({ success: false, text: errorMessage(error) || "保存失败" });
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  const handleReset = async (kind: PromptKind) => {
+    setSaving(true);
+    setMessage(null);
+    try {
+      const template = await resetPromptTemplate(kind, currentUser?.username);
+      applyTemplate(template);
+      setMessage({ success: true, text: "已恢复默认模板" });
+    } catch (error) {
+      setMessage({ success: false, text: errorMessage(error) || "恢复失败" });
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle className="flex items-center gap-2">
+          <FileText className="w-5 h-5" />
+          提示词模板
+        </CardTitle>
+        <CardDescription>
+          修改 AI 优化、审核、风险检测和字数压缩使用的提示词，保存后立即生效。占位符写作 {"{name}"}，字面量花括号写作 {"{{ }}"}
+        </CardDescription>
+      </CardHeader>
+      <CardContent>
+        <Tabs
+          value={activeKind}
+          onValueChange={(value) => {
+            setActiveKind(value as PromptKind);
+            setMessage(null);
+          }}
+        >
+          <TabsList className="grid w-full grid-cols-4">
+            {KINDS.map(({ kind, label }) => (
+              <TabsTrigger key={kind} value={kind}>
+                {label}
+              </TabsTrigger>
+            ))}
+          </TabsList>
+
+          {KINDS.map(({ kind, placeholders }) => {
+            const template = templates[kind];
+            const changed = template !== undefined && drafts[kind] !== template.body;
+            return (
+              <TabsContent key={kind} value={kind} className="mt-4 space-y-4">
+                <div className="flex items-center justify-between text-xs text-gray-500">
+                  <span>可用占位符：{placeholders}</span>
+                  {template && (
+                    <span>
+                      {template.version === 0 ? "内置模板" : `版本 ${template.version}`}
+                      {template.version > 0 && template.is_default && "（默认内容）"}
+                      {template.author && ` · ${template.author}`}
+                    </span>
+                  )}
+                </div>
+                <Textarea
+                  className="min-h-[360px] font-mono text-xs"
+                  value={drafts[kind] ?? ""}
+                  onChange={(e) => setDrafts((prev) => ({ ...prev, [kind]: e.target.value }))}
+                />
+
+                {message && (
+                  <div
+                    className={`p-3 rounded-md text-sm ${
+                      message.success ? "bg-green-50 text-green-800" : "bg-red-50 text-red-800"
+                    }`}
+                  >
+                    {message.text}
+                  </div>
+                )}
+
+                <div className="flex gap-3">
+                  <Button
+                    variant="outline"
+                    onClick={() => handleReset(kind)}
+                    disabled={saving || !template || template.is_default}
+                    className="flex-1"
+                  >
+                    <RotateCcw className="w-4 h-4 mr-2" />
+                    恢复默认
+                  </Button>
+                  <Button onClick={() => handleSave(kind)} disabled={saving || !changed} className="flex-1">
+                    {saving ? (
+                      <>
+                        <Loader2 className="w-4 h-4 mr-2 animate-spin" />
+                        保存中...
+                      </>
+                    ) : (
+                      "保存模板"
+                    )}
+                  </Button>
+                </div>
+              </TabsContent>
+            );
+          })}
+        </Tabs>
+      </CardContent>
+    </Card>
+  );
+}
@@ -1,5 +1,5 @@
 import { useState, useEffect } from "react";
-import { loadFeishuConfig, getBitableTables, saveFeishuConfig, BitableTable, TableConfig } from "../../lib/api";
+import { loadFeishuConfig, getBitableTables, saveFeishuConfig, isLocalMode, BitableTable, TableConfig } from "../../lib/api";
 import { Button } from "../ui/button";
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "../ui/card";
 import { Loader2, RefreshCw, CheckCircle2, Database } from "lucide-react";
@@ -71,7 +71,7 @@ export default function TableSettings() {
     }
 
     const config = loadFeishuConfig();
-    if (!config || !config.appToken || !config.appId || !config.appSecret) {
+    if (!config || !config.appToken || isLocalMode(config)) {
       setMessage("请先在飞书设置中配置飞书凭证和 BITABLE_APP_TOKEN");
       return;
     }
@@ -101,8 +101,7 @@ export default function TableSettings() {
       }
 
       await saveFeishuConfig({
-        appId: config.appId,
-        appSecret: config.appSecret,
+        appId: config.appId!,
         appToken: config.appToken,
         tableId: selectedTableId,
         tables: updatedTables,
@@ -0,0 +1,167 @@
+import { useState, useEffect } from "react";
+import {
+  getAiUsage,
+  getModelPrices,
+  setModelPrice,
+  errorMessage,
+  ModelPrice,
+  UsageSummary,
+} from "../../lib/api";
+import { Button } from "../ui/button";
+import { Input } from "../ui/input";
+import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "../ui/card";
+import { BarChart3, Loader2, Trash2 } from "lucide-react";
+
+// 可选的统计范围（天），0 表示全部
+const RANGES = [
+  { days: 7, label: "近 7 天" },
+  { days: 30, label: "近 30 天" },
+  { days: 0, label: "全部" },
+];
+
+const formatCost = (cost?: number | null) => (cost == null ? "未定价" : cost.toFixed(4));
+
+// AI 用量统计：按日期、用户和模型汇总 token 和费用，并维护模型价格
+export default function UsageSettings() {
+  const [days, setDays] = useState(7);
+  const [summary, setSummary] = useState<UsageSummary[]>([]);
+  const [prices, setPrices] = useState<ModelPrice[]>([]);
+  const [draft, setDraft] = useState({ model: "", prompt: "", completion: "" });
+  const [saving, setSaving] = useState(false);
+  const [message, setMessage] = useState("");
+
+  useEffect(() => {
+    const since = days > 0 ? Math.floor(Date.now() / 1000) - days * 24 * 60 * 60 : undefined;
+    getAiUsage(since)
+      .then(setSummary)
+      .catch((error) => console.error("加载 AI 用量失败:", error));
+  }, [days, prices]);
+
+  useEffect(() => {
+    getModelPrices()
+      .then(setPrices)
+      .catch((error) => console.error("加载模型价格失败:", error));
+  }, []);
+
+  const updatePrice = async (model: string, promptPrice?: number, completionPrice?: number) => {
+    setSaving(true);
+    setMessage("");
+    try {
+      setPrices(await setModelPrice(model, promptPrice, completionPrice));
+      return true;
+    } catch (error) {
+      setMessage(errorMessage(error) || "保存价格失败");
+      return false;
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  const handleSave = async () => {
+    const saved = await updatePrice(draft.model.trim(), Number(draft.prompt), Number(draft.completion));
+    if (saved) {
+      setDraft({ model: "", prompt: "", completion: "" });
+    }
+  };
+
+  const totalCost = summary.reduce((sum, row) => sum + (row.cost ?? 0), 0);
+
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle className="flex items-center gap-2">
+          <BarChart3 className="w-5 h-5" />
+          AI 用量
+        </CardTitle>
+        <CardDescription>按日期、用户和模型统计 token 用量，配置模型价格（每百万 token）后估算费用</CardDescription>
+      </CardHeader>
+      <CardContent className="space-y-6">
+        <div className="flex items-center justify-between">
+          <div className="flex gap-2">
+            {RANGES.map((range) => (
+              <Button
+                key={range.days}
+                size="sm"
+                variant={days === range.days ? "default" : "outline"}
+                onClick={() => setDays(range.days)}
+              >
+                {range.label}
+              </Button>
+            ))}
+          </div>
+          <span className="text-sm text-gray-600">已定价费用合计 {totalCost.toFixed(4)}</span>
+        </div>
+
+        {summary.length === 0 ? (
+          <div className="text-sm text-gray-500">暂无用量记录</div>
+        ) : (
+          <table className="w-full text-sm">
+            <thead>
+              <tr className="text-left text-gray-500 border-b">
+                <th className="py-2">日期</th>
+                <th>用户</th>
+                <th>模型</th>
+                <th className="text-right">调用</th>
+                <th className="text-right">输入 token</th>
+                <th className="text-right">输出 token</th>
+                <th className="text-right">费用</th>
+              </tr>
+            </thead>
+            <tbody>
+              {summary.map((row) => (
+                <tr key={`${row.day}-${row.user}-${row.model}`} className="border-b last:border-0">
+                  <td className="py-2">{row.day}</td>
+                  <td>{row.user || "-"}</td>
+                  <td>{row.model}</td>
+                  <td className="text-right">{row.calls}</td>
+                  <td className="text-right">{row.prompt_tokens}</td>
+                  <td className="text-right">{row.completion_tokens}</td>
+                  <td className="text-right">{formatCost(row.cost)}</td>
+                </tr>
+              ))}
+            </tbody>
+          </table>
+        )}
+
+        <div className="space-y-3">
+          <div className="text-sm font-medium text-gray-700">模型价格</div>
+          {prices.map((price) => (
+            <div key={price.model} className="flex items-center justify-between text-sm">
+              <span>
+                {price.model}：输入 {price.prompt_price} / 输出 {price.completion_price}
+              </span>
+              <Button size="sm" variant="outline" onClick={() => updatePrice(price.model)} disabled={saving}>
+                <Trash2 className="w-4 h-4" />
+              </Button>
+            </div>
+          ))}
+          <div className="flex gap-2">
+            <Input
+              placeholder="模型名称"
+              value={draft.model}
+              onChange={(e) => setDraft({ ...draft, model: e.target.value })}
+            />
+            <Input
+              type="number"
+              min="0"
+              placeholder="输入价格"
+              value={draft.prompt}
+              onChange={(e) => setDraft({ ...draft, prompt: e.target.value })}
+            />
+            <Input
+              type="number"
+              min="0"
+              placeholder="输出价格"
+              value={draft.completion}
+              onChange={(e) => setDraft({ ...draft, completion: e.target.value })}
+            />
+            <Button onClick={handleSave} disabled={saving || !draft.model.trim()}>
+              {saving ? <Loader2 className="w-4 h-4 animate-spin" /> : "保存"}
+            </Button>
+          </div>
+          {message && <div className="p-3 rounded-md text-sm bg-red-50 text-red-800">{message}</div>}
+        </div>
+      </CardContent>
+    </Card>
+  );
+}
@@ -1,4 +1,66 @@
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+
+// 后端命令统一返回的错误（kind 为稳定的错误分类）
+export interface AppError {
+  kind:
+    | "network"
+    | "timeout"
+    | "not_configured"
+    | "invalid_app_id"
+    | "invalid_app_secret"
+    | "token_invalid"
+    | "rate_limited"
+    | "feishu"
+    | "http"
+    | "parse"
+    | "invalid_input"
+    | "storage"
+    | "ai"
+    | "conflict"
+    | "cancelled"
+    | "internal";
+  message: string;
+  code?: number;
+  status?: number;
+  retryable: boolean;
+  retries?: number; // 失败前已自动重试的次数
+  conflict?: UpdateConflict; // kind 为 conflict 时的详细信息
+}
+
+// 单条记录、表格列表等命令的结果，附带请求自动重试的次数
+export interface Retried<T> {
+  value: T;
+  retries: number;
+}
+
+// 调用返回 Retried 的命令，只取结果
+async function invokeValue<T>(command: string, args: Record<string, unknown>): Promise<T> {
+  const result = await invoke<Retried<T>>(command, args);
+  return result.value;
+}
+
+// 写回时记录已被他人修改
+export interface UpdateConflict {
+  record_id: string;
+  expected_modified_time: number | null; // 恢复修订时为空
+  current_modified_time: number | null;
+  fields: FieldDiff[]; // 本次要写入、且当前值与写入值不同的字段
+}
+
+export interface FieldDiff {
+  field: string;
+  current: any | null; // 飞书中的当前值
+  incoming: any; // 本次要写入的值
+}
+
+// 从 invoke 抛出的错误中取出可展示的信息
+export function errorMessage(error: any): string {
+  if (error && typeof error === "object" && typeof error.message === "string") {
+    return error.message;
+  }
+  return error ? String(error) : "";
+}
 
 export interface FeishuCredentials {
   app_id: string;
@@ -13,7 +75,7 @@ export interface TableConfig {
 
 export interface FeishuConfig {
   appId: string;
-  appSecret: string;
+  configured: boolean; // 后端是否已保存飞书凭证（App Secret 只保存在后端加密存储中）
   appToken: string; // 主要的 App Token（用于兼容旧版本）
   tableId: string; // 主要的 Table ID（用于兼容旧版本）
   tables: TableConfig[]; // 多个表格配置
@@ -29,21 +91,48 @@ export interface AnswerRecord {
   fields: Record<string, any>;
 }
 
+// 后端已保存飞书凭证的标记（不含任何凭证内容），供同步判断是否为本地模式
+const CREDENTIALS_FLAG_KEY = "FEISHU_CREDENTIALS_CONFIGURED";
+
+function setCredentialsFlag(configured: boolean) {
+  if (configured) {
+    localStorage.setItem(CREDENTIALS_FLAG_KEY, "1");
+  } else {
+    localStorage.removeItem(CREDENTIALS_FLAG_KEY);
+  }
+}
+
 // 设置飞书凭证（保存到后端）
 export async function setFeishuCredentials(
   appId: string,
   appSecret: string
 ): Promise<string> {
-  return await invoke("set_feishu_credentials", { appId, appSecret });
+  const result = await invoke<string>("set_feishu_credentials", { appId, appSecret });
+  setCredentialsFlag(true);
+  return result;
 }
 
-// 保存飞书完整配置到本地
-// TODO: Phase 2 将替换为 Tauri secure storage
-export async function saveFeishuConfig(config: FeishuConfig): Promise<void> {
-  // Phase 1: 使用 localStorage（临时方案）
-  // Phase 2: 将替换为 Tauri secure storage 以确保安全性
+// 查询后端是否已保存飞书凭证，并刷新本地标记
+export async function hasFeishuCredentials(): Promise<boolean> {
+  const configured = await invoke<boolean>("has_feishu_credentials");
+  setCredentialsFlag(configured);
+  return configured;
+}
+
+// 启动时已保存的凭证无法读取（如机器标识变化或文件损坏），旧文件已移到 backup
+export interface UnreadableSecrets {
+  reason: string;
+  backup: string;
+}
+
+// 已保存的凭证是否无法读取，无法读取时需要重新填写飞书凭证和 AI 配置
+export async function getSecretStoreStatus(): Promise<UnreadableSecrets | null> {
+  return await invoke("get_secret_store_status");
+}
+
+// 保存飞书表格配置到本地（凭证通过 setFeishuCredentials 保存到后端）
+export async function saveFeishuConfig(config: Omit<FeishuConfig, "configured">): Promise<void> {
   localStorage.setItem("FEISHU_APP_ID", config.appId || "");
-  localStorage.setItem("FEISHU_APP_SECRET", config.appSecret || "");
   localStorage.setItem("BITABLE_APP_TOKEN", config.appToken);
   localStorage.setItem("ANSWERS_TABLE_ID", config.tableId);
   
@@ -58,32 +147,41 @@ export async function saveFeishuConfig(config: FeishuConfig): Promise<void> {
       localStorage.setItem("ANSWERS_TABLE_ID", firstTable.tableId);
     }
   }
-  
-  // 只有在有完整配置时才保存到后端（用于后端 API 调用）
-  // 本地模式下不需要后端凭证
-  if (config.appId && config.appSecret) {
-    try {
-      await setFeishuCredentials(config.appId, config.appSecret);
-    } catch (error) {
-      console.warn("保存后端凭证失败（本地模式下可忽略）:", error);
-      // 本地模式下忽略后端错误，不影响配置保存
+}
+
+// 旧版本保存在 localStorage 中的明文凭证
+const LEGACY_SECRET_KEYS = ["FEISHU_APP_SECRET", "ARK_API_KEY", "ARK_BASE_URL", "ARK_MODEL_ID"];
+
+// 一次性迁移：把 localStorage 中的明文凭证导入后端加密存储，成功后清除；
+// 随后从后端刷新凭证标记
+export async function migrateLegacySecrets(): Promise<void> {
+  try {
+    if (LEGACY_SECRET_KEYS.some((key) => localStorage.getItem(key) !== null)) {
+      await invoke("migrate_legacy_secrets", {
+        appId: localStorage.getItem("FEISHU_APP_ID"),
+        appSecret: localStorage.getItem("FEISHU_APP_SECRET"),
+        aiApiKey: localStorage.getItem("ARK_API_KEY"),
+        aiApiBase: localStorage.getItem("ARK_BASE_URL"),
+        aiModel: localStorage.getItem("ARK_MODEL_ID"),
+      });
+      LEGACY_SECRET_KEYS.forEach((key) => localStorage.removeItem(key));
     }
+    await hasFeishuCredentials();
+  } catch (error) {
+    console.error("迁移本地凭证失败:", error);
   }
 }
 
-// 从本地加载飞书配置
-// TODO: Phase 2 将从 Tauri secure storage 读取
+// 从本地加载飞书配置（不含 App Secret，configured 表示后端是否已保存凭证）
 export function loadFeishuConfig(): Partial<FeishuConfig> | null {
-  // Phase 1: 从 localStorage 读取（临时方案）
-  // Phase 2: 将从 Tauri secure storage 读取
   const appId = localStorage.getItem("FEISHU_APP_ID");
-  const appSecret = localStorage.getItem("FEISHU_APP_SECRET");
+  const configured = localStorage.getItem(CREDENTIALS_FLAG_KEY) === "1";
   const appToken = localStorage.getItem("BITABLE_APP_TOKEN");
   const tableId = localStorage.getItem("ANSWERS_TABLE_ID");
 
-  // 支持本地模式：即使没有 appId 和 appSecret，只要有 appToken 和 tableId 也可以使用
+  // 支持本地模式：即使没有飞书凭证，只要有 appToken 和 tableId 也可以使用
   // 这种情况下只能使用缓存数据，无法同步
-  const hasFullConfig = appId && appSecret;
+  const hasFullConfig = appId && configured;
   const hasLocalConfig = appToken && tableId;
 
   if (!hasFullConfig && !hasLocalConfig) {
@@ -108,22 +206,39 @@ export function loadFeishuConfig(): Partial<FeishuConfig> | null {
 
   return {
     appId: appId || "",
-    appSecret: appSecret || "",
+    configured,
     appToken: appToken || "",
     tableId: tableId || "",
     tables,
   };
 }
 
-// 测试飞书连接（通过后端）
+// 开放平台地址：飞书（国内）、Lark（海外）或私有化部署的自定义地址
+export type FeishuEndpoint =
+  | { preset: "feishu" }
+  | { preset: "lark" }
+  | { preset: "custom"; base_url: string };
+
+export async function getFeishuEndpoint(): Promise<FeishuEndpoint> {
+  return await invoke("get_feishu_endpoint");
+}
+
+// 保存开放平台地址，返回规范化后的配置（自定义地址会补全 /open-apis）
+export async function setFeishuEndpoint(endpoint: FeishuEndpoint): Promise<FeishuEndpoint> {
+  return await invoke("set_feishu_endpoint", { endpoint });
+}
+
+// 测试飞书连接（通过后端），传入 endpoint 时测试该地址，否则使用已保存的地址
 export async function testConnection(
   appId: string,
-  appSecret: string
+  appSecret: string,
+  endpoint?: FeishuEndpoint
 ): Promise<{ success: boolean; message: string; token?: string }> {
   try {
     const result = await invoke<string>("test_feishu_connection", {
       appId,
       appSecret,
+      endpoint,
     });
     return {
       success: true,
@@ -132,11 +247,16 @@ export async function testConnection(
   } catch (error: any) {
     return {
       success: false,
-      message: error?.toString() || "连接测试失败",
+      message: errorMessage(error) || "连接测试失败",
     };
   }
 }
 
+// 没有飞书凭证时为本地模式，只能使用缓存数据
+export function isLocalMode(config: Partial<FeishuConfig> | null): boolean {
+  return !config || !config.appId || !config.configured;
+}
+
 // 获取飞书 access token（从后端缓存获取）
 export async function getFeishuAccessToken(): Promise<string> {
   return await invoke("get_feishu_access_token");
@@ -144,7 +264,7 @@ export async function getFeishuAccessToken(): Promise<string> {
 
 // 获取 Bitable 表格列表
 export async function getBitableTables(appToken: string): Promise<BitableTable[]> {
-  return await invoke("get_bitable_tables", { appToken });
+  return await invokeValue<BitableTable[]>("get_bitable_tables", { appToken });
 }
 
 // 获取 Answers 表数据
@@ -152,7 +272,7 @@ export async function getAnswersData(
   appToken: string,
   tableId: string
 ): Promise<AnswerRecord[]> {
-  return await invoke("get_answers_data", { appToken, tableId });
+  return await invokeValue<AnswerRecord[]>("get_answers_data", { appToken, tableId });
 }
 
 // Answers 表的结构化数据
@@ -163,9 +283,30 @@ export interface Answer {
   enable_status: string;
   scene: string;
   tone: string;
-  product_name: string;
+  product_name: string; // 多个产品用"、"连接
+  product_names: string[]; // 对应的所有产品
   product_id: string;
   raw_fields?: Record<string, any>; // 原始字段数据（用于调试）
+  last_modified_time?: number; // 最后修改时间（毫秒），写回时用于冲突检测
+}
+
+// 答案列表及数据来源（后端 SQLite 缓存）
+export interface AnswersResult {
+  answers: Answer[];
+  from_cache: boolean; // 是否来自本地缓存
+  synced_at: number | null; // 缓存最后同步时间（秒）
+  cache_age_secs: number | null; // 缓存距今秒数
+  fallback_reason: AppError | null; // 在线拉取失败回退到缓存的原因
+  retries: number; // 请求自动重试的次数
+}
+
+// 获取答案列表及缓存信息，offline 为 true 时只读取本地缓存
+export async function listAnswersWithMeta(
+  appToken: string,
+  tableId: string,
+  offline?: boolean
+): Promise<AnswersResult> {
+  return await invoke("list_answers", { appToken, tableId, offline });
 }
 
 // 获取答案列表（结构化数据）
@@ -173,7 +314,102 @@ export async function listAnswers(
   appToken: string,
   tableId: string
 ): Promise<Answer[]> {
-  return await invoke("list_answers", { appToken, tableId });
+  const result = await listAnswersWithMeta(appToken, tableId);
+  return result.answers;
+}
+
+// 增量同步结果
+export interface SyncSummary {
+  full_sync: boolean;
+  added: string[];
+  updated: string[];
+  removed: string[];
+  watermark: number | null;
+  synced_at: number;
+  retries: number; // 请求自动重试的次数
+}
+
+// 增量同步表格到后端本地缓存（首次同步为全量）
+export async function syncAnswers(
+  appToken: string,
+  tableId: string,
+  modifiedField?: string
+): Promise<SyncSummary> {
+  return await invoke("sync_answers", { appToken, tableId, modifiedField });
+}
+
+// 飞书请求的重试策略（指数退避 + 随机抖动）
+export interface RetryPolicy {
+  max_retries: number;
+  base_delay_ms: number;
+  max_delay_ms: number;
+}
+
+export async function getRetryPolicy(): Promise<RetryPolicy> {
+  return await invoke("get_retry_policy");
+}
+
+export async function setRetryPolicy(policy: RetryPolicy): Promise<RetryPolicy> {
+  return await invoke("set_retry_policy", { policy });
+}
+
+// 表结构中的字段（单选/多选字段附带选项）
+export interface TableField {
+  field_id: string;
+  field_name: string;
+  field_type: number; // 飞书字段类型，3 = 单选，4 = 多选
+  options?: { id: string | null; name: string }[];
+}
+
+// 获取表的字段列表，refresh 为 true 时跳过后端缓存
+export async function getTableSchema(
+  appToken: string,
+  tableId: string,
+  refresh?: boolean
+): Promise<TableField[]> {
+  return await invokeValue<TableField[]>("get_table_schema", { appToken, tableId, refresh });
+}
+
+// Answer 逻辑字段到飞书列名的映射（每个字段可配置多个别名）
+export interface FieldMapping {
+  question: string[];
+  standard_answer: string[];
+  enable_status: string[];
+  scene: string[];
+  tone: string[];
+  product_name: string[];
+  product_id: string[];
+}
+
+export interface TableFieldMapping {
+  mapping: FieldMapping;
+  columns: Record<string, string>; // 逻辑字段 -> 实际列名
+}
+
+export interface FieldMappingValidation {
+  valid: boolean;
+  columns: Record<string, string>;
+  unmatched: string[];
+  missing_required: string[];
+  empty_aliases: string[]; // 含有空白别名的逻辑字段
+  duplicate_columns: string[]; // 被多个逻辑字段匹配到的列
+}
+
+// 获取表格的字段映射（未配置时返回默认映射）
+export async function getFieldMapping(
+  appToken: string,
+  tableId: string
+): Promise<TableFieldMapping> {
+  return await invoke("get_field_mapping", { appToken, tableId });
+}
+
+// 保存表格的字段映射（后端会用表格实际字段校验）
+export async function setFieldMapping(
+  appToken: string,
+  tableId: string,
+  mapping: FieldMapping
+): Promise<FieldMappingValidation> {
+  return await invokeValue<FieldMappingValidation>("set_field_mapping", { appToken, tableId, mapping });
 }
 
 // 答案数据缓存接口
@@ -241,39 +477,56 @@ export function getCacheTimestamp(tableId: string): number | null {
   return cache ? cache.timestamp : null;
 }
 
+// AI 接口类型
+export type AiProvider = "openai" | "anthropic" | "azure" | "ollama";
+
 // AI 配置相关接口
 export interface AiConfig {
-  api_key: string;
+  provider: AiProvider;
+  api_key: string; // Ollama 可为空
   base_url: string;
-  model_id: string;
+  model_id: string; // Azure 为部署名
+  api_version?: string; // 仅 Azure
   request_timeout?: number;
 }
 
-// 设置 AI 配置
+// 设置 AI 配置（未传 provider 时沿用已保存的接口类型）
 export async function setAiConfig(config: {
+  provider?: AiProvider;
   api_key: string;
   base_url: string;
   model_id: string;
+  api_version?: string;
   request_timeout?: number;
 }): Promise<string> {
   return await invoke("set_ai_config", {
     apiKey: config.api_key,
     apiBase: config.base_url,
     model: config.model_id,
+    provider: config.provider,
+    apiVersion: config.api_version,
   });
 }
 
 // 获取 AI 配置
 export async function loadAiConfig(): Promise<AiConfig | null> {
   try {
-    const config = await invoke<{ api_key: string; api_base: string; model: string } | null>("get_ai_config");
+    const config = await invoke<{
+      provider: AiProvider;
+      api_key: string;
+      api_base: string;
+      model: string;
+      api_version?: string;
+    } | null>("get_ai_config");
     if (!config) {
       return null;
     }
     return {
+      provider: config.provider,
       api_key: config.api_key,
       base_url: config.api_base,
       model_id: config.model,
+      api_version: config.api_version,
       request_timeout: 30000, // 默认 30 秒
     };
   } catch (error) {
@@ -288,17 +541,58 @@ export async function testAiConnection(): Promise<{ success: boolean; message: s
     const result = await invoke<string>("test_ai_connection");
     return { success: true, message: result };
   } catch (error: any) {
-    return { success: false, message: error?.toString() || "连接失败" };
+    return { success: false, message: errorMessage(error) || "连接失败" };
   }
 }
 
+// 按日期、用户和模型汇总的 AI 用量，cost 为空表示未配置该模型价格
+export interface UsageSummary {
+  day: string;
+  user?: string | null;
+  model: string;
+  calls: number;
+  prompt_tokens: number;
+  completion_tokens: number;
+  cost?: number | null;
+}
+
+// 模型价格，按每百万 token 计
+export interface ModelPrice {
+  model: string;
+  prompt_price: number;
+  completion_price: number;
+}
+
+// AI 用量汇总，since / until 为秒级时间戳
+export async function getAiUsage(since?: number, until?: number): Promise<UsageSummary[]> {
+  return await invoke("get_ai_usage", { since, until });
+}
+
+export async function getModelPrices(): Promise<ModelPrice[]> {
+  return await invoke("get_model_prices");
+}
+
+// 设置模型价格，两项价格都不传时删除该模型的价格
+export async function setModelPrice(
+  model: string,
+  promptPrice?: number,
+  completionPrice?: number
+): Promise<ModelPrice[]> {
+  return await invoke("set_model_price", { model, promptPrice, completionPrice });
+}
+
+// 清空 AI 响应缓存，返回删除的条数
+export async function clearAiCache(): Promise<number> {
+  return await invoke("clear_ai_cache");
+}
+
 // 获取表格中的单条记录
 export async function getBitableRecord(
   appToken: string,
   tableId: string,
   recordId: string
 ): Promise<AnswerRecord> {
-  return await invoke("get_bitable_record", {
+  return await invokeValue<AnswerRecord>("get_bitable_record", {
     appToken,
     tableId,
     recordId,
@@ -310,60 +604,402 @@ export async function updateAnswerToFeishu(
   appToken: string,
   tableId: string,
   recordId: string,
-  fields: Record<string, any>
-): Promise<string> {
-  return await invoke("update_answer_to_feishu", {
+  fields: Record<string, any>,
+  expectedModifiedTime?: number, // 传入时，记录已被他人修改则拒绝写入（kind 为 conflict）
+  revision?: RevisionInfo // 保存到本地修订记录中的作者和 AI 操作
+): Promise<Answer> {
+  return await invokeValue<Answer>("update_answer_to_feishu", {
     appToken,
     tableId,
     recordId,
     fields,
+    expectedModifiedTime,
+    revision,
   });
 }
 
+export interface RevisionInfo {
+  author?: string;
+  ai_action?: string; // 如"AI优化"、"AI审核"、"人工调整"
+}
+
+// 写回飞书前保存的本地修订（字段为表格实际列名，old_fields 中为 null 表示写入前为空）
+export interface Revision {
+  id: number;
+  app_token: string;
+  table_id: string;
+  record_id: string;
+  old_fields: Record<string, any>;
+  new_fields: Record<string, any>;
+  author: string | null;
+  ai_action: string | null;
+  restored_from: number | null; // 由恢复操作产生时，被恢复的修订 id
+  created_at: number; // 秒级时间戳
+}
+
+// 获取某条记录的修订历史（最新的在前）
+export async function listAnswerRevisions(
+  appToken: string,
+  tableId: string,
+  recordId: string
+): Promise<Revision[]> {
+  return await invoke("list_answer_revisions", { appToken, tableId, recordId });
+}
+
+// 把记录恢复到某条修订写入之前的内容
+export async function restoreAnswerRevision(
+  appToken: string,
+  tableId: string,
+  revisionId: number,
+  author?: string
+): Promise<Answer> {
+  return await invokeValue<Answer>("restore_answer_revision", {
+    appToken,
+    tableId,
+    revisionId,
+    author,
+  });
+}
+
+// AI 流式输出事件（后端以 ai-stream 事件推送）
+export type AiStreamEvent =
+  | { type: "delta"; request_id: string; text: string }
+  | { type: "done"; request_id: string; result: any }
+  | { type: "error"; request_id: string; error: AppError };
+
+// 生成 AI 请求 ID，用于接收流式输出和取消请求
+export function newAiRequestId(): string {
+  return crypto.randomUUID();
+}
+
+// 传入 onDelta 时以流式输出调用 AI 命令，边生成边回调增量文本；返回值与非流式调用相同。
+// 传入 requestId 时可在请求进行中调用 cancelAiRequest 取消，此时以 kind 为 cancelled 的错误结束
+async function invokeAiStream<T>(
+  command: string,
+  args: Record<string, unknown>,
+  onDelta?: (text: string) => void,
+  requestId?: string
+): Promise<T> {
+  if (!onDelta && !requestId) {
+    return await invoke<T>(command, args);
+  }
+  const id = requestId ?? newAiRequestId();
+  const unlisten = onDelta
+    ? await listen<AiStreamEvent>("ai-stream", (event) => {
+        if (event.payload.request_id === id && event.payload.type === "delta") {
+          onDelta(event.payload.text);
+        }
+      })
+    : undefined;
+  try {
+    return await invoke<T>(command, { ...args, requestId: id });
+  } finally {
+    unlisten?.();
+  }
+}
+
+// 取消进行中的 AI 请求，请求已结束时返回 false
+export async function cancelAiRequest(requestId: string): Promise<boolean> {
+  return await invoke("cancel_ai_request", { requestId });
+}
+
+// AI 提示词模板
+export type PromptKind = "optimize" | "review" | "risk" | "compress";
+
+// 生成结果所用的模板版本（version 为 0 表示内置模板）
+export interface TemplateRef {
+  kind: PromptKind;
+  version: number;
+}
+
+// 一次 AI 调用消耗的 token 数，接口未返回用量时为 0
+export interface TokenUsage {
+  prompt_tokens: number;
+  completion_tokens: number;
+}
+
+// AI 调用的附加参数：用量记录归属的答案和用户，以及是否跳过响应缓存
+export interface AiCallOptions {
+  recordId?: string;
+  user?: string;
+  bypassCache?: boolean; // 为 true 时重新调用 AI，不使用缓存的回复
+}
+
+export interface PromptTemplate extends TemplateRef {
+  body: string;
+  is_default: boolean;
+  author?: string;
+  created_at?: number;
+}
+
+// AI 优化结果，字数按字符计
+export interface AiOptimizationResult {
+  final_reply: string;
+  explanation: string;
+  corrections: string[]; // 纠正原回复的原因
+  original_length: number;
+  reply_length: number;
+  max_length: number; // 原回复字数的 150%
+  attempts: number; // 调用 AI 的次数，大于 1 表示超出字数后自动压缩过
+  structured: boolean;
+  template: TemplateRef;
+  usage: TokenUsage; // 包括压缩在内所有调用的用量
+  cached: boolean; // 来自本地缓存，未调用 AI
+}
+
+// 各 AI 功能当前生效的模板
+export async function listPromptTemplates(): Promise<PromptTemplate[]> {
+  return await invoke("list_prompt_templates");
+}
+
+// 某类模板保存过的所有版本，最新的在前
+export async function getPromptTemplateHistory(kind: PromptKind): Promise<PromptTemplate[]> {
+  return await invoke("get_prompt_template_history", { kind });
+}
+
+// 保存为新版本（后端校验占位符）
+export async function savePromptTemplate(
+  kind: PromptKind,
+  body: string,
+  author?: string
+): Promise<PromptTemplate> {
+  return await invoke("save_prompt_template", { kind, body, author });
+}
+
+// 恢复内置模板
+export async function resetPromptTemplate(kind: PromptKind, author?: string): Promise<PromptTemplate> {
+  return await invoke("reset_prompt_template", { kind, author });
+}
+
 // AI 优化答案
 export async function optimizeAnswerWithAI(
   answer: string,
-  context?: string
-): Promise<string> {
-  return await invoke("optimize_answer_with_ai", { answer, context });
+  context?: string,
+  onDelta?: (text: string) => void,
+  requestId?: string,
+  options?: AiCallOptions
+): Promise<AiOptimizationResult> {
+  return await invokeAiStream("optimize_answer_with_ai", { answer, context, ...options }, onDelta, requestId);
+}
+
+// AI 审核结论
+export type ReviewVerdict = "reasonable" | "mostly_reasonable" | "needs_revision";
+
+// AI 审核结果（后端已解析为结构化字段）
+export interface AiReviewResult {
+  verdict: ReviewVerdict;
+  explanation: string;
+  risks: string[];
+  suggestion?: string | null;
+  revision_reason?: string | null;
+  suggested_reply?: string | null;
+  principles: string[];
+  structured: boolean; // false 表示 AI 未返回 JSON，由文本解析得到
+  raw_text: string;
+  template: TemplateRef;
+  usage: TokenUsage;
+  cached: boolean;
 }
 
 // AI 审核答案
 export async function reviewAnswerWithAI(
   answer: string,
-  context?: string
-): Promise<string> {
-  return await invoke("review_answer_with_ai", { answer, context });
+  context?: string,
+  onDelta?: (text: string) => void,
+  requestId?: string,
+  options?: AiCallOptions
+): Promise<AiReviewResult> {
+  return await invokeAiStream("review_answer_with_ai", { answer, context, ...options }, onDelta, requestId);
 }
 
 // AI 风险检测
 export async function checkAnswerRisk(
-  answer: string
-): Promise<{ hasRisk: boolean; reason: string }> {
+  answer: string,
+  options?: AiCallOptions
+): Promise<{ hasRisk: boolean; reason: string; template?: TemplateRef; usage?: TokenUsage; cached?: boolean }> {
   try {
-    const result = await invoke<{ hasRisk: boolean; reason: string }>("check_answer_risk", { answer });
-    return result;
+    const result = await invoke<{
+      has_risk: boolean;
+      reason: string;
+      template: TemplateRef;
+      usage: TokenUsage;
+      cached: boolean;
+    }>(
+      "check_answer_risk",
+      { answer, ...options }
+    );
+    return {
+      hasRisk: result.has_risk,
+      reason: result.reason,
+      template: result.template,
+      usage: result.usage,
+      cached: result.cached,
+    };
   } catch (error: any) {
     return {
       hasRisk: false,
-      reason: error?.toString() || "检测失败",
+      reason: errorMessage(error) || "检测失败",
     };
   }
 }
 
+// 批量审核任务及进度
+export type BatchStatus = "running" | "paused" | "completed";
+
+export interface BatchJob {
+  id: number;
+  app_token: string;
+  table_id: string;
+  status: BatchStatus;
+  total: number;
+  done: number; // 审核完成的条数
+  failed: number; // 审核出错的条数，继续任务时会重试
+  flagged: number; // 结论为需修改或存在风险的条数
+  created_at: number;
+  updated_at: number;
+}
+
+// 一条答案的批量审核结果
+export interface BatchItemResult {
+  record_id: string;
+  question: string;
+  answer: string;
+  review?: Omit<AiReviewResult, "structured" | "raw_text" | "template" | "usage" | "cached"> | null;
+  risk?: { has_risk: boolean; reason: string } | null;
+  error?: string | null;
+  reviewed_at?: number | null;
+}
+
+// 对表中所有启用的答案执行 AI 审核和风险检测，任务在后台运行
+export async function startBatchReview(
+  appToken: string,
+  tableId: string,
+  user?: string,
+  concurrency?: number
+): Promise<BatchJob> {
+  return await invoke("start_batch_review", { appToken, tableId, user, concurrency });
+}
+
+// 继续暂停或中断的任务，并重试出错的条目
+export async function resumeBatchReview(jobId: number, user?: string, concurrency?: number): Promise<BatchJob> {
+  return await invoke("resume_batch_review", { jobId, user, concurrency });
+}
+
+// 暂停任务：进行中的审核完成后停止
+export async function pauseBatchReview(jobId: number): Promise<boolean> {
+  return await invoke("pause_batch_review", { jobId });
+}
+
+// 某张表的批量审核任务（最新的在前）
+export async function listBatchReviews(appToken: string, tableId: string): Promise<BatchJob[]> {
+  return await invoke("list_batch_reviews", { appToken, tableId });
+}
+
+// 任务的审核结果，默认只返回需修改或存在风险的答案
+export async function getBatchReviewResults(
+  jobId: number,
+  flaggedOnly = true
+): Promise<BatchItemResult[]> {
+  return await invoke("get_batch_review_results", { jobId, flaggedOnly });
+}
+
+// 批量审核进度事件：任务当前进度，任务因错误停止时附带 error
+export interface BatchReviewEvent extends BatchJob {
+  error?: AppError;
+}
+
+// 监听批量审核进度，返回取消监听的函数
+export async function onBatchReviewProgress(
+  callback: (event: BatchReviewEvent) => void
+): Promise<() => void> {
+  return await listen<BatchReviewEvent>("batch-review-progress", (event) => callback(event.payload));
+}
+
 // 创建新记录到飞书
 export async function createAnswerToFeishu(
   appToken: string,
   tableId: string,
   fields: Record<string, any>
-): Promise<string> {
-  return await invoke("create_answer_to_feishu", {
+): Promise<Answer> {
+  return await invokeValue<Answer>("create_answer_to_feishu", {
     appToken,
     tableId,
     fields,
   });
 }
 
+// 删除飞书表格中的记录
+export async function deleteAnswerFromFeishu(
+  appToken: string,
+  tableId: string,
+  recordId: string
+): Promise<string> {
+  return await invokeValue<string>("delete_answer_from_feishu", {
+    appToken,
+    tableId,
+    recordId,
+  });
+}
+
+// 批量操作中单条记录的结果（index 为在请求列表中的位置）
+export interface BatchRecordResult {
+  index: number;
+  record_id: string | null; // 创建失败时为空
+  success: boolean;
+  error?: AppError;
+}
+
+export interface BatchResult {
+  results: BatchRecordResult[];
+  succeeded: number;
+  failed: number;
+  retries: number;
+}
+
+export interface BatchUpdateItem {
+  record_id: string;
+  fields: Record<string, any>;
+}
+
+// 批量创建记录（按接口上限自动分批）
+export async function batchCreateAnswersToFeishu(
+  appToken: string,
+  tableId: string,
+  records: Record<string, any>[]
+): Promise<BatchResult> {
+  return await invoke("batch_create_answers_to_feishu", {
+    appToken,
+    tableId,
+    records,
+  });
+}
+
+// 批量更新记录
+export async function batchUpdateAnswersToFeishu(
+  appToken: string,
+  tableId: string,
+  records: BatchUpdateItem[]
+): Promise<BatchResult> {
+  return await invoke("batch_update_answers_to_feishu", {
+    appToken,
+    tableId,
+    records,
+  });
+}
+
+// 批量删除记录
+export async function batchDeleteAnswersFromFeishu(
+  appToken: string,
+  tableId: string,
+  recordIds: string[]
+): Promise<BatchResult> {
+  return await invoke("batch_delete_answers_from_feishu", {
+    appToken,
+    tableId,
+    recordIds,
+  });
+}
+
 // 获取用户最后同步时间（用于限制普通用户一天只能同步一次）
 export function getLastSyncTimeForUser(userId: string): number | null {
   try {
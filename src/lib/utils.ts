@@ -1,5 +1,6 @@
 import { type ClassValue, clsx } from "clsx"
 import { twMerge } from "tailwind-merge"
+import type { AiOptimizationResult, AiReviewResult } from "./api"
 
 export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs))
@@ -42,6 +43,29 @@ export function extractBitableInfo(url: string): {
   }
 }
 
+/**
+ * 将后端返回的结构化优化结果转换为界面展示用的回复和说明文本
+ */
+export function toOptimizedAnswer(result: AiOptimizationResult): {
+  answerText: string;
+  explanationText?: string;
+  cached?: boolean;
+} {
+  const lines = [
+    result.explanation,
+    ...result.corrections.map((reason) => `纠正原因：${reason}`),
+  ];
+  if (result.attempts > 1) {
+    lines.push(`（超出字数上限，已自动压缩至 ${result.reply_length}/${result.max_length} 字）`);
+  }
+  const explanationText = lines.filter((line) => line.trim()).join("\n");
+  return {
+    answerText: result.final_reply,
+    explanationText: explanationText || undefined,
+    cached: result.cached,
+  };
+}
+
 /**
  * 从 AI 返回的文本中提取优化后的客服回复和说明文本
  * 
@@ -255,6 +279,7 @@ export interface ReviewResult {
   basis?: string; // 修改依据（专家原则）
   rawText: string; // 原始文本，用于显示
   isComplete?: boolean; // 审核结果是否完整（需修改时必须有修改原因和推荐回复）
+  cached?: boolean; // 来自本地缓存，未调用 AI
 }
 
 export function extractReviewResult(reviewText: string): ReviewResult {
@@ -400,6 +425,34 @@ export function extractReviewResult(reviewText: string): ReviewResult {
   return result;
 }
 
+const VERDICT_LABELS: Record<AiReviewResult["verdict"], ReviewResult["conclusion"]> = {
+  reasonable: "合理",
+  mostly_reasonable: "基本合理",
+  needs_revision: "需修改",
+};
+
+/**
+ * 将后端返回的结构化审核结果转换为界面展示用的 ReviewResult
+ */
+export function toReviewResult(review: AiReviewResult): ReviewResult {
+  const conclusion = VERDICT_LABELS[review.verdict] ?? "";
+  const modificationReason = review.revision_reason || undefined;
+  const recommendedReply = review.suggested_reply || undefined;
+  return {
+    conclusion,
+    judgmentExplanation: review.explanation,
+    riskPoints: review.risks.join("\n"),
+    modificationReason,
+    recommendedReply,
+    suggestion: review.suggestion || undefined,
+    basis: review.principles.length > 0 ? review.principles.join("\n") : undefined,
+    rawText: review.raw_text,
+    // 需修改时必须有修改原因和推荐回复
+    isComplete: conclusion !== "需修改" || (!!modificationReason && !!recommendedReply),
+    cached: review.cached,
+  };
+}
+
 /**
  * 解析轻量级专业风险校验结果
  * 
@@ -4,7 +4,9 @@ import Navigation from "../components/Navigation";
 import AccountSettings from "../components/settings/AccountSettings";
 import FeishuSettings from "../components/settings/FeishuSettings";
 import AISettings from "../components/settings/AISettings";
+import PromptSettings from "../components/settings/PromptSettings";
 import TableSettings from "../components/settings/TableSettings";
+import UsageSettings from "../components/settings/UsageSettings";
 import { Tabs, TabsList, TabsTrigger, TabsContent } from "../components/ui/tabs";
 import { User, Cloud, Sparkles, Database } from "lucide-react";
 
@@ -71,8 +73,10 @@ export default function SettingsPage() {
               <TableSettings />
             </TabsContent>
             
-            <TabsContent value="ai" className="mt-6">
+            <TabsContent value="ai" className="mt-6 space-y-6">
               <AISettings />
+              <PromptSettings />
+              <UsageSettings />
             </TabsContent>
           </Tabs>
         </div>